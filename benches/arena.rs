@@ -0,0 +1,34 @@
+use std::{hint::black_box, time::Instant};
+
+use flp_gsp::{
+    Expression,
+    arena::{ArenaExpression, Bump},
+};
+
+const QUERIES: [&str; 4] = [
+    r#"("name" = "Jack")"#,
+    r#"(("age" > "18") & ("sex" ? ["male", "Male"]))"#,
+    r#"((! "age" -) & (("name" * "J?c*") | ("name" ~ "jo\"e")))"#,
+    r#"(("status" ? {allowed}) & (! ("score" < "1.5")))"#,
+];
+const ROUNDS: usize = 100_000;
+
+fn main() {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for query in QUERIES {
+            black_box(query.parse::<Expression>().unwrap());
+        }
+    }
+    println!("default: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let mut bump = Bump::new();
+    for _ in 0..ROUNDS {
+        for query in QUERIES {
+            black_box(ArenaExpression::parse_in(query, &bump).unwrap());
+        }
+        bump.reset();
+    }
+    println!("arena: {:?}", start.elapsed());
+}
@@ -0,0 +1,83 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use flp_gsp::{Expression, Node, interpreter::sqlite::*};
+
+/// Counts allocations, including reallocations, to compare how many each interpretation makes.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const LEAF: &str = r#"(("age" > "18") | ("name" ? ["a", "b"]))"#;
+const DEPTH: usize = 256;
+const ROUNDS: usize = 1_000;
+
+fn main() {
+    let mut expression = LEAF.parse::<Expression>().unwrap();
+    for _ in 0..DEPTH {
+        let negated = Expression {
+            node: Node::Not(Box::new(expression)),
+        };
+        expression = negated.and(LEAF.parse::<Expression>().unwrap());
+    }
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(interpret(&expression, &renames, &types).unwrap());
+    }
+    println!(
+        "interpret: {:?}, {} allocations per tree",
+        start.elapsed(),
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ROUNDS
+    );
+
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        sql.clear();
+        binds.clear();
+        interpret_into(&expression, &renames, &types, &mut sql, &mut binds).unwrap();
+        black_box((&sql, &binds));
+    }
+    println!(
+        "interpret_into: {:?}, {} allocations per tree",
+        start.elapsed(),
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ROUNDS
+    );
+    // Text binds own their strings, so these allocations remain with any writer.
+    let texts = binds
+        .iter()
+        .filter(|bind| matches!(bind, SqliteType::Text(_)))
+        .count();
+    println!("{} nodes, {} text binds", 5 * DEPTH + 3, texts);
+}
@@ -0,0 +1,127 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Type, parse_macro_input};
+
+/// Derives `flp_gsp::interpreter::evaluate::Queryable` for a struct with named fields.
+///
+/// Each field is looked up by its name and stringified with `ToString`. `Option` fields are
+/// missing when `None`. Use `#[gsp(rename = "...")]` to look a field up by another key and
+/// `#[gsp(skip)]` to hide it.
+#[proc_macro_derive(Queryable, attributes(gsp))]
+pub fn derive_queryable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Attributes {
+    rename: Option<String>,
+    skip: bool,
+}
+
+fn attributes(attrs: &[syn::Attribute]) -> syn::Result<Attributes> {
+    let mut attributes = Attributes {
+        rename: None,
+        skip: false,
+    };
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("gsp")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attributes.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                attributes.skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename` or `skip`"))
+            }
+        })?;
+    }
+    Ok(attributes)
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Queryable can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Queryable can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut arms = Vec::new();
+    for field in fields {
+        let attributes = attributes(&field.attrs)?;
+        if attributes.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let key = attributes.rename.unwrap_or_else(|| ident.to_string());
+        let value = if is_option(&field.ty) {
+            quote! {
+                self.#ident
+                    .as_ref()
+                    .map(|value| ::std::borrow::Cow::Owned(::std::string::ToString::to_string(value)))
+            }
+        } else {
+            quote! {
+                ::std::option::Option::Some(::std::borrow::Cow::Owned(
+                    ::std::string::ToString::to_string(&self.#ident),
+                ))
+            }
+        };
+        arms.push(quote! { #key => #value, });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::flp_gsp::interpreter::evaluate::Queryable for #name #ty_generics #where_clause {
+            fn field(&self, key: &str) -> ::std::option::Option<::std::borrow::Cow<'_, str>> {
+                match key {
+                    #(#arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
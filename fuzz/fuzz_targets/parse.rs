@@ -0,0 +1,27 @@
+#![no_main]
+
+use bumpalo::Bump;
+use flp_gsp::{Expression, arena::ArenaExpression, lucene};
+use libfuzzer_sys::fuzz_target;
+
+// Every parser must return `Ok` or `Err` for any input, and whatever parses must render back to
+// the same expression. The arena parser must accept exactly what the default parser does.
+fuzz_target!(|input: &str| {
+    let parsed = input.parse::<Expression>().ok();
+    if let Some(expression) = &parsed {
+        let rendered = expression.to_string();
+        assert_eq!(
+            rendered.parse::<Expression>().ok().as_ref(),
+            Some(expression),
+            "{}",
+            rendered
+        );
+    }
+    let _ = Expression::parse_lenient(input);
+    let bump = Bump::new();
+    let arena = ArenaExpression::parse_in(input, &bump)
+        .ok()
+        .map(Expression::from);
+    assert_eq!(arena, parsed, "{}", input);
+    let _ = lucene::from_lucene(input);
+});
@@ -0,0 +1,97 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Metadata attached to subtrees of an expression, e.g. to tell default filters from user input.
+//!
+//! Annotations are kept next to the expression, keyed by the path of the annotated subtree (see
+//! `Expression::get_at`), so interpreters are unaffected by them. The expression is only changed
+//! through `map` and `replace_at`, which keep the paths in sync with it.
+
+use std::collections::BTreeMap;
+
+use crate::{Error, Expression};
+
+#[derive(Debug)]
+pub struct Annotated<M> {
+    expression: Expression,
+    annotations: BTreeMap<Vec<usize>, M>,
+}
+
+impl<M> Annotated<M> {
+    pub fn new(expression: Expression) -> Self {
+        Self {
+            expression,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Drops the annotations.
+    pub fn into_expression(self) -> Expression {
+        self.expression
+    }
+
+    /// Attaches `meta` to the subtree at `path`, replacing any previous annotation there.
+    pub fn annotate(&mut self, path: &[usize], meta: M) -> Result<(), Error> {
+        self.expression.get_at(path)?;
+        self.annotations.insert(path.to_vec(), meta);
+        Ok(())
+    }
+
+    pub fn meta(&self, path: &[usize]) -> Option<&M> {
+        self.annotations.get(path)
+    }
+
+    /// All annotations with their paths, in path order.
+    pub fn annotations(&self) -> impl Iterator<Item = (&[usize], &M)> {
+        self.annotations
+            .iter()
+            .map(|(path, meta)| (path.as_slice(), meta))
+    }
+
+    /// Transforms the expression, keeping the annotations whose paths still exist afterwards.
+    ///
+    /// Meant for passes that keep the shape of the tree, such as renaming keys.
+    pub fn map(self, f: impl FnOnce(Expression) -> Expression) -> Self {
+        let expression = f(self.expression);
+        let annotations = self
+            .annotations
+            .into_iter()
+            .filter(|(path, _)| expression.get_at(path).is_ok())
+            .collect();
+        Self {
+            expression,
+            annotations,
+        }
+    }
+
+    /// Replaces the subtree at `path`, dropping the annotations inside the replaced subtree.
+    pub fn replace_at(self, path: &[usize], new: Expression) -> Result<Self, Error> {
+        let expression = self.expression.replace_at(path, new)?;
+        let annotations = self
+            .annotations
+            .into_iter()
+            .filter(|(annotated, _)| !annotated.starts_with(path))
+            .collect();
+        Ok(Self {
+            expression,
+            annotations,
+        })
+    }
+}
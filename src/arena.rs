@@ -0,0 +1,458 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parsing into a caller-supplied arena, for services parsing many short queries.
+//!
+//! The grammar is the same as `Expression::from_str`, but the parser is a single pass without
+//! backtracking, and every node and string lives in the arena, so dropping the arena frees the
+//! whole batch at once.
+
+pub use bumpalo::Bump;
+
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+use std::str::CharIndices;
+
+use crate::{CmpOp, Error, Expression, Node, ParseOptions};
+
+#[derive(Debug)]
+pub enum ArenaNode<'b> {
+    And(&'b ArenaExpression<'b>, &'b ArenaExpression<'b>),
+    Or(&'b ArenaExpression<'b>, &'b ArenaExpression<'b>),
+    Not(&'b ArenaExpression<'b>),
+    Equal(&'b str, &'b str),
+    NotEqual(&'b str, &'b str),
+    EqualCI(&'b str, &'b str),
+    Greater(&'b str, &'b str),
+    Less(&'b str, &'b str),
+    GreaterOrEqual(&'b str, &'b str),
+    LessOrEqual(&'b str, &'b str),
+    Wildcard(&'b str, &'b str),
+    Regex(&'b str, &'b str),
+    Fuzzy(&'b str, &'b str),
+    Any(&'b str, &'b [&'b str]),
+    AnyParam(&'b str, &'b str),
+    Intersects(&'b str, &'b [&'b str]),
+    Null(&'b str),
+    FullText(&'b str),
+    Between(&'b str, &'b str, &'b str),
+    GeoWithin(&'b str, f64, f64, f64),
+    Length(&'b str, CmpOp, usize),
+    MatchAll,
+}
+
+#[derive(Debug)]
+pub struct ArenaExpression<'b> {
+    pub node: ArenaNode<'b>,
+}
+
+impl<'b> ArenaExpression<'b> {
+    /// Parses `input` with all nodes allocated in `bump`.
+    pub fn parse_in(input: &str, bump: &'b Bump) -> Result<&'b Self, Error> {
+        Self::parse_in_with(input, &ParseOptions::default(), bump)
+    }
+
+    /// Same as `parse_in`, with the options of `Expression::parse_with`.
+    pub fn parse_in_with(
+        input: &str,
+        options: &ParseOptions,
+        bump: &'b Bump,
+    ) -> Result<&'b Self, Error> {
+        if let Some(max) = options.max_len
+            && input.len() > max
+        {
+            return Err(Error::TooLong {
+                len: input.len(),
+                max,
+            });
+        }
+        let mut cursor = Cursor {
+            input,
+            position: 0,
+            bump,
+            depth: 0,
+            max_depth: options.max_depth,
+            full_text: options.full_text,
+        };
+        if cursor.peek() != Some('"') {
+            return cursor.relation();
+        }
+        // A bare comparison must be the whole input.
+        let comparison = cursor.comparison()?;
+        cursor.space();
+        if !cursor.rest().is_empty() {
+            return Err(cursor.error("expected end of input"));
+        }
+        Ok(comparison)
+    }
+}
+
+impl From<&ArenaExpression<'_>> for Expression {
+    fn from(expression: &ArenaExpression<'_>) -> Self {
+        let boxed = |e: &ArenaExpression<'_>| Box::new(Expression::from(e));
+        let node = match expression.node {
+            ArenaNode::And(left, right) => Node::And(boxed(left), boxed(right)),
+            ArenaNode::Or(left, right) => Node::Or(boxed(left), boxed(right)),
+            ArenaNode::Not(expression) => Node::Not(boxed(expression)),
+            ArenaNode::Equal(key, target) => Node::Equal(key.into(), target.into()),
+            ArenaNode::NotEqual(key, target) => Node::NotEqual(key.into(), target.into()),
+            ArenaNode::EqualCI(key, target) => Node::EqualCI(key.into(), target.into()),
+            ArenaNode::Greater(key, target) => Node::Greater(key.into(), target.into()),
+            ArenaNode::Less(key, target) => Node::Less(key.into(), target.into()),
+            ArenaNode::GreaterOrEqual(key, target) => {
+                Node::GreaterOrEqual(key.into(), target.into())
+            }
+            ArenaNode::LessOrEqual(key, target) => Node::LessOrEqual(key.into(), target.into()),
+            ArenaNode::Wildcard(key, target) => Node::Wildcard(key.into(), target.into()),
+            ArenaNode::Regex(key, target) => Node::Regex(key.into(), target.into()),
+            ArenaNode::Fuzzy(key, target) => Node::Fuzzy(key.into(), target.into()),
+            ArenaNode::Any(key, targets) => Node::Any(
+                key.into(),
+                targets.iter().map(|target| target.to_string()).collect(),
+            ),
+            ArenaNode::AnyParam(key, param) => Node::AnyParam(key.into(), param.into()),
+            ArenaNode::Intersects(key, targets) => Node::Intersects(
+                key.into(),
+                targets.iter().map(|target| target.to_string()).collect(),
+            ),
+            ArenaNode::Null(key) => Node::Null(key.into()),
+            ArenaNode::FullText(term) => Node::FullText(term.into()),
+            ArenaNode::Between(key, low, high) => {
+                Node::Between(key.into(), low.into(), high.into())
+            }
+            ArenaNode::GeoWithin(key, lat, lon, radius) => {
+                Node::GeoWithin(key.into(), lat, lon, radius)
+            }
+            ArenaNode::Length(key, op, length) => Node::Length(key.into(), op, length),
+            ArenaNode::MatchAll => Node::MatchAll,
+        };
+        Self { node }
+    }
+}
+
+struct Cursor<'i, 'b> {
+    input: &'i str,
+    position: usize,
+    bump: &'b Bump,
+    depth: usize,
+    max_depth: usize,
+    full_text: bool,
+}
+
+impl<'i, 'b> Cursor<'i, 'b> {
+    fn rest(&self) -> &'i str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn space(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::parser_at(self.input, self.position, message)
+    }
+
+    fn alloc(&self, node: ArenaNode<'b>) -> &'b ArenaExpression<'b> {
+        self.bump.alloc(ArenaExpression { node })
+    }
+
+    /// A relation nested at most `max_depth` deep, like the default parser.
+    fn relation(&mut self) -> Result<&'b ArenaExpression<'b>, Error> {
+        if self.depth == self.max_depth {
+            return Err(self.error("max depth exceeded"));
+        }
+        self.depth += 1;
+        let relation = self.group();
+        self.depth -= 1;
+        relation
+    }
+
+    /// `( )`, `( inner )` or `! relation`.
+    fn group(&mut self) -> Result<&'b ArenaExpression<'b>, Error> {
+        if self.eat('!') {
+            self.space();
+            let relation = self.relation()?;
+            return Ok(self.alloc(ArenaNode::Not(relation)));
+        }
+        self.expect('(')?;
+        self.space();
+        if self.eat(')') {
+            return Ok(self.alloc(ArenaNode::MatchAll));
+        }
+        let left = match self.peek() {
+            Some('"') => self.comparison()?,
+            Some('!') => {
+                self.eat('!');
+                self.space();
+                // A negated comparison cannot be the left side of `&` or `|`.
+                if self.peek() == Some('"') {
+                    let comparison = self.comparison()?;
+                    self.space();
+                    self.expect(')')?;
+                    return Ok(self.alloc(ArenaNode::Not(comparison)));
+                }
+                let relation = self.relation()?;
+                self.alloc(ArenaNode::Not(relation))
+            }
+            _ => {
+                let relation = self.relation()?;
+                self.space();
+                // A relation needs an operator, `(("a" = "b"))` is not a group.
+                if !matches!(self.peek(), Some('&' | '|')) {
+                    return Err(self.error("expected '&' or '|'"));
+                }
+                relation
+            }
+        };
+        self.space();
+        let expression = if self.eat('&') {
+            self.space();
+            let right = self.operand()?;
+            self.alloc(ArenaNode::And(left, right))
+        } else if self.eat('|') {
+            self.space();
+            let right = self.operand()?;
+            self.alloc(ArenaNode::Or(left, right))
+        } else {
+            left
+        };
+        self.space();
+        self.expect(')')?;
+        Ok(expression)
+    }
+
+    fn operand(&mut self) -> Result<&'b ArenaExpression<'b>, Error> {
+        if self.peek() == Some('"') {
+            self.comparison()
+        } else {
+            self.relation()
+        }
+    }
+
+    fn comparison(&mut self) -> Result<&'b ArenaExpression<'b>, Error> {
+        let key = self.text()?;
+        let before = self.position;
+        self.space();
+        let node = match self.peek() {
+            Some(c @ ('>' | '<' | '!')) if self.rest()[1..].starts_with('=') => {
+                self.eat(c);
+                self.eat('=');
+                self.space();
+                let target = self.text()?;
+                match c {
+                    '>' => ArenaNode::GreaterOrEqual(key, target),
+                    '<' => ArenaNode::LessOrEqual(key, target),
+                    _ => ArenaNode::NotEqual(key, target),
+                }
+            }
+            Some(c @ ('=' | '~' | '>' | '<' | '*' | '$' | '%')) => {
+                self.eat(c);
+                self.space();
+                let target = self.text()?;
+                match c {
+                    '=' => ArenaNode::Equal(key, target),
+                    '~' => ArenaNode::EqualCI(key, target),
+                    '>' => ArenaNode::Greater(key, target),
+                    '<' => ArenaNode::Less(key, target),
+                    '*' => ArenaNode::Wildcard(key, target),
+                    '$' if cfg!(any(feature = "regex", feature = "fancy-regex")) => {
+                        ArenaNode::Regex(key, target)
+                    }
+                    '$' => return Err(Error::FeatureDisabled("regex")),
+                    _ => ArenaNode::Fuzzy(key, target),
+                }
+            }
+            Some('?') => {
+                self.eat('?');
+                self.space();
+                match self.peek() {
+                    Some('{') => ArenaNode::AnyParam(key, self.param()?),
+                    _ => ArenaNode::Any(key, self.array()?),
+                }
+            }
+            Some('^') => {
+                self.eat('^');
+                self.space();
+                ArenaNode::Intersects(key, self.array()?)
+            }
+            Some('-') => {
+                self.eat('-');
+                ArenaNode::Null(key)
+            }
+            Some('@') => {
+                self.eat('@');
+                self.space();
+                let at = self.position;
+                if self.peek() == Some('[') {
+                    match self.array()? {
+                        [low, high] => ArenaNode::Between(key, low, high),
+                        _ => return Err(Error::parser_at(self.input, at, "expected two bounds")),
+                    }
+                } else {
+                    let (lat, lon, radius) = crate::geo::parse_circle(self.text()?)
+                        .ok_or_else(|| Error::parser_at(self.input, at, "invalid circle"))?;
+                    ArenaNode::GeoWithin(key, lat, lon, radius)
+                }
+            }
+            Some('#') => {
+                self.eat('#');
+                self.space();
+                let op = if self.eat('>') {
+                    if self.eat('=') {
+                        CmpOp::GreaterOrEqual
+                    } else {
+                        CmpOp::Greater
+                    }
+                } else if self.eat('<') {
+                    if self.eat('=') {
+                        CmpOp::LessOrEqual
+                    } else {
+                        CmpOp::Less
+                    }
+                } else {
+                    self.expect('=')?;
+                    CmpOp::Equal
+                };
+                self.space();
+                let at = self.position;
+                let length = self.text()?;
+                if length.is_empty() || !length.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(Error::parser_at(self.input, at, "invalid length"));
+                }
+                let length = length
+                    .parse()
+                    .map_err(|_| Error::parser_at(self.input, at, "invalid length"))?;
+                ArenaNode::Length(key, op, length)
+            }
+            _ if self.full_text => {
+                self.position = before;
+                ArenaNode::FullText(key)
+            }
+            _ => return Err(self.error("expected an operator")),
+        };
+        Ok(self.alloc(node))
+    }
+
+    fn text(&mut self) -> Result<&'b str, Error> {
+        self.expect('"')?;
+        let mut text = BumpString::new_in(self.bump);
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(text.into_bump_str());
+                }
+                '\\' => match chars.next().and_then(|(_, c)| unescape(c, &mut chars)) {
+                    Some(escaped) => text.push(escaped),
+                    None => {
+                        self.position += i;
+                        return Err(self.error("invalid escape"));
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+        self.position = self.input.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn array(&mut self) -> Result<&'b [&'b str], Error> {
+        self.expect('[')?;
+        self.space();
+        let mut texts = BumpVec::new_in(self.bump);
+        if !self.eat(']') {
+            loop {
+                texts.push(self.text()?);
+                self.space();
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',')?;
+                self.space();
+            }
+        }
+        Ok(texts.into_bump_slice())
+    }
+
+    fn param(&mut self) -> Result<&'b str, Error> {
+        self.expect('{')?;
+        let rest = self.rest();
+        let name = rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+        let name = &rest[..rest.len() - name.len()];
+        if name.is_empty() {
+            return Err(self.error("expected param name"));
+        }
+        self.position += name.len();
+        self.expect('}')?;
+        Ok(self.bump.alloc_str(name))
+    }
+}
+
+/// The character escaped by `\` and `c`, consuming the digits of a unicode escape from `chars`.
+fn unescape(c: char, chars: &mut CharIndices<'_>) -> Option<char> {
+    match c {
+        '\\' | '"' => Some(c),
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        'u' => {
+            let braced = chars.as_str().starts_with('{');
+            if braced {
+                chars.next();
+            }
+            let digits = chars.as_str();
+            let len = digits
+                .chars()
+                .take(if braced { 6 } else { 4 })
+                .take_while(char::is_ascii_hexdigit)
+                .count();
+            if len == 0 || (!braced && len != 4) {
+                return None;
+            }
+            let code = u32::from_str_radix(&digits[..len], 16).ok()?;
+            for _ in 0..len {
+                chars.next();
+            }
+            if braced && chars.next()?.1 != '}' {
+                return None;
+            }
+            char::from_u32(code)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,437 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{CmpOp, Expression, Node, wildcard};
+
+/// Templates used to describe each kind of node in words.
+///
+/// The placeholders each phrase receives are:
+///
+/// * `and` and `or`: `{left}` and `{right}`, the descriptions of both sides.
+/// * `not`: `{value}`, the description of the negated expression.
+/// * `equal` to `fuzzy`: `{key}` and `{value}`, the target.
+/// * `any` and `intersects`: `{key}` and `{value}`, the targets joined with `list_separator`.
+/// * `any_param`: `{key}` and `{value}`, the name of the param.
+/// * `null`: `{key}`.
+/// * `full_text`: `{value}`, the term.
+/// * `between`: `{key}`, `{low}` and `{high}`.
+/// * `within`: `{key}`, `{lat}` and `{lon}` of the center, and `{radius}` in meters.
+/// * `length`: `{key}`, `{op}`, one of the `length_*` words, and `{value}`, the length.
+/// * `match_all`: none.
+#[derive(Clone, Debug)]
+pub struct Phrases {
+    pub and: String,
+    pub or: String,
+    pub not: String,
+    pub equal: String,
+    pub not_equal: String,
+    pub equal_ci: String,
+    pub greater: String,
+    pub less: String,
+    pub greater_or_equal: String,
+    pub less_or_equal: String,
+    pub wildcard: String,
+    pub regex: String,
+    pub fuzzy: String,
+    pub any: String,
+    pub any_param: String,
+    pub intersects: String,
+    pub null: String,
+    pub full_text: String,
+    pub between: String,
+    pub within: String,
+    pub length: String,
+    pub match_all: String,
+    /// Words for the comparisons of `length`, e.g. `more than` for `>`.
+    pub length_equal: String,
+    pub length_greater: String,
+    pub length_less: String,
+    pub length_greater_or_equal: String,
+    pub length_less_or_equal: String,
+    pub list_separator: String,
+}
+impl Default for Phrases {
+    fn default() -> Self {
+        Self {
+            and: "({left} and {right})".into(),
+            or: "({left} or {right})".into(),
+            not: "not {value}".into(),
+            equal: "{key} is \"{value}\"".into(),
+            not_equal: "{key} is not \"{value}\"".into(),
+            equal_ci: "{key} is \"{value}\" ignoring case".into(),
+            greater: "{key} is greater than \"{value}\"".into(),
+            less: "{key} is less than \"{value}\"".into(),
+            greater_or_equal: "{key} is at least \"{value}\"".into(),
+            less_or_equal: "{key} is at most \"{value}\"".into(),
+            wildcard: "{key} matches \"{value}\"".into(),
+            regex: "{key} matches the regex \"{value}\"".into(),
+            fuzzy: "{key} is similar to \"{value}\"".into(),
+            any: "{key} is one of {value}".into(),
+            any_param: "{key} is one of the list {value}".into(),
+            intersects: "{key} has any of {value}".into(),
+            null: "{key} is empty".into(),
+            full_text: "contains \"{value}\"".into(),
+            between: "{key} is between \"{low}\" and \"{high}\"".into(),
+            within: "{key} is within {radius} meters of ({lat}, {lon})".into(),
+            length: "{key} has {op} {value} characters".into(),
+            match_all: "anything".into(),
+            length_equal: "exactly".into(),
+            length_greater: "more than".into(),
+            length_less: "fewer than".into(),
+            length_greater_or_equal: "at least".into(),
+            length_less_or_equal: "at most".into(),
+            list_separator: ", ".into(),
+        }
+    }
+}
+
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut buffer = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        buffer.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let replaced = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+        match replaced {
+            Some((end, value)) => {
+                buffer.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                buffer.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    buffer.push_str(rest);
+    buffer
+}
+
+impl Expression {
+    pub fn describe(&self, phrases: &Phrases) -> String {
+        match &self.node {
+            Node::And(left, right) => fill(
+                &phrases.and,
+                &[
+                    ("left", &left.describe(phrases)),
+                    ("right", &right.describe(phrases)),
+                ],
+            ),
+            Node::Or(left, right) => fill(
+                &phrases.or,
+                &[
+                    ("left", &left.describe(phrases)),
+                    ("right", &right.describe(phrases)),
+                ],
+            ),
+            Node::Not(expr) => fill(&phrases.not, &[("value", &expr.describe(phrases))]),
+            Node::Equal(key, target) => fill(&phrases.equal, &[("key", key), ("value", target)]),
+            Node::NotEqual(key, target) => {
+                fill(&phrases.not_equal, &[("key", key), ("value", target)])
+            }
+            Node::EqualCI(key, target) => {
+                fill(&phrases.equal_ci, &[("key", key), ("value", target)])
+            }
+            Node::Greater(key, target) => {
+                fill(&phrases.greater, &[("key", key), ("value", target)])
+            }
+            Node::Less(key, target) => fill(&phrases.less, &[("key", key), ("value", target)]),
+            Node::GreaterOrEqual(key, target) => fill(
+                &phrases.greater_or_equal,
+                &[("key", key), ("value", target)],
+            ),
+            Node::LessOrEqual(key, target) => {
+                fill(&phrases.less_or_equal, &[("key", key), ("value", target)])
+            }
+            Node::Wildcard(key, target) => {
+                fill(&phrases.wildcard, &[("key", key), ("value", target)])
+            }
+            Node::Regex(key, target) => fill(&phrases.regex, &[("key", key), ("value", target)]),
+            Node::Fuzzy(key, target) => fill(&phrases.fuzzy, &[("key", key), ("value", target)]),
+            Node::Any(key, targets) => fill(
+                &phrases.any,
+                &[
+                    ("key", key),
+                    ("value", &targets.join(&phrases.list_separator)),
+                ],
+            ),
+            Node::AnyParam(key, param) => {
+                fill(&phrases.any_param, &[("key", key), ("value", param)])
+            }
+            Node::Intersects(key, targets) => fill(
+                &phrases.intersects,
+                &[
+                    ("key", key),
+                    ("value", &targets.join(&phrases.list_separator)),
+                ],
+            ),
+            Node::Null(key) => fill(&phrases.null, &[("key", key)]),
+            Node::FullText(term) => fill(&phrases.full_text, &[("value", term)]),
+            Node::Between(key, low, high) => fill(
+                &phrases.between,
+                &[("key", key), ("low", low), ("high", high)],
+            ),
+            Node::GeoWithin(key, lat, lon, radius) => fill(
+                &phrases.within,
+                &[
+                    ("key", key),
+                    ("lat", &lat.to_string()),
+                    ("lon", &lon.to_string()),
+                    ("radius", &radius.to_string()),
+                ],
+            ),
+            Node::Length(key, op, length) => fill(
+                &phrases.length,
+                &[
+                    ("key", key),
+                    (
+                        "op",
+                        match op {
+                            CmpOp::Equal => &phrases.length_equal,
+                            CmpOp::Greater => &phrases.length_greater,
+                            CmpOp::Less => &phrases.length_less,
+                            CmpOp::GreaterOrEqual => &phrases.length_greater_or_equal,
+                            CmpOp::LessOrEqual => &phrases.length_less_or_equal,
+                        },
+                    ),
+                    ("value", &length.to_string()),
+                ],
+            ),
+            Node::MatchAll => phrases.match_all.clone(),
+        }
+    }
+}
+
+/// Quotes a key with double quotes unless it is a plain identifier.
+fn sql_like_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let plain = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+    if plain {
+        key.to_string()
+    } else {
+        format!("\"{}\"", key.replace('"', "\"\""))
+    }
+}
+
+fn sql_like_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn sql_like_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| sql_like_literal(value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Expression {
+    /// Renders the expression in an SQL-like syntax for reading, e.g.
+    /// `name = 'Bob' AND (age > '30' OR sex IN ('male', 'other'))`.
+    ///
+    /// Values are inlined as quoted literals, so the result is for display only; use an
+    /// interpreter to query a database. `And`s and `Or`s are only parenthesized when nested in the
+    /// other one or in a `NOT`.
+    pub fn to_sql_like_string(&self) -> String {
+        let operand = |expression: &Expression, relation: fn(&Node) -> bool| {
+            let rendered = expression.to_sql_like_string();
+            if relation(&expression.node) {
+                format!("({})", rendered)
+            } else {
+                rendered
+            }
+        };
+        match &self.node {
+            Node::And(left, right) => {
+                let or = |node: &Node| matches!(node, Node::Or(..));
+                format!("{} AND {}", operand(left, or), operand(right, or))
+            }
+            Node::Or(left, right) => {
+                let and = |node: &Node| matches!(node, Node::And(..));
+                format!("{} OR {}", operand(left, and), operand(right, and))
+            }
+            Node::Not(expr) => match &expr.node {
+                Node::Null(key) => format!("{} IS NOT NULL", sql_like_key(key)),
+                _ => format!(
+                    "NOT {}",
+                    operand(expr, |node| matches!(node, Node::And(..) | Node::Or(..)))
+                ),
+            },
+            Node::Equal(key, target) => {
+                format!("{} = {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::NotEqual(key, target) => {
+                format!("{} <> {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::EqualCI(key, target) => {
+                format!("{} ILIKE {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::Greater(key, target) => {
+                format!("{} > {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::Less(key, target) => {
+                format!("{} < {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::GreaterOrEqual(key, target) => {
+                format!("{} >= {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::LessOrEqual(key, target) => {
+                format!("{} <= {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::Wildcard(key, target) => format!(
+                "{} LIKE {}",
+                sql_like_key(key),
+                sql_like_literal(&wildcard::to_like(target))
+            ),
+            Node::Regex(key, target) => {
+                format!("{} REGEXP {}", sql_like_key(key), sql_like_literal(target))
+            }
+            Node::Fuzzy(key, target) => format!(
+                "{} SOUNDS LIKE {}",
+                sql_like_key(key),
+                sql_like_literal(target)
+            ),
+            Node::Any(key, targets) => {
+                format!("{} IN ({})", sql_like_key(key), sql_like_list(targets))
+            }
+            Node::AnyParam(key, param) => format!("{} IN :{}", sql_like_key(key), param),
+            Node::Intersects(key, targets) => format!(
+                "{} INTERSECTS ({})",
+                sql_like_key(key),
+                sql_like_list(targets)
+            ),
+            Node::Null(key) => format!("{} IS NULL", sql_like_key(key)),
+            Node::FullText(term) => format!("MATCH({})", sql_like_literal(term)),
+            Node::Between(key, low, high) => format!(
+                "{} BETWEEN {} AND {}",
+                sql_like_key(key),
+                sql_like_literal(low),
+                sql_like_literal(high)
+            ),
+            Node::GeoWithin(key, lat, lon, radius) => format!(
+                "{} WITHIN {} m OF ({}, {})",
+                sql_like_key(key),
+                radius,
+                lat,
+                lon
+            ),
+            Node::Length(key, op, length) => {
+                format!("LENGTH({}) {} {}", sql_like_key(key), op.symbol(), length)
+            }
+            Node::MatchAll => "TRUE".to_string(),
+        }
+    }
+}
+
+/// A list of Rust string literals, typed even when empty.
+fn rust_strings(values: &[String]) -> String {
+    if values.is_empty() {
+        return "Vec::<String>::new()".to_string();
+    }
+    let values = values
+        .iter()
+        .map(|value| format!("{:?}", value))
+        .collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+fn rust_f64(value: f64) -> String {
+    if value.is_nan() {
+        "f64::NAN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "f64::INFINITY"
+        } else {
+            "f64::NEG_INFINITY"
+        }
+        .to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+impl Expression {
+    /// Renders Rust source that builds this expression with the builder API, e.g.
+    /// `Expression::equal("name", "Bob").and(Expression::greater("age", "30"))`, for generating
+    /// test fixtures from a working query.
+    ///
+    /// The code expects `Expression` and `CmpOp` of this crate to be in scope.
+    pub fn to_builder_code(&self) -> String {
+        let pair = |builder: &str, key: &str, target: &str| {
+            format!("Expression::{}({:?}, {:?})", builder, key, target)
+        };
+        match &self.node {
+            Node::And(left, right) => {
+                format!(
+                    "{}.and({})",
+                    left.to_builder_code(),
+                    right.to_builder_code()
+                )
+            }
+            Node::Or(left, right) => {
+                format!("{}.or({})", left.to_builder_code(), right.to_builder_code())
+            }
+            Node::Not(expr) => format!("{}.not()", expr.to_builder_code()),
+            Node::Equal(key, target) => pair("equal", key, target),
+            Node::NotEqual(key, target) => pair("not_equal", key, target),
+            Node::EqualCI(key, target) => pair("equal_ci", key, target),
+            Node::Greater(key, target) => pair("greater", key, target),
+            Node::Less(key, target) => pair("less", key, target),
+            Node::GreaterOrEqual(key, target) => pair("greater_or_equal", key, target),
+            Node::LessOrEqual(key, target) => pair("less_or_equal", key, target),
+            Node::Wildcard(key, target) => pair("wildcard", key, target),
+            Node::Regex(key, target) => pair("regex", key, target),
+            Node::Fuzzy(key, target) => pair("fuzzy", key, target),
+            Node::AnyParam(key, param) => pair("any_param", key, param),
+            Node::Any(key, targets) => {
+                format!(
+                    "Expression::any_empty_ok({:?}, {})",
+                    key,
+                    rust_strings(targets)
+                )
+            }
+            Node::Intersects(key, targets) => {
+                format!(
+                    "Expression::intersects({:?}, {})",
+                    key,
+                    rust_strings(targets)
+                )
+            }
+            Node::Null(key) => format!("Expression::null({:?})", key),
+            Node::FullText(term) => format!("Expression::full_text({:?})", term),
+            Node::Between(key, low, high) => {
+                format!("Expression::between({:?}, {:?}, {:?})", key, low, high)
+            }
+            Node::GeoWithin(key, lat, lon, radius) => format!(
+                "Expression::geo_within({:?}, {}, {}, {})",
+                key,
+                rust_f64(*lat),
+                rust_f64(*lon),
+                rust_f64(*radius)
+            ),
+            Node::Length(key, op, length) => {
+                format!("Expression::length({:?}, CmpOp::{:?}, {})", key, op, length)
+            }
+            Node::MatchAll => "Expression::match_all()".to_string(),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Differences between two expressions at the level of their conditions.
+//!
+//! Both expressions are flattened into their comparisons, ignoring how they are combined, so
+//! changing `&` into `|` is not reported. Conditions are matched by key, operator and whether they
+//! are negated; a matched condition with another value is reported as changed.
+
+use crate::{Expression, Node, schema::operator};
+
+/// A comparison of an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    /// Empty for full-text terms.
+    pub key: String,
+    pub operator: &'static str,
+    /// Whether the comparison is under an odd number of `!`.
+    pub negated: bool,
+    /// The target, `["a", "b"]` for lists, `{param}` for params, `lat,lon,radius` for circles and
+    /// empty for `-`.
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Condition),
+    Removed(Condition),
+    ValueChanged { old: Condition, new: Condition },
+}
+
+impl Condition {
+    fn same_kind(&self, other: &Self) -> bool {
+        self.key == other.key && self.operator == other.operator && self.negated == other.negated
+    }
+}
+
+fn conditions(expression: &Expression, negated: bool, conditions: &mut Vec<Condition>) {
+    let (key, value) = match &expression.node {
+        Node::And(left, right) | Node::Or(left, right) => {
+            self::conditions(left, negated, conditions);
+            self::conditions(right, negated, conditions);
+            return;
+        }
+        Node::Not(expression) => return self::conditions(expression, !negated, conditions),
+        // `()` is not a comparison.
+        Node::MatchAll => return,
+        Node::Equal(key, target)
+        | Node::NotEqual(key, target)
+        | Node::EqualCI(key, target)
+        | Node::Greater(key, target)
+        | Node::Less(key, target)
+        | Node::GreaterOrEqual(key, target)
+        | Node::LessOrEqual(key, target)
+        | Node::Wildcard(key, target)
+        | Node::Regex(key, target)
+        | Node::Fuzzy(key, target) => (key.clone(), target.clone()),
+        Node::Any(key, targets) | Node::Intersects(key, targets) => (
+            key.clone(),
+            format!(
+                "[{}]",
+                targets
+                    .iter()
+                    .map(|target| format!("{:?}", target))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        Node::AnyParam(key, param) => (key.clone(), format!("{{{}}}", param)),
+        Node::Null(key) => (key.clone(), String::new()),
+        Node::Between(key, low, high) => (key.clone(), format!("[{:?}, {:?}]", low, high)),
+        Node::FullText(term) => (String::new(), term.clone()),
+        Node::GeoWithin(key, lat, lon, radius) => {
+            (key.clone(), format!("{},{},{}", lat, lon, radius))
+        }
+        Node::Length(key, _, length) => (key.clone(), length.to_string()),
+    };
+    conditions.push(Condition {
+        key,
+        operator: operator(&expression.node),
+        negated,
+        value,
+    });
+}
+
+impl Expression {
+    /// Flattens the expression into its comparisons, from left to right.
+    pub fn conditions(&self) -> Vec<Condition> {
+        let mut flattened = Vec::new();
+        conditions(self, false, &mut flattened);
+        flattened
+    }
+
+    /// Reports the conditions removed from `self`, added in `other` or whose value changed.
+    ///
+    /// Identical conditions are matched first. Remaining conditions of the same key, operator and
+    /// negation are paired in order as changed values. Changes and removals come in the order of
+    /// `self`, followed by additions in the order of `other`.
+    pub fn diff(&self, other: &Expression) -> Vec<Change> {
+        let mut old = self.conditions();
+        let mut new = other.conditions().into_iter().map(Some).collect::<Vec<_>>();
+        old.retain(|condition| {
+            match new
+                .iter_mut()
+                .find(|candidate| candidate.as_ref() == Some(condition))
+            {
+                Some(candidate) => {
+                    *candidate = None;
+                    false
+                }
+                None => true,
+            }
+        });
+        let mut changes = Vec::new();
+        for condition in old {
+            match new
+                .iter_mut()
+                .find(|candidate| candidate.as_ref().is_some_and(|c| c.same_kind(&condition)))
+                .and_then(Option::take)
+            {
+                Some(changed) => changes.push(Change::ValueChanged {
+                    old: condition,
+                    new: changed,
+                }),
+                None => changes.push(Change::Removed(condition)),
+            }
+        }
+        changes.extend(new.into_iter().flatten().map(Change::Added));
+        changes
+    }
+}
@@ -0,0 +1,62 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Coordinates and distances for the within operator (`@`).
+//!
+//! A point is written as `"lat,lon"` in degrees and a circle as `"lat,lon,radius"`, where the
+//! radius is in meters unless suffixed with `m`, `km` or `mi`, e.g.
+//! `("location" @ "37.7,-122.4,5km")`.
+
+/// Mean radius of the earth in meters.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Approximate length of one degree of latitude in meters.
+pub const METERS_PER_DEGREE: f64 = EARTH_RADIUS * std::f64::consts::PI / 180.0;
+
+/// Parses `"lat,lon"`. Returns `None` if either is not a number or out of range.
+pub fn parse_point(s: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = s.split_once(',')?;
+    let lat = lat.trim().parse::<f64>().ok()?;
+    let lon = lon.trim().parse::<f64>().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Parses `"lat,lon,radius"` into the center and the radius in meters.
+pub fn parse_circle(s: &str) -> Option<(f64, f64, f64)> {
+    let (point, radius) = s.rsplit_once(',')?;
+    let (lat, lon) = parse_point(point)?;
+    let radius = radius.trim();
+    let (number, factor) = if let Some(number) = radius.strip_suffix("km") {
+        (number, 1000.0)
+    } else if let Some(number) = radius.strip_suffix("mi") {
+        (number, 1609.344)
+    } else if let Some(number) = radius.strip_suffix('m') {
+        (number, 1.0)
+    } else {
+        (radius, 1.0)
+    };
+    let radius = number.trim_end().parse::<f64>().ok()? * factor;
+    (radius.is_finite() && radius >= 0.0).then_some((lat, lon, radius))
+}
+
+/// Great-circle distance in meters between two points, using the haversine formula.
+pub fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
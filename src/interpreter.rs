@@ -14,17 +14,47 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(feature = "bpf")]
+pub mod bpf;
+
+#[cfg(feature = "cypher")]
+pub mod cypher;
+
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+
 #[cfg(feature = "evaluate")]
 pub mod evaluate;
 
+#[cfg(feature = "evaluate-async")]
+pub mod evaluate_async;
+
+#[cfg(feature = "mongo")]
+pub mod mongo;
+
 #[cfg(feature = "mysql")]
 pub mod mysql;
 
 #[cfg(feature = "postgres")]
 pub mod postgres;
 
+#[cfg(feature = "postgrest")]
+pub mod postgrest;
+
+#[cfg(feature = "sea-query")]
+pub mod sea_query;
+
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
 #[cfg(feature = "hasura")]
 pub mod hasura;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "log")]
+pub mod log;
+
+#[cfg(feature = "lua")]
+pub mod lua;
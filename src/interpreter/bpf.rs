@@ -0,0 +1,217 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, num::ParseIntError};
+
+use crate::{Expression, Node};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot find key {0} in fields")]
+    UnknownKey(String),
+    #[error("Invalid value {0}")]
+    InvalidValue(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The packet field a key is matched against.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BpfField {
+    SrcHost,
+    DstHost,
+    /// Either the source or the destination host.
+    Host,
+    /// A network in CIDR notation, e.g. `10.0.0.0/8`.
+    SrcNet,
+    DstNet,
+    Net,
+    SrcPort,
+    DstPort,
+    Port,
+    /// A protocol keyword, e.g. `tcp` or `udp`.
+    Protocol,
+    /// The length of the packet in bytes.
+    Length,
+}
+impl BpfField {
+    /// The primitive comparing the field to a single value, if any.
+    fn primitive(&self) -> Option<&'static str> {
+        match self {
+            BpfField::SrcHost => Some("src host"),
+            BpfField::DstHost => Some("dst host"),
+            BpfField::Host => Some("host"),
+            BpfField::SrcNet => Some("src net"),
+            BpfField::DstNet => Some("dst net"),
+            BpfField::Net => Some("net"),
+            BpfField::SrcPort => Some("src port"),
+            BpfField::DstPort => Some("dst port"),
+            BpfField::Port => Some("port"),
+            BpfField::Protocol | BpfField::Length => None,
+        }
+    }
+
+    /// The primitive comparing the field to a range of values, if any.
+    fn range_primitive(&self) -> Option<&'static str> {
+        match self {
+            BpfField::SrcPort => Some("src portrange"),
+            BpfField::DstPort => Some("dst portrange"),
+            BpfField::Port => Some("portrange"),
+            _ => None,
+        }
+    }
+}
+
+pub type BpfFields = HashMap<String, BpfField>;
+
+const PROTOCOLS: &[&str] = &[
+    "arp", "icmp", "icmp6", "igmp", "ip", "ip6", "rarp", "sctp", "tcp", "udp",
+];
+
+fn field<'a>(fields: &'a BpfFields, key: &str) -> Result<&'a BpfField> {
+    fields.get(key).ok_or(Error::UnknownKey(key.to_string()))
+}
+
+/// Renders `field = target`, checking the target so that it cannot change the filter.
+fn primitive(field: &BpfField, target: &str) -> Result<String> {
+    match field {
+        BpfField::Protocol => {
+            if !PROTOCOLS.contains(&target) {
+                return Err(Error::InvalidValue(target.to_string()));
+            }
+            Ok(target.to_string())
+        }
+        BpfField::Length => Ok(format!("len = {}", target.parse::<u32>()?)),
+        BpfField::SrcPort | BpfField::DstPort | BpfField::Port => {
+            let port = target.parse::<u16>()?;
+            Ok(format!(
+                "{} {}",
+                field.primitive().unwrap_or_default(),
+                port
+            ))
+        }
+        _ => {
+            let valid = !target.is_empty()
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '-' | '_' | '/'));
+            if !valid {
+                return Err(Error::InvalidValue(target.to_string()));
+            }
+            Ok(format!(
+                "{} {}",
+                field.primitive().unwrap_or_default(),
+                target
+            ))
+        }
+    }
+}
+
+/// Renders an inclusive range of ports or lengths.
+fn range(field: &BpfField, key: &str, low: u32, high: u32) -> Result<String> {
+    if let Some(primitive) = field.range_primitive() {
+        if low > high || high > u16::MAX as u32 {
+            return Err(Error::InvalidValue(format!("{}-{}", low, high)));
+        }
+        Ok(format!("{} {}-{}", primitive, low, high))
+    } else if *field == BpfField::Length {
+        Ok(format!("(len >= {} and len <= {})", low, high))
+    } else {
+        Err(Error::UnsupportedNode(format!("range on {}", key)))
+    }
+}
+
+fn ordering(fields: &BpfFields, key: &str, operator: &str, target: &str) -> Result<String> {
+    let field = field(fields, key)?;
+    if *field == BpfField::Length {
+        return Ok(format!("len {} {}", operator, target.parse::<u32>()?));
+    }
+    let target = target.parse::<u32>()?;
+    let invalid = || Error::InvalidValue(format!("{} {}", operator, target));
+    let (low, high) = match operator {
+        ">" => (target.checked_add(1).ok_or_else(invalid)?, u16::MAX as u32),
+        ">=" => (target, u16::MAX as u32),
+        "<" => (0, target.checked_sub(1).ok_or_else(invalid)?),
+        _ => (0, target),
+    };
+    range(field, key, low, high)
+}
+
+pub fn interpret_expression(expression: &Expression, fields: &BpfFields) -> Result<String> {
+    Ok(match &expression.node {
+        Node::And(left, right) => format!(
+            "({} and {})",
+            interpret_expression(left, fields)?,
+            interpret_expression(right, fields)?
+        ),
+        Node::Or(left, right) => format!(
+            "({} or {})",
+            interpret_expression(left, fields)?,
+            interpret_expression(right, fields)?
+        ),
+        Node::Not(expr) => format!("(not {})", interpret_expression(expr, fields)?),
+        // A packet length is never negative.
+        Node::MatchAll => "len >= 0".to_string(),
+        Node::Equal(key, target) => primitive(field(fields, key)?, target)?,
+        Node::NotEqual(key, target) => {
+            format!("(not {})", primitive(field(fields, key)?, target)?)
+        }
+        Node::Greater(key, target) => ordering(fields, key, ">", target)?,
+        Node::Less(key, target) => ordering(fields, key, "<", target)?,
+        Node::GreaterOrEqual(key, target) => ordering(fields, key, ">=", target)?,
+        Node::LessOrEqual(key, target) => ordering(fields, key, "<=", target)?,
+        Node::Between(key, low, high) => {
+            range(field(fields, key)?, key, low.parse()?, high.parse()?)?
+        }
+        // An empty list matches nothing, whether or not the key is known, and a packet length is
+        // never negative.
+        Node::Any(_, targets) if targets.is_empty() => "len < 0".to_string(),
+        Node::Any(key, targets) => {
+            let field = field(fields, key)?;
+            let mut primitives = Vec::with_capacity(targets.len());
+            for target in targets.iter() {
+                primitives.push(primitive(field, target)?);
+            }
+            format!("({})", primitives.join(" or "))
+        }
+        Node::EqualCI(..) => {
+            return Err(Error::UnsupportedNode("case insensitive equal".into()));
+        }
+        Node::Wildcard(..) => return Err(Error::UnsupportedNode("wildcard".into())),
+        Node::Regex(..) => return Err(Error::UnsupportedNode("regex".into())),
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+        Node::Length(..) => return Err(Error::UnsupportedNode("length".into())),
+        Node::Null(..) => return Err(Error::UnsupportedNode("null".into())),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+    })
+}
+
+/// Interprets the expression as a libpcap filter, e.g. `(tcp and (src host 10.0.0.1 or port 53))`.
+///
+/// Only equality, ranges and lists on the fields of `fields` can be expressed. Other comparisons,
+/// such as regexes and wildcards, fail with `Error::UnsupportedNode`.
+pub fn interpret(expression: &Expression, fields: &BpfFields) -> Result<String> {
+    interpret_expression(expression, fields)
+}
@@ -0,0 +1,217 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+
+use crate::{Expression, Node, wildcard};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[error("Cannot find key {0} in types")]
+    UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CypherType {
+    Boolean,
+    Integer,
+    Float,
+    StringLike,
+}
+impl CypherType {
+    pub fn to_cypher_value(&self, s: &str) -> Result<CypherValue> {
+        match self {
+            CypherType::Boolean => Ok(CypherValue::Boolean(s.parse()?)),
+            CypherType::Integer => Ok(CypherValue::Integer(s.parse()?)),
+            CypherType::Float => Ok(CypherValue::Float(s.parse()?)),
+            CypherType::StringLike => Ok(CypherValue::String(s.into())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+pub enum CypherValue {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<CypherValue>),
+}
+
+pub type CypherTypes = HashMap<String, CypherType>;
+
+/// Parameters referenced as `$p0`, `$p1`, ... by the predicate.
+pub type CypherParams = HashMap<String, CypherValue>;
+
+/// Quotes a variable or property name with backticks unless it is a plain identifier.
+fn quote(identifier: &str) -> String {
+    let mut chars = identifier.chars();
+    let plain = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        identifier.to_string()
+    } else {
+        format!("`{}`", identifier.replace('`', "``"))
+    }
+}
+
+/// Translates a wildcard pattern to a regex for `=~`, which always matches the whole value.
+fn wildcard_regex(pattern: &str) -> String {
+    let mut regex = String::from("(?s)");
+    for token in wildcard::tokens(pattern) {
+        match token {
+            wildcard::Token::Literal(c) => {
+                // A backslash before any non-alphabetic character is literal in Java regexes.
+                if c.is_ascii_punctuation() {
+                    regex.push('\\');
+                }
+                regex.push(c);
+            }
+            wildcard::Token::AnyOne => regex.push('.'),
+            wildcard::Token::AnyMany => regex.push_str(".*"),
+        }
+    }
+    regex
+}
+
+struct Writer<'a> {
+    variable: String,
+    types: &'a CypherTypes,
+    params: CypherParams,
+}
+impl Writer<'_> {
+    fn property(&self, key: &str) -> Result<(String, &CypherType)> {
+        let ty = self
+            .types
+            .get(key)
+            .ok_or(Error::UnknownKey(key.to_string()))?;
+        Ok((format!("{}.{}", self.variable, quote(key)), ty))
+    }
+
+    fn param(&mut self, value: CypherValue) -> String {
+        let name = format!("p{}", self.params.len());
+        let placeholder = format!("${}", name);
+        self.params.insert(name, value);
+        placeholder
+    }
+
+    fn compare(&mut self, key: &str, operator: &str, target: &str) -> Result<String> {
+        let (property, ty) = self.property(key)?;
+        let value = ty.to_cypher_value(target)?;
+        Ok(format!("{} {} {}", property, operator, self.param(value)))
+    }
+
+    fn write(&mut self, expression: &Expression) -> Result<String> {
+        Ok(match &expression.node {
+            Node::And(left, right) => {
+                format!("({} AND {})", self.write(left)?, self.write(right)?)
+            }
+            Node::Or(left, right) => {
+                format!("({} OR {})", self.write(left)?, self.write(right)?)
+            }
+            Node::Not(expr) => format!("(NOT {})", self.write(expr)?),
+            Node::MatchAll => "true".to_string(),
+            Node::Equal(key, target) => self.compare(key, "=", target)?,
+            Node::NotEqual(key, target) => self.compare(key, "<>", target)?,
+            Node::EqualCI(key, target) => {
+                let (property, _) = self.property(key)?;
+                let param = self.param(CypherValue::String(target.to_string()));
+                format!("toLower({}) = toLower({})", property, param)
+            }
+            Node::Greater(key, target) => self.compare(key, ">", target)?,
+            Node::Less(key, target) => self.compare(key, "<", target)?,
+            Node::GreaterOrEqual(key, target) => self.compare(key, ">=", target)?,
+            Node::LessOrEqual(key, target) => self.compare(key, "<=", target)?,
+            Node::Between(key, low, high) => {
+                let (property, ty) = self.property(key)?;
+                let (low, high) = (ty.to_cypher_value(low)?, ty.to_cypher_value(high)?);
+                let low = self.param(low);
+                let high = self.param(high);
+                format!("({} >= {} AND {} <= {})", property, low, property, high)
+            }
+            Node::Wildcard(key, target) => {
+                let (property, _) = self.property(key)?;
+                let param = self.param(CypherValue::String(wildcard_regex(target)));
+                format!("{} =~ {}", property, param)
+            }
+            Node::Regex(key, target) => {
+                let (property, _) = self.property(key)?;
+                let param = self.param(CypherValue::String(target.to_string()));
+                format!("{} =~ {}", property, param)
+            }
+            // An empty list matches nothing, whether or not the key is known.
+            Node::Any(_, targets) if targets.is_empty() => "false".to_string(),
+            Node::Any(key, targets) => {
+                let (property, ty) = self.property(key)?;
+                let mut values = Vec::with_capacity(targets.len());
+                for target in targets.iter() {
+                    values.push(ty.to_cypher_value(target)?);
+                }
+                format!("{} IN {}", property, self.param(CypherValue::List(values)))
+            }
+            Node::Length(key, op, length) => {
+                let (property, _) = self.property(key)?;
+                let param = self.param(CypherValue::Integer(*length as i64));
+                format!("size({}) {} {}", property, op.symbol(), param)
+            }
+            Node::Null(key) => format!("{} IS NULL", self.property(key)?.0),
+            Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+            Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+            Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+            Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+            Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        })
+    }
+}
+
+/// Interprets the expression as a Cypher predicate on the properties of `variable`, e.g.
+/// `(n.age > $p0 AND n.name =~ $p1)`, and the parameters it references.
+pub fn interpret_expression(
+    expression: &Expression,
+    variable: &str,
+    types: &CypherTypes,
+) -> Result<(String, CypherParams)> {
+    let mut writer = Writer {
+        variable: quote(variable),
+        types,
+        params: CypherParams::new(),
+    };
+    let predicate = writer.write(expression)?;
+    Ok((predicate, writer.params))
+}
+
+pub fn interpret(
+    expression: &Expression,
+    variable: &str,
+    types: &CypherTypes,
+) -> Result<(String, CypherParams)> {
+    interpret_expression(expression, variable, types)
+}
@@ -0,0 +1,126 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use arrow::datatypes::{DataType, Schema};
+use datafusion_common::{DataFusionError, ScalarValue};
+use datafusion_expr::{Expr, cast, expr::Like, ident, lit};
+use datafusion_functions::expr_fn::{character_length, regexp_match};
+
+use crate::{CmpOp, Expression, Node, wildcard};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("DataFusion error: {0}")]
+    DataFusion(#[from] DataFusionError),
+    #[error("Cannot find key {0} in schema")]
+    UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Casts the target to the data type of the key's field, failing instead of producing a null.
+fn literal(schema: &Schema, key: &str, target: &str) -> Result<Expr> {
+    let field = schema
+        .field_with_name(key)
+        .map_err(|_| Error::UnknownKey(key.to_string()))?;
+    Ok(lit(
+        ScalarValue::Utf8(Some(target.to_string())).cast_to(field.data_type())?
+    ))
+}
+
+fn text(key: &str) -> Expr {
+    cast(ident(key), DataType::Utf8)
+}
+
+/// Escapes `%`, `_` and `\` for LIKE.
+fn escape_like(target: &str) -> String {
+    let mut escaped = String::with_capacity(target.len());
+    for c in target.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn like(key: &str, pattern: String, case_insensitive: bool) -> Expr {
+    Expr::Like(Like::new(
+        false,
+        Box::new(text(key)),
+        Box::new(lit(pattern)),
+        Some('\\'),
+        case_insensitive,
+    ))
+}
+
+/// Translates the expression into a DataFusion filter.
+///
+/// Keys are column names taken verbatim, without parsing dots or folding case. Targets are cast
+/// to the data type of their field in `schema`. Text operators (`~`, `*`, `$` and length) compare
+/// the column cast to text. Nulls follow SQL's three-valued logic, except that a null never
+/// matches a regex, so `(! ("key" $ "..."))` keeps rows whose column is null.
+pub fn interpret(expression: &Expression, schema: &Schema) -> Result<Expr> {
+    Ok(match &expression.node {
+        Node::And(left, right) => interpret(left, schema)?.and(interpret(right, schema)?),
+        Node::Or(left, right) => interpret(left, schema)?.or(interpret(right, schema)?),
+        Node::Not(expr) => !interpret(expr, schema)?,
+        Node::MatchAll => lit(true),
+        Node::Equal(key, target) => ident(key).eq(literal(schema, key, target)?),
+        Node::NotEqual(key, target) => ident(key).not_eq(literal(schema, key, target)?),
+        Node::EqualCI(key, target) => like(key, escape_like(target), true),
+        Node::Greater(key, target) => ident(key).gt(literal(schema, key, target)?),
+        Node::Less(key, target) => ident(key).lt(literal(schema, key, target)?),
+        Node::GreaterOrEqual(key, target) => ident(key).gt_eq(literal(schema, key, target)?),
+        Node::LessOrEqual(key, target) => ident(key).lt_eq(literal(schema, key, target)?),
+        Node::Between(key, low, high) => {
+            ident(key).between(literal(schema, key, low)?, literal(schema, key, high)?)
+        }
+        Node::Wildcard(key, target) => like(key, wildcard::to_like(target), false),
+        Node::Regex(key, target) => {
+            regexp_match(text(key), lit(target.as_str()), None).is_not_null()
+        }
+        Node::Any(_, targets) if targets.is_empty() => lit(false),
+        Node::Any(key, targets) => ident(key).in_list(
+            targets
+                .iter()
+                .map(|target| literal(schema, key, target))
+                .collect::<Result<_>>()?,
+            false,
+        ),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        Node::Null(key) => ident(key).is_null(),
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+        Node::Length(key, op, length) => {
+            let left = cast(character_length(text(key)), DataType::Int64);
+            let right = lit(*length as i64);
+            match op {
+                CmpOp::Equal => left.eq(right),
+                CmpOp::Greater => left.gt(right),
+                CmpOp::Less => left.lt(right),
+                CmpOp::GreaterOrEqual => left.gt_eq(right),
+                CmpOp::LessOrEqual => left.lt_eq(right),
+            }
+        }
+    })
+}
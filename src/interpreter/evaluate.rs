@@ -14,169 +14,1002 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(feature = "datetime")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "fancy-regex")]
+use fancy_regex::Regex;
+#[cfg(all(feature = "regex", not(feature = "fancy-regex")))]
 use regex::Regex;
-use std::collections::HashMap;
-use wildmatch::WildMatch;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::BuildHasher,
+    num::{ParseFloatError, ParseIntError},
+    str::ParseBoolError,
+    sync::atomic::{self, AtomicBool},
+    time::Instant,
+};
 
-use crate::{Expression, Node};
+use crate::{
+    Expression, MapLike, Node, geo,
+    wildcard::{self, WildcardConfig},
+};
+#[cfg(feature = "derive")]
+pub use flp_gsp_derive::Queryable;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Evaluation cancelled")]
+    Cancelled,
+    #[error("Cannot find key {0} in rules or record")]
+    UnknownKey(String),
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[cfg(feature = "datetime")]
+    #[error("Cannot parse to chrono: {0}")]
+    ParseChrono(#[from] chrono::ParseError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Default flags applied to every regex of a rule.
+///
+/// The flags are rendered as a leading inline group (e.g. `(?im)`), so any inline flags inside the
+/// pattern itself take precedence over them, e.g. `(?-i)` turns a configured case insensitivity
+/// back off.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RegexFlags {
+    pub case_insensitive: bool,
+    pub multi_line: bool,
+    pub dot_matches_new_line: bool,
+    pub ignore_whitespace: bool,
+}
+impl RegexFlags {
+    pub fn apply<'a>(&self, pattern: &'a str) -> Cow<'a, str> {
+        let mut flags = String::new();
+        if self.case_insensitive {
+            flags.push('i');
+        }
+        if self.multi_line {
+            flags.push('m');
+        }
+        if self.dot_matches_new_line {
+            flags.push('s');
+        }
+        if self.ignore_whitespace {
+            flags.push('x');
+        }
+        if flags.is_empty() {
+            Cow::Borrowed(pattern)
+        } else {
+            Cow::Owned(format!("(?{}){}", flags, pattern))
+        }
+    }
+}
 
 pub struct EvaluateRule {
     pub is_equal: fn(value: &str, target: &str) -> bool,
+    pub is_not_equal: fn(value: &str, target: &str) -> bool,
     pub is_equal_ci: fn(value: &str, target: &str) -> bool,
     pub is_greater_than: fn(value: &str, target: &str) -> bool,
     pub is_less_than: fn(value: &str, target: &str) -> bool,
+    pub is_greater_or_equal: fn(value: &str, target: &str) -> bool,
+    pub is_less_or_equal: fn(value: &str, target: &str) -> bool,
+    /// Whether the value is within the inclusive range from `low` to `high`.
+    pub is_between: fn(value: &str, low: &str, high: &str) -> bool,
     pub is_match_wildcard: fn(value: &str, target: &str) -> bool,
     pub is_match_regex: fn(value: &str, target: &str) -> bool,
+    pub is_fuzzy: fn(value: &str, target: &str) -> bool,
     pub is_in: fn(value: &str, target: &[String]) -> bool,
+    /// The value of a multi-valued field is a comma-separated list by default.
+    pub is_intersecting: fn(value: &str, target: &[String]) -> bool,
     pub is_none: fn(value: &str) -> bool,
+    pub is_within: fn(value: &str, lat: f64, lon: f64, radius: f64) -> bool,
+    pub regex_flags: RegexFlags,
 }
 impl Default for EvaluateRule {
     fn default() -> Self {
         Self {
             is_equal: |value, target| value == target,
-            is_equal_ci: |value, target| value.to_lowercase() == target.to_lowercase(),
+            is_not_equal: |value, target| value != target,
+            is_equal_ci: eq_ignore_case,
             is_greater_than: |value, target| value > target,
             is_less_than: |value, target| value < target,
-            is_match_wildcard: |value, target| WildMatch::new(target).matches(value),
-            is_match_regex: |value, target| {
-                let reg = Regex::new(target);
-                if reg.is_err() {
-                    return false;
-                }
-                let reg = reg.unwrap();
-                reg.is_match(value)
-            },
+            is_greater_or_equal: |value, target| value >= target,
+            is_less_or_equal: |value, target| value <= target,
+            is_between: |value, low, high| low <= value && value <= high,
+            is_match_wildcard: |value, target| wildcard::is_match(target, value),
+            is_match_regex,
+            is_fuzzy: |value, target| edit_distance(value, target) <= 2,
             is_in: |value, target| target.contains(&value.to_string()),
+            is_intersecting: |value, target| {
+                value
+                    .split(',')
+                    .any(|element| target.iter().any(|target| target == element))
+            },
             is_none: |value| {
                 value.eq_ignore_ascii_case("none") || value.eq_ignore_ascii_case("null")
             },
+            is_within: |value, lat, lon, radius| {
+                geo::parse_point(value)
+                    .is_some_and(|(lat2, lon2)| geo::haversine(lat, lon, lat2, lon2) <= radius)
+            },
+            regex_flags: RegexFlags::default(),
+        }
+    }
+}
+
+impl EvaluateRule {
+    /// Orders text case-insensitively for `>`, `<`, `>=`, `<=` and ranges, so that
+    /// `"apple" < "Banana"`.
+    ///
+    /// Characters are compared by their lowercase mapping as in `eq_ignore_case`, not by a
+    /// locale-aware collation.
+    pub fn case_insensitive() -> Self {
+        Self {
+            is_greater_than: |value, target| cmp_case_insensitive(value, target).is_gt(),
+            is_less_than: |value, target| cmp_case_insensitive(value, target).is_lt(),
+            is_greater_or_equal: |value, target| cmp_case_insensitive(value, target).is_ge(),
+            is_less_or_equal: |value, target| cmp_case_insensitive(value, target).is_le(),
+            is_between: |value, low, high| {
+                cmp_case_insensitive(value, low).is_ge()
+                    && cmp_case_insensitive(value, high).is_le()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Orders numbers numerically for `>`, `<`, `>=`, `<=` and ranges, so that `"9" < "100"`.
+    ///
+    /// Both sides are parsed as `f64`. If either fails to parse, they are compared as text like the
+    /// default rule. Equality is still textual.
+    pub fn numeric() -> Self {
+        Self {
+            is_greater_than: |value, target| cmp_numeric(value, target).is_gt(),
+            is_less_than: |value, target| cmp_numeric(value, target).is_lt(),
+            is_greater_or_equal: |value, target| cmp_numeric(value, target).is_ge(),
+            is_less_or_equal: |value, target| cmp_numeric(value, target).is_le(),
+            is_between: |value, low, high| {
+                cmp_numeric(value, low).is_ge() && cmp_numeric(value, high).is_le()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Invalid patterns never match.
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+fn is_match_regex(value: &str, pattern: &str) -> bool {
+    Regex::new(pattern).is_ok_and(|regex| regex_matches(&regex, value))
+}
+
+/// Without a regex engine, patterns never match unless the rule supplies its own matcher.
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+fn is_match_regex(_: &str, _: &str) -> bool {
+    false
+}
+
+#[cfg(all(feature = "regex", not(feature = "fancy-regex")))]
+fn regex_matches(regex: &Regex, value: &str) -> bool {
+    regex.is_match(value)
+}
+
+/// Without a regex engine, nothing is ever compiled.
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+#[derive(Debug)]
+enum Regex {}
+
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+fn regex_matches(regex: &Regex, _: &str) -> bool {
+    match *regex {}
+}
+
+/// Values exceeding the backtrack limit of `fancy-regex` never match.
+#[cfg(feature = "fancy-regex")]
+fn regex_matches(regex: &Regex, value: &str) -> bool {
+    regex.is_match(value).unwrap_or(false)
+}
+
+/// Levenshtein distance in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
         }
     }
+    row[b.len()]
+}
+
+/// The lowercase mapping of each character, with the final sigma `ς` folded to `σ`.
+fn fold_case(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == 'ς' { 'σ' } else { c })
+}
+
+/// Whether two strings are equal ignoring case, the default `EvaluateRule::is_equal_ci`.
+///
+/// ASCII text is compared with `eq_ignore_ascii_case`. Other text is compared by the lowercase
+/// mapping of each character (`char::to_lowercase`), with the final sigma `ς` folded to `σ`,
+/// without allocating. This is neither locale-aware nor full Unicode case folding, e.g. `"İ"`
+/// lowercases to `"i̇"` (with a combining dot) and so does not equal `"i"`, and `"ß"` does not
+/// equal `"ss"`.
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    if a.is_ascii() && b.is_ascii() {
+        return a.eq_ignore_ascii_case(b);
+    }
+    fold_case(a).eq(fold_case(b))
+}
+
+fn cmp_case_insensitive(a: &str, b: &str) -> Ordering {
+    fold_case(a).cmp(fold_case(b))
+}
+
+fn cmp_numeric(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) if !a.is_nan() && !b.is_nan() => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
 }
 
 pub type EvaluateRules = HashMap<String, EvaluateRule>;
 pub type EvaluatePairs = HashMap<String, String>;
+/// Records with any number of values per key, e.g. tags.
+pub type EvaluateMultiPairs = HashMap<String, Vec<String>>;
+pub type EvaluateParams = HashMap<String, Vec<String>>;
 
-pub fn interpret_expression(
+#[derive(Clone, Debug, Default)]
+pub struct EvaluateOptions {
+    /// Fields full-text terms are searched in. A term matches if any of these fields contains it,
+    /// ignoring case. Full-text terms never match when this is empty.
+    pub full_text_fields: Vec<String>,
+    /// Values treated as null by `-` in addition to `EvaluateRule::is_none`, per key. E.g. `-1`
+    /// for a view representing missing values with that sentinel.
+    pub null_sentinels: HashMap<String, HashSet<String>>,
+    /// Values used for keys missing from a record, e.g. `false` for an `archived` flag. Without a
+    /// default, comparisons on a missing key do not match.
+    pub defaults: HashMap<String, String>,
+    /// What a comparison on a key without a rule, or missing from a record without a default,
+    /// evaluates to.
+    pub unknown_key: UnknownKeyPolicy,
+    /// Wildcard characters of `*` patterns. Patterns are translated into the default syntax, `*`
+    /// and `?`, before being passed to `EvaluateRule::is_match_wildcard`.
+    pub wildcard: WildcardConfig,
+}
+
+/// How comparisons on unknown keys are evaluated.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnknownKeyPolicy {
+    /// The comparison does not match.
+    #[default]
+    False,
+    /// The comparison matches.
+    True,
+    /// Evaluating fails with `Error::UnknownKey`, e.g. to catch typos in keys.
+    Error,
+}
+
+/// Aborts an evaluation once the deadline has passed or the flag is set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cancellation<'a> {
+    pub deadline: Option<Instant>,
+    pub flag: Option<&'a AtomicBool>,
+}
+impl Cancellation<'_> {
+    pub fn is_cancelled(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .flag
+                .is_some_and(|flag| flag.load(atomic::Ordering::Relaxed))
+    }
+
+    fn check(cancellation: Option<&Self>) -> Result<()> {
+        match cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A record whose fields can be looked up by key for evaluation.
+///
+/// With the `derive` feature, `#[derive(Queryable)]` generates the implementation below, supporting
+/// `#[gsp(rename = "...")]` and `#[gsp(skip)]` on fields.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use flp_gsp::{Expression, interpreter::evaluate::*};
+///
+/// struct Person {
+///     name: String,
+///     age: u8,
+/// }
+/// impl Queryable for Person {
+///     fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+///         match key {
+///             "name" => Some(Cow::Borrowed(&self.name)),
+///             "age" => Some(Cow::Owned(self.age.to_string())),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let expression = r#"("name" = "Jack")"#.parse::<Expression>().unwrap();
+/// let mut rules = EvaluateRules::new();
+/// rules.insert("name".into(), EvaluateRule::default());
+///
+/// let persons = vec![
+///     Person { name: "Jack".into(), age: 18 },
+///     Person { name: "Joe".into(), age: 20 },
+/// ];
+/// let matched = persons
+///     .into_iter()
+///     .filter(expression.predicate(&rules))
+///     .collect::<Vec<_>>();
+/// assert_eq!(matched.len(), 1);
+/// ```
+pub trait Queryable {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>>;
+}
+impl<S: BuildHasher> Queryable for HashMap<String, String, S> {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.get(key).map(|value| Cow::Borrowed(value.as_str()))
+    }
+}
+impl Queryable for BTreeMap<String, String> {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.get(key).map(|value| Cow::Borrowed(value.as_str()))
+    }
+}
+
+impl Expression {
+    pub fn predicate<'a, T: Queryable + ?Sized, R: MapLike<EvaluateRule> + ?Sized>(
+        &'a self,
+        rules: &'a R,
+    ) -> impl Fn(&T) -> bool + 'a {
+        move |record| interpret(self, rules, record)
+    }
+}
+
+/// The value of `key`, or its default from `options` if the record has none.
+pub(crate) fn value<'a, Q: Queryable + ?Sized>(
+    key: &str,
+    pairs: &'a Q,
+    options: &'a EvaluateOptions,
+) -> Option<Cow<'a, str>> {
+    pairs.field(key).or_else(|| {
+        options
+            .defaults
+            .get(key)
+            .map(|value| Cow::Borrowed(value.as_str()))
+    })
+}
+
+fn rule_and_value<'a, R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    key: &str,
+    rules: &'a R,
+    pairs: &'a Q,
+    options: &'a EvaluateOptions,
+) -> Option<(&'a EvaluateRule, Cow<'a, str>)> {
+    Some((rules.lookup(key)?, value(key, pairs, options)?))
+}
+
+/// Applies `f` to the rule and value of `key`, or follows `EvaluateOptions::unknown_key` if either
+/// is missing.
+fn apply_rule<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    key: &str,
+    rules: &R,
+    pairs: &Q,
+    options: &EvaluateOptions,
+    f: impl FnOnce(&EvaluateRule, &str) -> bool,
+) -> Result<bool> {
+    match rule_and_value(key, rules, pairs, options) {
+        Some((rule, value)) => Ok(f(rule, &value)),
+        None => match options.unknown_key {
+            UnknownKeyPolicy::False => Ok(false),
+            UnknownKeyPolicy::True => Ok(true),
+            UnknownKeyPolicy::Error => Err(Error::UnknownKey(key.to_string())),
+        },
+    }
+}
+
+/// Regex patterns of an expression compiled once, for evaluating it against many records.
+///
+/// Patterns are compiled with the `regex_flags` of their key's rule. When evaluating with compiled
+/// regexes, they are matched directly instead of calling the rule's `is_match_regex`. Invalid
+/// patterns never match. Without the `regex` or `fancy-regex` feature nothing is compiled, and the
+/// rule's `is_match_regex` is always called.
+#[derive(Debug, Default)]
+pub struct CompiledRegexes {
+    regexes: HashMap<String, Option<Regex>>,
+}
+impl CompiledRegexes {
+    pub fn new<R: MapLike<EvaluateRule> + ?Sized>(expression: &Expression, rules: &R) -> Self {
+        let mut compiled = Self::default();
+        compiled.compile(expression, rules);
+        compiled
+    }
+
+    #[cfg_attr(
+        not(any(feature = "regex", feature = "fancy-regex")),
+        allow(clippy::only_used_in_recursion)
+    )]
+    fn compile<R: MapLike<EvaluateRule> + ?Sized>(&mut self, expression: &Expression, rules: &R) {
+        match &expression.node {
+            Node::And(left, right) | Node::Or(left, right) => {
+                self.compile(left, rules);
+                self.compile(right, rules);
+            }
+            Node::Not(expression) => self.compile(expression, rules),
+            #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+            Node::Regex(key, target) => {
+                if let Some(rule) = rules.lookup(key) {
+                    let pattern = rule.regex_flags.apply(target);
+                    if !self.regexes.contains_key(pattern.as_ref()) {
+                        let regex = Regex::new(&pattern).ok();
+                        self.regexes.insert(pattern.into_owned(), regex);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Number of distinct patterns compiled.
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    fn is_match(&self, value: &str, pattern: &str) -> Option<bool> {
+        let regex = self.regexes.get(pattern)?;
+        Some(
+            regex
+                .as_ref()
+                .is_some_and(|regex| regex_matches(regex, value)),
+        )
+    }
+}
+
+struct Context<'a> {
+    params: Option<&'a EvaluateParams>,
+    options: &'a EvaluateOptions,
+    cancellation: Option<&'a Cancellation<'a>>,
+    regexes: Option<&'a CompiledRegexes>,
+}
+
+fn evaluate<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
     expression: &Expression,
-    rules: &EvaluateRules,
-    pairs: &EvaluatePairs,
-) -> bool {
-    match &expression.node {
+    rules: &R,
+    pairs: &Q,
+    context: &Context,
+) -> Result<bool> {
+    Ok(match &expression.node {
         Node::And(left, right) => {
-            interpret_expression(left, rules, pairs) && interpret_expression(right, rules, pairs)
+            evaluate(left, rules, pairs, context)? && evaluate(right, rules, pairs, context)?
         }
         Node::Or(left, right) => {
-            interpret_expression(left, rules, pairs) || interpret_expression(right, rules, pairs)
+            evaluate(left, rules, pairs, context)? || evaluate(right, rules, pairs, context)?
         }
-        Node::Not(expr) => !interpret_expression(expr, rules, pairs),
-        Node::Equal(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
-            }
-            let value = value.unwrap();
-            (rule.is_equal)(value, target)
+        Node::Not(expr) => match &expr.node {
+            Node::Null(key) => is_present(key, rules, pairs, context.options)?,
+            _ => !evaluate(expr, rules, pairs, context)?,
+        },
+        _ => compare(expression, rules, pairs, context)?,
+    })
+}
+
+/// A record of one value, to evaluate a comparison against each value of a multi-valued key.
+struct Single<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+impl Queryable for Single<'_> {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        (key == self.key).then_some(Cow::Borrowed(self.value))
+    }
+}
+
+fn evaluate_multi<R: MapLike<EvaluateRule> + ?Sized, P: MapLike<Vec<String>> + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &P,
+    context: &Context,
+) -> Result<bool> {
+    Ok(match &expression.node {
+        Node::And(left, right) => {
+            evaluate_multi(left, rules, pairs, context)?
+                && evaluate_multi(right, rules, pairs, context)?
         }
-        Node::EqualCI(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
-            }
-            let value = value.unwrap();
-            (rule.is_equal_ci)(value, target)
+        Node::Or(left, right) => {
+            evaluate_multi(left, rules, pairs, context)?
+                || evaluate_multi(right, rules, pairs, context)?
         }
-        Node::Greater(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
+        Node::Not(expr) => match &expr.node {
+            // Present if any value is not null, so neither an empty list nor a missing key is.
+            Node::Null(key) => {
+                for value in pairs.lookup(key).into_iter().flatten() {
+                    if is_present(key, rules, &Single { key, value }, context.options)? {
+                        return Ok(true);
+                    }
+                }
+                false
             }
-            let value = value.unwrap();
-            (rule.is_greater_than)(value, target)
+            _ => !evaluate_multi(expr, rules, pairs, context)?,
+        },
+        Node::Null(key) if pairs.lookup(key).is_some_and(Vec::is_empty) => {
+            rules.lookup(key).is_some()
         }
-        Node::Less(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
+        Node::MatchAll => true,
+        node => match node.key().and_then(|key| Some((key, pairs.lookup(key)?))) {
+            Some((key, values)) => {
+                for value in values {
+                    if compare(expression, rules, &Single { key, value }, context)? {
+                        return Ok(true);
+                    }
+                }
+                false
             }
-            let value = value.unwrap();
+            // Full-text terms have no key, and there are no full-text fields without options.
+            None => false,
+        },
+    })
+}
+
+/// Pending work of `evaluate_iterative`, popped off its stack.
+enum Frame<'e> {
+    Evaluate(&'e Expression),
+    /// Evaluates the right side of an `And` if the result so far is `true`.
+    And(&'e Expression),
+    /// Evaluates the right side of an `Or` if the result so far is `false`.
+    Or(&'e Expression),
+    Not,
+}
+
+/// Same as `evaluate` with an explicit stack instead of recursion.
+fn evaluate_iterative<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    context: &Context,
+) -> Result<bool> {
+    let mut stack = vec![Frame::Evaluate(expression)];
+    let mut result = false;
+    while let Some(frame) = stack.pop() {
+        match frame {
+            Frame::Evaluate(expression) => match &expression.node {
+                Node::And(left, right) => {
+                    stack.push(Frame::And(right));
+                    stack.push(Frame::Evaluate(left));
+                }
+                Node::Or(left, right) => {
+                    stack.push(Frame::Or(right));
+                    stack.push(Frame::Evaluate(left));
+                }
+                Node::Not(expr) => match &expr.node {
+                    Node::Null(key) => result = is_present(key, rules, pairs, context.options)?,
+                    _ => {
+                        stack.push(Frame::Not);
+                        stack.push(Frame::Evaluate(expr));
+                    }
+                },
+                _ => result = compare(expression, rules, pairs, context)?,
+            },
+            Frame::And(right) if result => stack.push(Frame::Evaluate(right)),
+            Frame::Or(right) if !result => stack.push(Frame::Evaluate(right)),
+            Frame::And(_) | Frame::Or(_) => {}
+            Frame::Not => result = !result,
+        }
+    }
+    Ok(result)
+}
+
+/// Whether `value` of `key` is none or one of its null sentinels.
+fn is_null_value(rule: &EvaluateRule, key: &str, value: &str, options: &EvaluateOptions) -> bool {
+    (rule.is_none)(value)
+        || options
+            .null_sentinels
+            .get(key)
+            .is_some_and(|sentinels| sentinels.contains(value))
+}
+
+/// Whether the value of `key` is none or one of its null sentinels, for `-`.
+fn is_null<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    key: &str,
+    rules: &R,
+    pairs: &Q,
+    options: &EvaluateOptions,
+) -> Result<bool> {
+    apply_rule(key, rules, pairs, options, |rule, value| {
+        is_null_value(rule, key, value, options)
+    })
+}
+
+/// Whether `key` has a value that is not null, for `!-`. Like any comparison on a missing key,
+/// this follows `EvaluateOptions::unknown_key` if the key is missing, so by default a missing key
+/// is neither null nor present.
+fn is_present<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    key: &str,
+    rules: &R,
+    pairs: &Q,
+    options: &EvaluateOptions,
+) -> Result<bool> {
+    apply_rule(key, rules, pairs, options, |rule, value| {
+        !is_null_value(rule, key, value, options)
+    })
+}
+
+/// Evaluates a comparison, delegating `And`, `Or` and `Not` back to `evaluate`.
+fn compare<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    context: &Context,
+) -> Result<bool> {
+    let Context {
+        params,
+        options,
+        cancellation,
+        regexes,
+    } = *context;
+    Ok(match &expression.node {
+        Node::And(..) | Node::Or(..) | Node::Not(_) => evaluate(expression, rules, pairs, context)?,
+        Node::MatchAll => true,
+        Node::Equal(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_equal)(value, target)
+        })?,
+        Node::NotEqual(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_not_equal)(value, target)
+        })?,
+        Node::EqualCI(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_equal_ci)(value, target)
+        })?,
+        Node::Greater(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_greater_than)(value, target)
+        })?,
+        Node::Less(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
             (rule.is_less_than)(value, target)
+        })?,
+        Node::GreaterOrEqual(key, target) => {
+            apply_rule(key, rules, pairs, options, |rule, value| {
+                (rule.is_greater_or_equal)(value, target)
+            })?
         }
+        Node::LessOrEqual(key, target) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_less_or_equal)(value, target)
+        })?,
+        Node::Between(key, low, high) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_between)(value, low, high)
+        })?,
         Node::Wildcard(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
-            }
-            let value = value.unwrap();
-            (rule.is_match_wildcard)(value, target)
+            let target = options.wildcard.translate(target);
+            apply_rule(key, rules, pairs, options, |rule, value| {
+                (rule.is_match_wildcard)(value, &target)
+            })?
         }
         Node::Regex(key, target) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
-            }
-            let value = value.unwrap();
-            (rule.is_match_regex)(value, target)
+            Cancellation::check(cancellation)?;
+            apply_rule(key, rules, pairs, options, |rule, value| {
+                let pattern = rule.regex_flags.apply(target);
+                regexes
+                    .and_then(|regexes| regexes.is_match(value, &pattern))
+                    .unwrap_or_else(|| (rule.is_match_regex)(value, &pattern))
+            })?
         }
-        Node::Any(key, targets) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
-            }
-            let value = value.unwrap();
+        Node::Fuzzy(key, target) => {
+            Cancellation::check(cancellation)?;
+            apply_rule(key, rules, pairs, options, |rule, value| {
+                (rule.is_fuzzy)(value, target)
+            })?
+        }
+        // An empty list matches nothing, whether or not the key is known, as in the SQL
+        // interpreters.
+        Node::Any(_, targets) if targets.is_empty() => false,
+        Node::Any(key, targets) => apply_rule(key, rules, pairs, options, |rule, value| {
             (rule.is_in)(value, targets)
+        })?,
+        Node::Intersects(key, targets) => apply_rule(key, rules, pairs, options, |rule, value| {
+            (rule.is_intersecting)(value, targets)
+        })?,
+        Node::AnyParam(key, param) => {
+            let targets = params
+                .and_then(|params| params.get(param))
+                .ok_or(Error::UnresolvedParam(param.to_string()))?;
+            !targets.is_empty()
+                && apply_rule(key, rules, pairs, options, |rule, value| {
+                    (rule.is_in)(value, targets)
+                })?
         }
-        Node::Null(key) => {
-            let rule = rules.get(key);
-            if rule.is_none() {
-                return false;
-            }
-            let rule = rule.unwrap();
-            let value = pairs.get(key);
-            if value.is_none() {
-                return false;
+        Node::Null(key) => is_null(key, rules, pairs, options)?,
+        Node::Length(key, op, length) => apply_rule(key, rules, pairs, options, |_, value| {
+            op.compare(value.chars().count(), *length)
+        })?,
+        Node::GeoWithin(key, lat, lon, radius) => {
+            apply_rule(key, rules, pairs, options, |rule, value| {
+                (rule.is_within)(value, *lat, *lon, *radius)
+            })?
+        }
+        Node::FullText(term) => {
+            let term = term.to_lowercase();
+            options.full_text_fields.iter().any(|key| {
+                value(key, pairs, options).is_some_and(|value| value.to_lowercase().contains(&term))
+            })
+        }
+    })
+}
+
+/// Params are not available here, so an expression with a `{param}` list never matches, even
+/// when negated.
+pub fn interpret_expression<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+) -> bool {
+    let context = Context {
+        params: None,
+        options: &EvaluateOptions::default(),
+        cancellation: None,
+        regexes: None,
+    };
+    evaluate(expression, rules, pairs, &context).unwrap_or(false)
+}
+
+pub fn interpret<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+) -> bool {
+    interpret_expression(expression, rules, pairs)
+}
+
+/// Same as `interpret`, resolving `{param}` lists of `Any` from `params`.
+pub fn interpret_with_params<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    params: &EvaluateParams,
+) -> Result<bool> {
+    let context = Context {
+        params: Some(params),
+        options: &EvaluateOptions::default(),
+        cancellation: None,
+        regexes: None,
+    };
+    evaluate(expression, rules, pairs, &context)
+}
+
+/// Same as `interpret_with_params`, with `options` for full-text terms.
+pub fn interpret_with_options<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    params: &EvaluateParams,
+    options: &EvaluateOptions,
+) -> Result<bool> {
+    let context = Context {
+        params: Some(params),
+        options,
+        cancellation: None,
+        regexes: None,
+    };
+    evaluate(expression, rules, pairs, &context)
+}
+
+/// Same as `interpret`, walking the expression with an explicit stack instead of recursion, so that
+/// very deep expressions cannot overflow the stack. `And` and `Or` short-circuit the same way.
+pub fn interpret_iterative<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+) -> bool {
+    let context = Context {
+        params: None,
+        options: &EvaluateOptions::default(),
+        cancellation: None,
+        regexes: None,
+    };
+    evaluate_iterative(expression, rules, pairs, &context).unwrap_or(false)
+}
+
+/// Same as `interpret` for records with any number of values per key. A comparison matches if any
+/// of the key's values matches it, and `-` also matches an empty list. `Not`, `And` and `Or`
+/// combine these per-comparison results, so `!("tags" = "a")` matches records without an `a` tag.
+pub fn interpret_multi<R: MapLike<EvaluateRule> + ?Sized, P: MapLike<Vec<String>> + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &P,
+) -> bool {
+    let context = Context {
+        params: None,
+        options: &EvaluateOptions::default(),
+        cancellation: None,
+        regexes: None,
+    };
+    evaluate_multi(expression, rules, pairs, &context).unwrap_or(false)
+}
+
+/// Same as `interpret`, matching regexes with ones compiled by `CompiledRegexes::new` for this
+/// expression, so that evaluating many records compiles each pattern only once.
+pub fn interpret_compiled<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    regexes: &CompiledRegexes,
+) -> bool {
+    let context = Context {
+        params: None,
+        options: &EvaluateOptions::default(),
+        cancellation: None,
+        regexes: Some(regexes),
+    };
+    evaluate(expression, rules, pairs, &context).unwrap_or(false)
+}
+
+/// Same as `interpret_with_options` over many records, returning the matching ones.
+///
+/// Regexes are compiled once for all records, as with `interpret_compiled`. `cancellation` is
+/// checked before every record and every regex or fuzzy comparison, failing with
+/// `Error::Cancelled` once it is cancelled. A single comparison is not interrupted.
+pub fn filter_cancellable<'r, R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized + 'r>(
+    expression: &Expression,
+    rules: &R,
+    records: impl IntoIterator<Item = &'r Q>,
+    params: &EvaluateParams,
+    options: &EvaluateOptions,
+    cancellation: &Cancellation,
+) -> Result<Vec<&'r Q>> {
+    let regexes = CompiledRegexes::new(expression, rules);
+    let context = Context {
+        params: Some(params),
+        options,
+        cancellation: Some(cancellation),
+        regexes: Some(&regexes),
+    };
+    let mut matched = Vec::new();
+    for record in records {
+        Cancellation::check(Some(cancellation))?;
+        if evaluate(expression, rules, record, &context)? {
+            matched.push(record);
+        }
+    }
+    Ok(matched)
+}
+
+/// A typed value of a record for `interpret_typed`, like `SqliteType`. `None` is a null value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvaluateType {
+    Boolean(Option<bool>),
+    #[cfg(feature = "datetime")]
+    DateTime(Option<DateTime<Utc>>),
+    Integer(Option<i64>),
+    Real(Option<f64>),
+    Text(Option<String>),
+}
+impl EvaluateType {
+    /// Parses `s` into a value of the same type.
+    pub fn replace_and_return(&self, s: &str) -> Result<Self> {
+        match self {
+            EvaluateType::Boolean(_) => Ok(EvaluateType::Boolean(Some(s.parse()?))),
+            #[cfg(feature = "datetime")]
+            EvaluateType::DateTime(_) => Ok(EvaluateType::DateTime(Some(s.parse()?))),
+            EvaluateType::Integer(_) => Ok(EvaluateType::Integer(Some(s.parse()?))),
+            EvaluateType::Real(_) => Ok(EvaluateType::Real(Some(s.parse()?))),
+            EvaluateType::Text(_) => Ok(EvaluateType::Text(Some(s.to_string()))),
+        }
+    }
+
+    /// Orders the value against `target` parsed into its type. `None` if the value is null or the
+    /// two are not comparable, e.g. a NaN.
+    fn compare(&self, target: &str) -> Result<Option<Ordering>> {
+        Ok(match (self, &self.replace_and_return(target)?) {
+            (EvaluateType::Boolean(Some(a)), EvaluateType::Boolean(Some(b))) => a.partial_cmp(b),
+            #[cfg(feature = "datetime")]
+            (EvaluateType::DateTime(Some(a)), EvaluateType::DateTime(Some(b))) => a.partial_cmp(b),
+            (EvaluateType::Integer(Some(a)), EvaluateType::Integer(Some(b))) => a.partial_cmp(b),
+            (EvaluateType::Real(Some(a)), EvaluateType::Real(Some(b))) => a.partial_cmp(b),
+            (EvaluateType::Text(Some(a)), EvaluateType::Text(Some(b))) => a.partial_cmp(b),
+            _ => None,
+        })
+    }
+
+    /// The value as text for text operators, e.g. wildcards. `None` if it is null.
+    fn text(&self) -> Option<String> {
+        match self {
+            EvaluateType::Boolean(value) => value.map(|b| b.to_string()),
+            #[cfg(feature = "datetime")]
+            EvaluateType::DateTime(value) => value.map(|dt| dt.to_rfc3339()),
+            EvaluateType::Integer(value) => value.map(|i| i.to_string()),
+            EvaluateType::Real(value) => value.map(|f| f.to_string()),
+            EvaluateType::Text(value) => value.clone(),
+        }
+    }
+}
+
+pub type EvaluateTypedPairs = HashMap<String, EvaluateType>;
+
+fn evaluate_typed(expression: &Expression, pairs: &EvaluateTypedPairs) -> Result<bool> {
+    let typed = |key: &str| pairs.get(key).ok_or(Error::UnknownKey(key.to_string()));
+    let ordering = |key: &str, target: &str, f: fn(Ordering) -> bool| {
+        Ok(typed(key)?.compare(target)?.is_some_and(f))
+    };
+    // Text operators compare the text of the value like the default rule.
+    let rule = EvaluateRule::default();
+    let text =
+        |key: &str, f: &dyn Fn(&str) -> bool| Ok(typed(key)?.text().is_some_and(|value| f(&value)));
+    match &expression.node {
+        Node::And(left, right) => Ok(evaluate_typed(left, pairs)? && evaluate_typed(right, pairs)?),
+        Node::Or(left, right) => Ok(evaluate_typed(left, pairs)? || evaluate_typed(right, pairs)?),
+        Node::Not(expr) => Ok(!evaluate_typed(expr, pairs)?),
+        Node::MatchAll => Ok(true),
+        Node::Equal(key, target) => ordering(key, target, Ordering::is_eq),
+        Node::NotEqual(key, target) => ordering(key, target, Ordering::is_ne),
+        Node::Greater(key, target) => ordering(key, target, Ordering::is_gt),
+        Node::Less(key, target) => ordering(key, target, Ordering::is_lt),
+        Node::GreaterOrEqual(key, target) => ordering(key, target, Ordering::is_ge),
+        Node::LessOrEqual(key, target) => ordering(key, target, Ordering::is_le),
+        Node::Between(key, low, high) => {
+            Ok(ordering(key, low, Ordering::is_ge)? && ordering(key, high, Ordering::is_le)?)
+        }
+        Node::Any(key, targets) => {
+            for target in targets.iter() {
+                if ordering(key, target, Ordering::is_eq)? {
+                    return Ok(true);
+                }
             }
-            let value = value.unwrap();
-            (rule.is_none)(value)
+            Ok(false)
+        }
+        Node::Null(key) => Ok(typed(key)?.text().is_none()),
+        Node::EqualCI(key, target) => text(key, &|value| (rule.is_equal_ci)(value, target)),
+        Node::Wildcard(key, target) => text(key, &|value| (rule.is_match_wildcard)(value, target)),
+        Node::Regex(key, target) => text(key, &|value| (rule.is_match_regex)(value, target)),
+        Node::Fuzzy(key, target) => text(key, &|value| (rule.is_fuzzy)(value, target)),
+        Node::Intersects(key, targets) => {
+            text(key, &|value| (rule.is_intersecting)(value, targets))
+        }
+        Node::GeoWithin(key, lat, lon, radius) => {
+            text(key, &|value| (rule.is_within)(value, *lat, *lon, *radius))
         }
+        Node::Length(key, op, length) => {
+            text(key, &|value| op.compare(value.chars().count(), *length))
+        }
+        Node::FullText(term) => {
+            let term = term.to_lowercase();
+            Ok(pairs.values().any(|value| match value {
+                EvaluateType::Text(Some(text)) => text.to_lowercase().contains(&term),
+                _ => false,
+            }))
+        }
+        Node::AnyParam(_, param) => Err(Error::UnresolvedParam(param.to_string())),
     }
 }
 
-pub fn interpret(expression: &Expression, rules: &EvaluateRules, pairs: &EvaluatePairs) -> bool {
-    interpret_expression(expression, rules, pairs)
+/// Evaluates the expression against typed values, so that e.g. integers and dates are ordered
+/// by their value rather than as text.
+///
+/// Targets are parsed into the type of the value they are compared with, failing with a parse
+/// error if they do not fit, and a key missing from `pairs` fails with `Error::UnknownKey`. A null
+/// value matches no comparison except `-`, but unlike SQL, `!` inverts that, so e.g.
+/// `(! ("age" > "18"))` matches a null `age`. Text operators such as wildcards compare the text of
+/// the value like the default rule, and full-text terms are searched in all text values.
+pub fn interpret_typed(expression: &Expression, pairs: &EvaluateTypedPairs) -> Result<bool> {
+    evaluate_typed(expression, pairs)
 }
@@ -0,0 +1,391 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! An async variant of `evaluate`, for rules that need I/O, e.g. checking membership against a
+//! remote service.
+
+use std::{borrow::Cow, future::Future, pin::Pin};
+
+use crate::{Expression, MapLike, Node};
+
+use super::evaluate::{EvaluateOptions, EvaluateParams, EvaluateRule, Queryable, UnknownKeyPolicy};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error<E> {
+    #[error("Rule failed: {0}")]
+    Rule(E),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Cannot find key {0} in rules or record")]
+    UnknownKey(String),
+}
+
+pub type Result<T, E> = std::result::Result<T, Error<E>>;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Compares a value to a target, e.g. for `=`.
+pub type AsyncCompare<E> = Box<
+    dyn for<'a> Fn(&'a str, &'a str) -> BoxFuture<'a, std::result::Result<bool, E>> + Send + Sync,
+>;
+/// Whether a value is within the inclusive range from `low` to `high`.
+pub type AsyncBetween<E> = Box<
+    dyn for<'a> Fn(&'a str, &'a str, &'a str) -> BoxFuture<'a, std::result::Result<bool, E>>
+        + Send
+        + Sync,
+>;
+/// Compares a value to a list of targets, e.g. for `?`.
+pub type AsyncList<E> = Box<
+    dyn for<'a> Fn(&'a str, &'a [String]) -> BoxFuture<'a, std::result::Result<bool, E>>
+        + Send
+        + Sync,
+>;
+/// Checks a value alone, e.g. for `-`.
+pub type AsyncCheck<E> =
+    Box<dyn for<'a> Fn(&'a str) -> BoxFuture<'a, std::result::Result<bool, E>> + Send + Sync>;
+/// Whether a value is within `radius` meters of the latitude and longitude.
+pub type AsyncWithin<E> = Box<
+    dyn for<'a> Fn(&'a str, f64, f64, f64) -> BoxFuture<'a, std::result::Result<bool, E>>
+        + Send
+        + Sync,
+>;
+
+/// Boxes an async closure as an `AsyncCompare`, helping the compiler infer its signature, e.g.
+/// `compare(|value, target| Box::pin(async move { Ok(value == target) }))`.
+pub fn compare<E, F>(f: F) -> AsyncCompare<E>
+where
+    F: for<'a> Fn(&'a str, &'a str) -> BoxFuture<'a, std::result::Result<bool, E>>
+        + Send
+        + Sync
+        + 'static,
+{
+    Box::new(f)
+}
+
+/// Boxes an async closure as an `AsyncList`, e.g. for `is_in`.
+pub fn list<E, F>(f: F) -> AsyncList<E>
+where
+    F: for<'a> Fn(&'a str, &'a [String]) -> BoxFuture<'a, std::result::Result<bool, E>>
+        + Send
+        + Sync
+        + 'static,
+{
+    Box::new(f)
+}
+
+/// Boxes an async closure as an `AsyncCheck`, e.g. for `is_none`.
+pub fn check<E, F>(f: F) -> AsyncCheck<E>
+where
+    F: for<'a> Fn(&'a str) -> BoxFuture<'a, std::result::Result<bool, E>> + Send + Sync + 'static,
+{
+    Box::new(f)
+}
+
+/// The async counterpart of `EvaluateRule`, whose checks may await and fail.
+///
+/// `Default` and `From<EvaluateRule>` wrap the synchronous checks, so that only the slots needing
+/// I/O have to be replaced, e.g. `AsyncEvaluateRule { is_in: list(...), ..Default::default() }`.
+pub struct AsyncEvaluateRule<E> {
+    pub is_equal: AsyncCompare<E>,
+    pub is_not_equal: AsyncCompare<E>,
+    pub is_equal_ci: AsyncCompare<E>,
+    pub is_greater_than: AsyncCompare<E>,
+    pub is_less_than: AsyncCompare<E>,
+    pub is_greater_or_equal: AsyncCompare<E>,
+    pub is_less_or_equal: AsyncCompare<E>,
+    pub is_between: AsyncBetween<E>,
+    /// Receives the pattern in the default wildcard syntax, see `EvaluateOptions::wildcard`.
+    pub is_match_wildcard: AsyncCompare<E>,
+    /// Receives the pattern as written, without any regex flags applied.
+    pub is_match_regex: AsyncCompare<E>,
+    pub is_fuzzy: AsyncCompare<E>,
+    pub is_in: AsyncList<E>,
+    pub is_intersecting: AsyncList<E>,
+    pub is_none: AsyncCheck<E>,
+    pub is_within: AsyncWithin<E>,
+}
+
+fn ready<'a, E: Send + 'a>(result: bool) -> BoxFuture<'a, std::result::Result<bool, E>> {
+    Box::pin(std::future::ready(Ok(result)))
+}
+
+impl<E: Send + 'static> From<EvaluateRule> for AsyncEvaluateRule<E> {
+    fn from(rule: EvaluateRule) -> Self {
+        let EvaluateRule {
+            is_equal,
+            is_not_equal,
+            is_equal_ci,
+            is_greater_than,
+            is_less_than,
+            is_greater_or_equal,
+            is_less_or_equal,
+            is_between,
+            is_match_wildcard,
+            is_match_regex,
+            is_fuzzy,
+            is_in,
+            is_intersecting,
+            is_none,
+            is_within,
+            regex_flags,
+        } = rule;
+        Self {
+            is_equal: compare(move |value, target| ready(is_equal(value, target))),
+            is_not_equal: compare(move |value, target| ready(is_not_equal(value, target))),
+            is_equal_ci: compare(move |value, target| ready(is_equal_ci(value, target))),
+            is_greater_than: compare(move |value, target| ready(is_greater_than(value, target))),
+            is_less_than: compare(move |value, target| ready(is_less_than(value, target))),
+            is_greater_or_equal: compare(move |value, target| {
+                ready(is_greater_or_equal(value, target))
+            }),
+            is_less_or_equal: compare(move |value, target| ready(is_less_or_equal(value, target))),
+            is_between: Box::new(move |value, low, high| ready(is_between(value, low, high))),
+            is_match_wildcard: compare(move |value, target| {
+                ready(is_match_wildcard(value, target))
+            }),
+            is_match_regex: compare(move |value, target| {
+                ready(is_match_regex(value, &regex_flags.apply(target)))
+            }),
+            is_fuzzy: compare(move |value, target| ready(is_fuzzy(value, target))),
+            is_in: list(move |value, targets| ready(is_in(value, targets))),
+            is_intersecting: list(move |value, targets| ready(is_intersecting(value, targets))),
+            is_none: check(move |value| ready(is_none(value))),
+            is_within: Box::new(move |value, lat, lon, radius| {
+                ready(is_within(value, lat, lon, radius))
+            }),
+        }
+    }
+}
+
+impl<E: Send + 'static> Default for AsyncEvaluateRule<E> {
+    fn default() -> Self {
+        EvaluateRule::default().into()
+    }
+}
+
+pub type AsyncEvaluateRules<E> = std::collections::HashMap<String, AsyncEvaluateRule<E>>;
+
+struct Context<'a, R: ?Sized, Q: ?Sized> {
+    rules: &'a R,
+    pairs: &'a Q,
+    params: Option<&'a EvaluateParams>,
+    options: &'a EvaluateOptions,
+}
+
+impl<R: ?Sized, Q: ?Sized> Clone for Context<'_, R, Q> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<R: ?Sized, Q: ?Sized> Copy for Context<'_, R, Q> {}
+
+impl<'a, R, Q> Context<'a, R, Q>
+where
+    R: ?Sized,
+    Q: Queryable + ?Sized,
+{
+    /// The rule and value of `key`, if both exist.
+    fn lookup<E>(&self, key: &str) -> Option<(&'a AsyncEvaluateRule<E>, Cow<'a, str>)>
+    where
+        R: MapLike<AsyncEvaluateRule<E>>,
+    {
+        Some((
+            self.rules.lookup(key)?,
+            super::evaluate::value(key, self.pairs, self.options)?,
+        ))
+    }
+
+    /// What a comparison on a key without a rule or value evaluates to.
+    fn unknown<E>(&self, key: &str) -> Result<bool, E> {
+        match self.options.unknown_key {
+            UnknownKeyPolicy::False => Ok(false),
+            UnknownKeyPolicy::True => Ok(true),
+            UnknownKeyPolicy::Error => Err(Error::UnknownKey(key.to_string())),
+        }
+    }
+}
+
+/// Whether the value of `key` is none or one of its null sentinels. Returns `None` for an unknown
+/// key.
+async fn is_null<E, R, Q>(key: &str, context: Context<'_, R, Q>) -> Result<Option<bool>, E>
+where
+    R: MapLike<AsyncEvaluateRule<E>> + Sync + ?Sized,
+    Q: Queryable + Sync + ?Sized,
+{
+    let Some((rule, value)) = context.lookup(key) else {
+        return Ok(None);
+    };
+    let is_sentinel = context
+        .options
+        .null_sentinels
+        .get(key)
+        .is_some_and(|sentinels| sentinels.contains(value.as_ref()));
+    Ok(Some(
+        is_sentinel || (rule.is_none)(&value).await.map_err(Error::Rule)?,
+    ))
+}
+
+fn evaluate<'a, E, R, Q>(
+    expression: &'a Expression,
+    context: Context<'a, R, Q>,
+) -> BoxFuture<'a, Result<bool, E>>
+where
+    E: Send + 'a,
+    R: MapLike<AsyncEvaluateRule<E>> + Sync + ?Sized,
+    Q: Queryable + Sync + ?Sized,
+{
+    Box::pin(async move {
+        // Applies a slot of the rule of `key` to its value, or follows `unknown_key`.
+        macro_rules! apply {
+            ($key:expr, |$rule:ident, $value:ident| $call:expr) => {
+                match context.lookup($key) {
+                    Some(($rule, $value)) => {
+                        let $value: &str = &$value;
+                        $call.await.map_err(Error::Rule)
+                    }
+                    None => context.unknown($key),
+                }
+            };
+        }
+
+        match &expression.node {
+            Node::And(left, right) => {
+                Ok(evaluate(left, context).await? && evaluate(right, context).await?)
+            }
+            Node::Or(left, right) => {
+                Ok(evaluate(left, context).await? || evaluate(right, context).await?)
+            }
+            Node::Not(expr) => match &expr.node {
+                Node::Null(key) => match is_null(key, context).await? {
+                    Some(is_null) => Ok(!is_null),
+                    None => context.unknown(key),
+                },
+                _ => Ok(!evaluate(expr, context).await?),
+            },
+            Node::MatchAll => Ok(true),
+            Node::Equal(key, target) => apply!(key, |rule, value| (rule.is_equal)(value, target)),
+            Node::NotEqual(key, target) => {
+                apply!(key, |rule, value| (rule.is_not_equal)(value, target))
+            }
+            Node::EqualCI(key, target) => {
+                apply!(key, |rule, value| (rule.is_equal_ci)(value, target))
+            }
+            Node::Greater(key, target) => {
+                apply!(key, |rule, value| (rule.is_greater_than)(value, target))
+            }
+            Node::Less(key, target) => {
+                apply!(key, |rule, value| (rule.is_less_than)(value, target))
+            }
+            Node::GreaterOrEqual(key, target) => {
+                apply!(key, |rule, value| (rule.is_greater_or_equal)(value, target))
+            }
+            Node::LessOrEqual(key, target) => {
+                apply!(key, |rule, value| (rule.is_less_or_equal)(value, target))
+            }
+            Node::Between(key, low, high) => {
+                apply!(key, |rule, value| (rule.is_between)(value, low, high))
+            }
+            Node::Wildcard(key, target) => {
+                let target = context.options.wildcard.translate(target);
+                apply!(key, |rule, value| (rule.is_match_wildcard)(value, &target))
+            }
+            Node::Regex(key, target) => {
+                apply!(key, |rule, value| (rule.is_match_regex)(value, target))
+            }
+            Node::Fuzzy(key, target) => apply!(key, |rule, value| (rule.is_fuzzy)(value, target)),
+            // An empty list matches nothing, whether or not the key is known.
+            Node::Any(_, targets) if targets.is_empty() => Ok(false),
+            Node::Any(key, targets) => apply!(key, |rule, value| (rule.is_in)(value, targets)),
+            Node::AnyParam(key, param) => {
+                let targets = context
+                    .params
+                    .and_then(|params| params.get(param))
+                    .ok_or(Error::UnresolvedParam(param.to_string()))?;
+                if targets.is_empty() {
+                    return Ok(false);
+                }
+                apply!(key, |rule, value| (rule.is_in)(value, targets))
+            }
+            Node::Intersects(key, targets) => {
+                apply!(key, |rule, value| (rule.is_intersecting)(value, targets))
+            }
+            Node::GeoWithin(key, lat, lon, radius) => {
+                apply!(key, |rule, value| (rule.is_within)(
+                    value, *lat, *lon, *radius
+                ))
+            }
+            Node::Length(key, op, length) => match context.lookup::<E>(key) {
+                Some((_, value)) => Ok(op.compare(value.chars().count(), *length)),
+                None => context.unknown(key),
+            },
+            Node::Null(key) => match is_null(key, context).await? {
+                Some(is_null) => Ok(is_null),
+                None => context.unknown(key),
+            },
+            Node::FullText(term) => {
+                let term = term.to_lowercase();
+                Ok(context.options.full_text_fields.iter().any(|key| {
+                    super::evaluate::value(key, context.pairs, context.options)
+                        .is_some_and(|value| value.to_lowercase().contains(&term))
+                }))
+            }
+        }
+    })
+}
+
+/// Evaluates the expression against `pairs`, awaiting each rule in turn.
+///
+/// Like `evaluate::interpret`, `And` and `Or` short-circuit: the right side is not evaluated, and
+/// its rules not awaited, when the left side decides the result. The first rule error aborts the
+/// evaluation with `Error::Rule`. Params are not available here, so `Any` against a `{param}` list
+/// fails with `Error::UnresolvedParam`.
+pub async fn interpret<E, R, Q>(expression: &Expression, rules: &R, pairs: &Q) -> Result<bool, E>
+where
+    E: Send,
+    R: MapLike<AsyncEvaluateRule<E>> + Sync + ?Sized,
+    Q: Queryable + Sync + ?Sized,
+{
+    let options = EvaluateOptions::default();
+    let context = Context {
+        rules,
+        pairs,
+        params: None,
+        options: &options,
+    };
+    evaluate(expression, context).await
+}
+
+/// Same as `interpret`, resolving `{param}` lists of `Any` from `params`, with `options`.
+pub async fn interpret_with_options<E, R, Q>(
+    expression: &Expression,
+    rules: &R,
+    pairs: &Q,
+    params: &EvaluateParams,
+    options: &EvaluateOptions,
+) -> Result<bool, E>
+where
+    E: Send,
+    R: MapLike<AsyncEvaluateRule<E>> + Sync + ?Sized,
+    Q: Queryable + Sync + ?Sized,
+{
+    let context = Context {
+        rules,
+        pairs,
+        params: Some(params),
+        options,
+    };
+    evaluate(expression, context).await
+}
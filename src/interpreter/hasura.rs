@@ -16,7 +16,7 @@
 
 use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
 
-use crate::{Expression, Node};
+use crate::{Expression, Node, wildcard};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -28,6 +28,10 @@ pub enum Error {
     ParseBool(#[from] ParseBoolError),
     #[error("Cannot find key {0} in types")]
     UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -70,6 +74,7 @@ pub fn interpret_expression(expression: &Expression, types: &HasuraTypes) -> Res
             let clause = interpret_expression(expr, types)?;
             format!("{{_not:{}}}", clause)
         }
+        Node::MatchAll => "{}".to_string(),
         Node::Equal(key, target) => format!(
             "{{{}:{{_eq:{}}}}}",
             key,
@@ -78,6 +83,14 @@ pub fn interpret_expression(expression: &Expression, types: &HasuraTypes) -> Res
                 .ok_or(Error::UnknownKey(key.to_string()))?
                 .to_hasura_string(target)?
         ),
+        Node::NotEqual(key, target) => format!(
+            "{{{}:{{_neq:{}}}}}",
+            key,
+            types
+                .get(key)
+                .ok_or(Error::UnknownKey(key.to_string()))?
+                .to_hasura_string(target)?
+        ),
         Node::EqualCI(key, target) => format!(
             "{{{}:{{_ilike:{}}}}}",
             key,
@@ -102,13 +115,38 @@ pub fn interpret_expression(expression: &Expression, types: &HasuraTypes) -> Res
                 .ok_or(Error::UnknownKey(key.to_string()))?
                 .to_hasura_string(target)?
         ),
+        Node::GreaterOrEqual(key, target) => format!(
+            "{{{}:{{_gte:{}}}}}",
+            key,
+            types
+                .get(key)
+                .ok_or(Error::UnknownKey(key.to_string()))?
+                .to_hasura_string(target)?
+        ),
+        Node::LessOrEqual(key, target) => format!(
+            "{{{}:{{_lte:{}}}}}",
+            key,
+            types
+                .get(key)
+                .ok_or(Error::UnknownKey(key.to_string()))?
+                .to_hasura_string(target)?
+        ),
+        Node::Between(key, low, high) => {
+            let ty = types.get(key).ok_or(Error::UnknownKey(key.to_string()))?;
+            format!(
+                "{{{}:{{_gte:{},_lte:{}}}}}",
+                key,
+                ty.to_hasura_string(low)?,
+                ty.to_hasura_string(high)?
+            )
+        }
         Node::Wildcard(key, target) => format!(
             "{{{}:{{_ilike:{}}}}}",
             key,
             types
                 .get(key)
                 .ok_or(Error::UnknownKey(key.to_string()))?
-                .to_hasura_string(&target.replace("*", "%").replace("?", "_"))?
+                .to_hasura_string(&wildcard::to_like(target))?
         ),
         Node::Regex(key, target) => format!(
             "{{{}:{{_regex:{}}}}}",
@@ -130,6 +168,12 @@ pub fn interpret_expression(expression: &Expression, types: &HasuraTypes) -> Res
             }
             format!("{{{}:{{_in:[{}]}}}}", key, values.join(","))
         }
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+        Node::Length(..) => return Err(Error::UnsupportedNode("length".into())),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
         Node::Null(key) => {
             if !types.contains_key(key) {
                 return Err(Error::UnknownKey(key.to_string()));
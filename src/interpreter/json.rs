@@ -0,0 +1,184 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::Value;
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
+
+use super::evaluate::{EvaluateRule, Queryable};
+use crate::{
+    Expression, MapLike, Node, geo,
+    path::{self, DEFAULT_DELIMITER},
+};
+
+pub type JsonPairs = HashMap<String, Value>;
+
+/// A JSON record whose keys are paths into nested objects, e.g. `address.city`, split by
+/// `path::split` at `path_delimiter`. A numeric segment indexes into an array, e.g. `tags.0`.
+pub struct JsonRecord<'a> {
+    pub value: &'a Value,
+    /// `.` by default. An escaped delimiter, e.g. `\.`, belongs to its segment.
+    pub path_delimiter: char,
+}
+impl<'a> JsonRecord<'a> {
+    pub fn new(value: &'a Value) -> Self {
+        Self {
+            value,
+            path_delimiter: DEFAULT_DELIMITER,
+        }
+    }
+}
+impl MapLike<Value> for JsonRecord<'_> {
+    fn lookup(&self, key: &str) -> Option<&Value> {
+        path::split(key, self.path_delimiter)
+            .iter()
+            .try_fold(self.value, |value, segment| match value {
+                Value::Object(map) => map.get(segment),
+                Value::Array(elements) => elements.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+}
+
+/// Looks up the text of scalars by path, so that a JSON record can be evaluated with
+/// `EvaluateRules`. `null` is the text `null`, which the default `is_none` matches, and arrays and
+/// objects are missing.
+impl Queryable for JsonRecord<'_> {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        match self.lookup(key)? {
+            Value::Null => Some(Cow::Borrowed("null")),
+            value => text(value),
+        }
+    }
+}
+
+/// Orders a JSON scalar against a target, parsing the target into the scalar's type.
+///
+/// Integers are compared exactly, other numbers as `f64`. Returns `None` for arrays, objects,
+/// null and targets that cannot be parsed.
+fn compare(value: &Value, target: &str) -> Option<Ordering> {
+    match value {
+        Value::Number(n) => match (n.as_i64(), target.parse::<i64>()) {
+            (Some(a), Ok(b)) => Some(a.cmp(&b)),
+            _ => n.as_f64()?.partial_cmp(&target.parse::<f64>().ok()?),
+        },
+        Value::Bool(b) => Some(b.cmp(&target.parse().ok()?)),
+        Value::String(s) => Some(s.as_str().cmp(target)),
+        _ => None,
+    }
+}
+
+/// Text of a JSON scalar for the text operators.
+fn text(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(s) => Some(Cow::Borrowed(s)),
+        Value::Number(n) => Some(Cow::Owned(n.to_string())),
+        Value::Bool(b) => Some(Cow::Owned(b.to_string())),
+        _ => None,
+    }
+}
+
+/// Coordinates of a `"lat,lon"` string or a `[lat, lon]` array.
+fn point(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::String(s) => geo::parse_point(s),
+        Value::Array(a) => match a.as_slice() {
+            [lat, lon] => geo::parse_point(&format!("{},{}", lat.as_f64()?, lon.as_f64()?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluates the expression against a flat map of typed JSON values.
+///
+/// `=`, `>`, `<`, `>=`, `<=` and `?` compare natively by the value's type, e.g. numbers numerically
+/// and booleans against `"true"`/`"false"`. `~`, `*`, `$` and `%` match the text of strings,
+/// numbers and booleans with the default `EvaluateRule`. `-` matches `null` and missing keys.
+/// Arrays and objects never match any other operator, except `@` matching `"lat,lon"` strings and
+/// `[lat, lon]` arrays, and `^` matching arrays with an element equal to a target as well as
+/// comma-separated lists. `#` compares the length of strings only. As with `evaluate`, `Any`
+/// against a `{param}` list and full-text terms never match.
+pub fn interpret_map(expression: &Expression, pairs: &impl MapLike<Value>) -> bool {
+    let rule = EvaluateRule::default();
+    let matches = |key: &str, f: &dyn Fn(&Value) -> bool| pairs.lookup(key).is_some_and(f);
+    let matches_text = |key: &str, f: &dyn Fn(&str) -> bool| {
+        matches(key, &|value| text(value).is_some_and(|text| f(&text)))
+    };
+    match &expression.node {
+        Node::And(left, right) => interpret_map(left, pairs) && interpret_map(right, pairs),
+        Node::Or(left, right) => interpret_map(left, pairs) || interpret_map(right, pairs),
+        Node::Not(expr) => !interpret_map(expr, pairs),
+        Node::MatchAll => true,
+        Node::Equal(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_eq)
+        }),
+        Node::NotEqual(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_ne)
+        }),
+        Node::EqualCI(key, target) => matches_text(key, &|text| (rule.is_equal_ci)(text, target)),
+        Node::Greater(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_gt)
+        }),
+        Node::Less(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_lt)
+        }),
+        Node::GreaterOrEqual(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_ge)
+        }),
+        Node::LessOrEqual(key, target) => matches(key, &|value| {
+            compare(value, target).is_some_and(Ordering::is_le)
+        }),
+        Node::Between(key, low, high) => matches(key, &|value| {
+            compare(value, low).is_some_and(Ordering::is_ge)
+                && compare(value, high).is_some_and(Ordering::is_le)
+        }),
+        Node::Wildcard(key, target) => {
+            matches_text(key, &|text| (rule.is_match_wildcard)(text, target))
+        }
+        Node::Regex(key, target) => matches_text(key, &|text| (rule.is_match_regex)(text, target)),
+        Node::Fuzzy(key, target) => matches_text(key, &|text| (rule.is_fuzzy)(text, target)),
+        Node::Any(key, targets) => matches(key, &|value| {
+            targets
+                .iter()
+                .any(|target| compare(value, target).is_some_and(Ordering::is_eq))
+        }),
+        Node::Intersects(key, targets) => matches(key, &|value| match value {
+            Value::Array(elements) => elements.iter().any(|element| {
+                targets
+                    .iter()
+                    .any(|target| compare(element, target).is_some_and(Ordering::is_eq))
+            }),
+            value => text(value).is_some_and(|text| (rule.is_intersecting)(&text, targets)),
+        }),
+        Node::GeoWithin(key, lat, lon, radius) => matches(key, &|value| {
+            point(value)
+                .is_some_and(|(lat2, lon2)| geo::haversine(*lat, *lon, lat2, lon2) <= *radius)
+        }),
+        Node::Length(key, op, length) => matches(key, &|value| {
+            value
+                .as_str()
+                .is_some_and(|s| op.compare(s.chars().count(), *length))
+        }),
+        Node::AnyParam(..) | Node::FullText(..) => false,
+        Node::Null(key) => pairs.lookup(key).is_none_or(Value::is_null),
+    }
+}
+
+/// Evaluates the expression against a JSON record, usually an object, resolving keys as paths with
+/// `JsonRecord`. Values are compared as with `interpret_map`.
+pub fn interpret(expression: &Expression, record: &Value) -> bool {
+    interpret_map(expression, &JsonRecord::new(record))
+}
@@ -0,0 +1,52 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use std::{borrow::Cow, collections::HashMap};
+
+use super::evaluate::{self, EvaluateRule, Queryable};
+use crate::{Expression, MapLike};
+
+/// Capture group index of each key in the line regex.
+pub type LogGroups = HashMap<String, usize>;
+
+pub struct LogRecord<'a> {
+    pub captures: Captures<'a>,
+    pub groups: &'a LogGroups,
+}
+impl Queryable for LogRecord<'_> {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.groups
+            .get(key)
+            .and_then(|index| self.captures.get(*index))
+            .map(|m| Cow::Borrowed(m.as_str()))
+    }
+}
+
+/// Evaluates the expression against the captures of `line`. Lines not matching `line_regex` never
+/// match.
+pub fn interpret<R: MapLike<EvaluateRule> + ?Sized>(
+    expression: &Expression,
+    rules: &R,
+    line_regex: &Regex,
+    groups: &LogGroups,
+    line: &str,
+) -> bool {
+    match line_regex.captures(line) {
+        Some(captures) => evaluate::interpret(expression, rules, &LogRecord { captures, groups }),
+        None => false,
+    }
+}
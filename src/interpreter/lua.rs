@@ -0,0 +1,238 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+
+use crate::{CmpOp, Expression, Node};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[error("Cannot find key {0} in types")]
+    UnknownKey(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LuaType {
+    Boolean,
+    Integer,
+    Float,
+    StringLike,
+}
+impl LuaType {
+    pub fn to_lua_string(&self, s: &str) -> Result<String> {
+        match self {
+            LuaType::Boolean => Ok(s.parse::<bool>()?.to_string()),
+            LuaType::Integer => Ok(s.parse::<i64>()?.to_string()),
+            LuaType::Float => {
+                let f = s.parse::<f64>()?;
+                Ok(if f.is_nan() {
+                    "(0/0)".into()
+                } else if f.is_infinite() {
+                    if f > 0.0 { "math.huge" } else { "-math.huge" }.into()
+                } else {
+                    format!("{:?}", f)
+                })
+            }
+            LuaType::StringLike => Ok(quote(s)),
+        }
+    }
+}
+
+pub type LuaTypes = HashMap<String, LuaType>;
+
+/// Quotes `s` as a Lua string literal.
+pub fn quote(s: &str) -> String {
+    let mut buffer = String::with_capacity(s.len() + 2);
+    buffer.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => buffer.push_str("\\\\"),
+            '"' => buffer.push_str("\\\""),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            c if c.is_ascii_control() => buffer.push_str(&format!("\\{:03}", c as u32)),
+            c => buffer.push(c),
+        }
+    }
+    buffer.push('"');
+    buffer
+}
+
+/// Reserved words of Lua, which cannot be used as names in `record.name`.
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+fn field(key: &str) -> String {
+    let is_name = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&key);
+    if is_name {
+        format!("record.{}", key)
+    } else {
+        format!("record[{}]", quote(key))
+    }
+}
+
+fn value(types: &LuaTypes, key: &str, target: &str) -> Result<String> {
+    types
+        .get(key)
+        .ok_or(Error::UnknownKey(key.to_string()))?
+        .to_lua_string(target)
+}
+
+/// Interprets the expression as a Lua boolean expression over a `record` table.
+///
+/// Operators without a Lua equivalent are emitted as calls to helpers the host must define in a
+/// `gsp` table: `gsp.wildcard(value, pattern)`, `gsp.regex(value, pattern)` and
+/// `gsp.fuzzy(value, target)`. Wildcard patterns are passed as is, see `crate::wildcard` for their
+/// escapes. Full-text terms are emitted as `gsp.full_text(record, term)` and `@` as
+/// `gsp.within(value, lat, lon, radius)`. Lengths are counted with `utf8.len`, which needs Lua 5.3
+/// or later. `Any` against a `{param}` list is emitted as `gsp.any(value, params["<param>"])`, and
+/// `^` as `gsp.intersects(value, {targets})`.
+pub fn interpret_expression(expression: &Expression, types: &LuaTypes) -> Result<String> {
+    Ok(match &expression.node {
+        Node::And(left, right) => format!(
+            "({} and {})",
+            interpret_expression(left, types)?,
+            interpret_expression(right, types)?
+        ),
+        Node::Or(left, right) => format!(
+            "({} or {})",
+            interpret_expression(left, types)?,
+            interpret_expression(right, types)?
+        ),
+        // `not` binds tighter than comparisons in Lua, so a comparison needs its own parentheses.
+        Node::Not(expr) => match expr.node {
+            Node::And(..) | Node::Or(..) | Node::Not(..) => {
+                format!("(not {})", interpret_expression(expr, types)?)
+            }
+            _ => format!("(not ({}))", interpret_expression(expr, types)?),
+        },
+        Node::MatchAll => "true".to_string(),
+        Node::Equal(key, target) => {
+            format!("{} == {}", field(key), value(types, key, target)?)
+        }
+        Node::NotEqual(key, target) => {
+            format!("{} ~= {}", field(key), value(types, key, target)?)
+        }
+        Node::EqualCI(key, target) => format!(
+            "string.lower({}) == string.lower({})",
+            field(key),
+            value(types, key, target)?
+        ),
+        Node::Greater(key, target) => {
+            format!("{} > {}", field(key), value(types, key, target)?)
+        }
+        Node::Less(key, target) => {
+            format!("{} < {}", field(key), value(types, key, target)?)
+        }
+        Node::GreaterOrEqual(key, target) => {
+            format!("{} >= {}", field(key), value(types, key, target)?)
+        }
+        Node::LessOrEqual(key, target) => {
+            format!("{} <= {}", field(key), value(types, key, target)?)
+        }
+        Node::Between(key, low, high) => {
+            let field = field(key);
+            format!(
+                "({} >= {} and {} <= {})",
+                field,
+                value(types, key, low)?,
+                field,
+                value(types, key, high)?
+            )
+        }
+        Node::Wildcard(key, target) => {
+            format!("gsp.wildcard({}, {})", field(key), quote(target))
+        }
+        Node::Regex(key, target) => format!("gsp.regex({}, {})", field(key), quote(target)),
+        Node::Fuzzy(key, target) => format!("gsp.fuzzy({}, {})", field(key), quote(target)),
+        Node::FullText(term) => format!("gsp.full_text(record, {})", quote(term)),
+        Node::GeoWithin(key, lat, lon, radius) => format!(
+            "gsp.within({}, {:?}, {:?}, {:?})",
+            field(key),
+            lat,
+            lon,
+            radius
+        ),
+        Node::Length(key, op, length) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            let op = match op {
+                CmpOp::Equal => "==",
+                op => op.symbol(),
+            };
+            format!("utf8.len({}) {} {}", field(key), op, length)
+        }
+        Node::Any(key, targets) => {
+            if targets.is_empty() {
+                "false".to_string()
+            } else {
+                let field = field(key);
+                let mut clauses = Vec::with_capacity(targets.len());
+                for target in targets.iter() {
+                    clauses.push(format!("{} == {}", field, value(types, key, target)?));
+                }
+                format!("({})", clauses.join(" or "))
+            }
+        }
+        Node::AnyParam(key, param) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            format!("gsp.any({}, params[{}])", field(key), quote(param))
+        }
+        Node::Intersects(key, targets) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            format!(
+                "gsp.intersects({}, {{{}}})",
+                field(key),
+                targets
+                    .iter()
+                    .map(|target| quote(target))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+        Node::Null(key) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            format!("{} == nil", field(key))
+        }
+    })
+}
+
+pub fn interpret(expression: &Expression, types: &LuaTypes) -> Result<String> {
+    interpret_expression(expression, types)
+}
@@ -0,0 +1,209 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::{Map, Number, Value, json};
+use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+
+use crate::{Expression, Node, geo, wildcard};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[error("Invalid value {0}")]
+    InvalidValue(String),
+    #[error("Cannot find key {0} in types")]
+    UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MongoType {
+    Boolean,
+    Integer,
+    Float,
+    StringLike,
+}
+impl MongoType {
+    pub fn to_mongo_value(&self, s: &str) -> Result<Value> {
+        match self {
+            MongoType::Boolean => Ok(Value::Bool(s.parse()?)),
+            MongoType::Integer => Ok(Value::Number(s.parse::<i64>()?.into())),
+            MongoType::Float => Number::from_f64(s.parse()?)
+                .map(Value::Number)
+                .ok_or(Error::InvalidValue(s.to_string())),
+            MongoType::StringLike => Ok(Value::String(s.to_string())),
+        }
+    }
+}
+
+pub type MongoRenames = HashMap<String, String>;
+pub type MongoTypes = HashMap<String, MongoType>;
+
+/// Escapes a literal for a PCRE regex.
+fn escape_regex(c: char, regex: &mut String) {
+    if c.is_ascii_punctuation() {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
+/// Translates a wildcard pattern to a regex matching the whole value.
+fn wildcard_regex(pattern: &str) -> String {
+    let mut regex = String::from("\\A");
+    for token in wildcard::tokens(pattern) {
+        match token {
+            wildcard::Token::Literal(c) => escape_regex(c, &mut regex),
+            wildcard::Token::AnyOne => regex.push('.'),
+            wildcard::Token::AnyMany => regex.push_str(".*"),
+        }
+    }
+    regex.push_str("\\z");
+    regex
+}
+
+/// Negates a filter. A filter on a single field is negated with `$not`, or `$ne` for an equality,
+/// since `$not` only applies to operators; anything else is wrapped in `$nor`.
+fn negate(filter: Value) -> Value {
+    if let Value::Object(map) = &filter
+        && let [(field, condition)] = map.iter().collect::<Vec<_>>().as_slice()
+        && !field.starts_with('$')
+    {
+        return match condition {
+            Value::Object(operators) if operators.keys().all(|key| key.starts_with('$')) => {
+                json!({ *field: { "$not": condition } })
+            }
+            _ => json!({ *field: { "$ne": condition } }),
+        };
+    }
+    json!({ "$nor": [filter] })
+}
+
+struct Writer<'a> {
+    renames: &'a MongoRenames,
+    types: &'a MongoTypes,
+}
+impl Writer<'_> {
+    fn field<'k>(&'k self, key: &'k str) -> &'k str {
+        self.renames.get(key).map_or(key, String::as_str)
+    }
+
+    fn value(&self, key: &str, target: &str) -> Result<Value> {
+        self.types
+            .get(key)
+            .ok_or(Error::UnknownKey(key.to_string()))?
+            .to_mongo_value(target)
+    }
+
+    /// `{ field: { operator: value } }`
+    fn operator(&self, key: &str, operator: &str, target: &str) -> Result<Value> {
+        let mut condition = Map::new();
+        condition.insert(operator.to_string(), self.value(key, target)?);
+        Ok(json!({ self.field(key): condition }))
+    }
+
+    fn write(&self, expression: &Expression) -> Result<Value> {
+        Ok(match &expression.node {
+            Node::And(left, right) => json!({ "$and": [self.write(left)?, self.write(right)?] }),
+            Node::Or(left, right) => json!({ "$or": [self.write(left)?, self.write(right)?] }),
+            Node::Not(expr) => negate(self.write(expr)?),
+            Node::MatchAll => json!({}),
+            Node::Equal(key, target) => json!({ self.field(key): self.value(key, target)? }),
+            Node::NotEqual(key, target) => self.operator(key, "$ne", target)?,
+            Node::EqualCI(key, target) => {
+                self.value(key, target)?;
+                let mut regex = String::from("\\A");
+                target.chars().for_each(|c| escape_regex(c, &mut regex));
+                regex.push_str("\\z");
+                json!({ self.field(key): { "$regex": regex, "$options": "i" } })
+            }
+            Node::Greater(key, target) => self.operator(key, "$gt", target)?,
+            Node::Less(key, target) => self.operator(key, "$lt", target)?,
+            Node::GreaterOrEqual(key, target) => self.operator(key, "$gte", target)?,
+            Node::LessOrEqual(key, target) => self.operator(key, "$lte", target)?,
+            Node::Between(key, low, high) => json!({
+                self.field(key): { "$gte": self.value(key, low)?, "$lte": self.value(key, high)? }
+            }),
+            Node::Wildcard(key, target) => {
+                self.value(key, target)?;
+                json!({ self.field(key): { "$regex": wildcard_regex(target), "$options": "s" } })
+            }
+            Node::Regex(key, target) => {
+                self.value(key, target)?;
+                json!({ self.field(key): { "$regex": target } })
+            }
+            Node::Any(key, targets) => {
+                let mut values = Vec::with_capacity(targets.len());
+                for target in targets.iter() {
+                    values.push(self.value(key, target)?);
+                }
+                json!({ self.field(key): { "$in": values } })
+            }
+            Node::Null(key) => {
+                if !self.types.contains_key(key) {
+                    return Err(Error::UnknownKey(key.to_string()));
+                }
+                json!({ self.field(key): null })
+            }
+            Node::FullText(term) => json!({ "$text": { "$search": term } }),
+            Node::GeoWithin(key, lat, lon, radius) => {
+                if !self.types.contains_key(key) {
+                    return Err(Error::UnknownKey(key.to_string()));
+                }
+                json!({
+                    self.field(key): {
+                        "$geoWithin": { "$centerSphere": [[lon, lat], radius / geo::EARTH_RADIUS] }
+                    }
+                })
+            }
+            Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+            Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+            Node::Length(..) => return Err(Error::UnsupportedNode("length".into())),
+            Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        })
+    }
+}
+
+/// Interprets the expression as a MongoDB filter document, e.g.
+/// `{"$and": [{"age": {"$gt": 18}}, {"sex": {"$in": ["male", "Male"]}}]}`.
+///
+/// Keys are renamed to fields by `renames` and targets coerced by `types`. `~` and `*` become
+/// anchored `$regex` filters, `-` matches null and missing fields, `@` is a `$centerSphere` on
+/// `[lon, lat]` coordinates, and full-text terms search the collection's text index with `$text`.
+pub fn interpret_expression(
+    expression: &Expression,
+    renames: &MongoRenames,
+    types: &MongoTypes,
+) -> Result<Value> {
+    Writer { renames, types }.write(expression)
+}
+
+pub fn interpret(
+    expression: &Expression,
+    renames: &MongoRenames,
+    types: &MongoTypes,
+) -> Result<Value> {
+    interpret_expression(expression, renames, types)
+}
@@ -18,7 +18,7 @@ use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, ParseError, U
 use rust_decimal::Decimal;
 use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
 
-use crate::{Expression, Node};
+use crate::{Expression, Node, wildcard};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -36,6 +36,10 @@ pub enum Error {
     Serialization(#[from] serde_json::Error),
     #[error("Cannot find key {0} in types")]
     UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -93,6 +97,19 @@ impl MysqlType {
 pub type MysqlRenames = HashMap<String, String>;
 pub type MysqlTypes = HashMap<String, MysqlType>;
 
+fn quote(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+/// Quotes the key as an identifier. A rename may be qualified, e.g. `table_a.gender`, in which case
+/// each part is quoted separately.
+fn column(renames: &MysqlRenames, key: &str) -> String {
+    match renames.get(key) {
+        Some(rename) => rename.split('.').map(quote).collect::<Vec<_>>().join("."),
+        None => quote(key),
+    }
+}
+
 pub fn interpret_expression(
     expression: &Expression,
     renames: &MysqlRenames,
@@ -117,8 +134,18 @@ pub fn interpret_expression(
             let (clause, types) = interpret_expression(expr, renames, types)?;
             (format!("(NOT {})", clause), types)
         }
+        Node::MatchAll => ("TRUE".to_string(), Vec::new()),
         Node::Equal(key, target) => (
-            format!("{} = ?", renames.get(key).unwrap_or(key)),
+            format!("{} = ?", column(renames, key)),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::NotEqual(key, target) => (
+            format!("{} <> ?", column(renames, key)),
             vec![
                 types
                     .get(key)
@@ -127,7 +154,7 @@ pub fn interpret_expression(
             ],
         ),
         Node::EqualCI(key, target) => (
-            format!("{} LIKE ?", renames.get(key).unwrap_or(key)),
+            format!("{} LIKE ?", column(renames, key)),
             vec![
                 types
                     .get(key)
@@ -136,7 +163,7 @@ pub fn interpret_expression(
             ],
         ),
         Node::Greater(key, target) => (
-            format!("{} > ?", renames.get(key).unwrap_or(key)),
+            format!("{} > ?", column(renames, key)),
             vec![
                 types
                     .get(key)
@@ -145,7 +172,25 @@ pub fn interpret_expression(
             ],
         ),
         Node::Less(key, target) => (
-            format!("{} < ?", renames.get(key).unwrap_or(key)),
+            format!("{} < ?", column(renames, key)),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::GreaterOrEqual(key, target) => (
+            format!("{} >= ?", column(renames, key)),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::LessOrEqual(key, target) => (
+            format!("{} <= ?", column(renames, key)),
             vec![
                 types
                     .get(key)
@@ -153,17 +198,24 @@ pub fn interpret_expression(
                     .replace_and_return(target)?,
             ],
         ),
+        Node::Between(key, low, high) => {
+            let ty = types.get(key).ok_or(Error::UnknownKey(key.to_string()))?;
+            (
+                format!("{} BETWEEN ? AND ?", column(renames, key)),
+                vec![ty.replace_and_return(low)?, ty.replace_and_return(high)?],
+            )
+        }
         Node::Wildcard(key, target) => (
-            format!("{} LIKE ?", renames.get(key).unwrap_or(key)),
+            format!("{} LIKE ?", column(renames, key)),
             vec![
                 types
                     .get(key)
                     .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(&target.replace("*", "%").replace("?", "_"))?,
+                    .replace_and_return(&wildcard::to_like(target))?,
             ],
         ),
         Node::Regex(key, target) => (
-            format!("{} = ?", renames.get(key).unwrap_or(key)),
+            format!("{} RLIKE ?", column(renames, key)),
             vec![
                 types
                     .get(key)
@@ -177,7 +229,7 @@ pub fn interpret_expression(
             } else {
                 format!(
                     "{} IN ({})",
-                    renames.get(key).unwrap_or(key),
+                    column(renames, key),
                     targets.iter().map(|_| "?").collect::<Vec<_>>().join(", ")
                 )
             };
@@ -192,15 +244,46 @@ pub fn interpret_expression(
             }
             (sql, binds)
         }
-        Node::Null(key) => {
+        Node::Intersects(key, targets) => {
+            let ty = types.get(key).ok_or(Error::UnknownKey(key.to_string()))?;
+            let sql = if targets.is_empty() {
+                "FALSE".to_string()
+            } else {
+                let column = column(renames, key);
+                format!(
+                    "({})",
+                    targets
+                        .iter()
+                        .map(|_| format!("FIND_IN_SET(?, {}) > 0", column))
+                        .collect::<Vec<_>>()
+                        .join(" OR ")
+                )
+            };
+            let mut binds = Vec::with_capacity(targets.len());
+            for target in targets.iter() {
+                binds.push(ty.replace_and_return(target)?);
+            }
+            (sql, binds)
+        }
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        Node::Length(key, op, length) => {
             if !types.contains_key(key) {
                 return Err(Error::UnknownKey(key.to_string()));
             }
             (
-                format!("{} IS NULL", renames.get(key).unwrap_or(key)),
-                vec![],
+                format!("CHAR_LENGTH({}) {} ?", column(renames, key), op.symbol()),
+                vec![MysqlType::BigUnsigned(Some(*length as u64))],
             )
         }
+        Node::Null(key) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            (format!("{} IS NULL", column(renames, key)), vec![])
+        }
     })
 }
 
@@ -21,7 +21,7 @@ use rust_decimal::Decimal;
 use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
 use uuid::Uuid;
 
-use crate::{Expression, Node};
+use crate::{Expression, Node, wildcard};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -43,6 +43,10 @@ pub enum Error {
     Serialization(#[from] serde_json::Error),
     #[error("Cannot find key {0} in types")]
     UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -98,32 +102,44 @@ impl PostgresType {
 pub type PostgresRenames = HashMap<String, String>;
 pub type PostgresTypes = HashMap<String, PostgresType>;
 
-pub fn interpret_expression(
+/// Interprets the expression, calling `placeholder` for each bind in order.
+fn write(
     expression: &Expression,
     renames: &PostgresRenames,
     types: &PostgresTypes,
+    placeholder: &mut dyn FnMut() -> String,
 ) -> Result<(String, Vec<PostgresType>)> {
     Ok(match &expression.node {
         Node::And(left, right) => {
-            let (left_clause, mut left_types) = interpret_expression(left, renames, types)?;
-            let (right_clause, mut right_types) = interpret_expression(right, renames, types)?;
+            let (left_clause, mut left_types) = write(left, renames, types, placeholder)?;
+            let (right_clause, mut right_types) = write(right, renames, types, placeholder)?;
             let clause = format!("({} AND {})", left_clause, right_clause);
             left_types.append(&mut right_types);
             (clause, left_types)
         }
         Node::Or(left, right) => {
-            let (left_clause, mut left_types) = interpret_expression(left, renames, types)?;
-            let (right_clause, mut right_types) = interpret_expression(right, renames, types)?;
+            let (left_clause, mut left_types) = write(left, renames, types, placeholder)?;
+            let (right_clause, mut right_types) = write(right, renames, types, placeholder)?;
             let clause = format!("({} OR {})", left_clause, right_clause);
             left_types.append(&mut right_types);
             (clause, left_types)
         }
         Node::Not(expr) => {
-            let (clause, types) = interpret_expression(expr, renames, types)?;
+            let (clause, types) = write(expr, renames, types, placeholder)?;
             (format!("(NOT {})", clause), types)
         }
+        Node::MatchAll => ("TRUE".to_string(), Vec::new()),
         Node::Equal(key, target) => (
-            format!("{} = ??", renames.get(key).unwrap_or(key)),
+            format!("{} = {}", renames.get(key).unwrap_or(key), placeholder()),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::NotEqual(key, target) => (
+            format!("{} <> {}", renames.get(key).unwrap_or(key), placeholder()),
             vec![
                 types
                     .get(key)
@@ -132,7 +148,11 @@ pub fn interpret_expression(
             ],
         ),
         Node::EqualCI(key, target) => (
-            format!("{} ILIKE ??", renames.get(key).unwrap_or(key)),
+            format!(
+                "{} ILIKE {}",
+                renames.get(key).unwrap_or(key),
+                placeholder()
+            ),
             vec![
                 types
                     .get(key)
@@ -141,7 +161,7 @@ pub fn interpret_expression(
             ],
         ),
         Node::Greater(key, target) => (
-            format!("{} > ??", renames.get(key).unwrap_or(key)),
+            format!("{} > {}", renames.get(key).unwrap_or(key), placeholder()),
             vec![
                 types
                     .get(key)
@@ -150,7 +170,25 @@ pub fn interpret_expression(
             ],
         ),
         Node::Less(key, target) => (
-            format!("{} < ??", renames.get(key).unwrap_or(key)),
+            format!("{} < {}", renames.get(key).unwrap_or(key), placeholder()),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::GreaterOrEqual(key, target) => (
+            format!("{} >= {}", renames.get(key).unwrap_or(key), placeholder()),
+            vec![
+                types
+                    .get(key)
+                    .ok_or(Error::UnknownKey(key.to_string()))?
+                    .replace_and_return(target)?,
+            ],
+        ),
+        Node::LessOrEqual(key, target) => (
+            format!("{} <= {}", renames.get(key).unwrap_or(key), placeholder()),
             vec![
                 types
                     .get(key)
@@ -158,17 +196,33 @@ pub fn interpret_expression(
                     .replace_and_return(target)?,
             ],
         ),
+        Node::Between(key, low, high) => {
+            let ty = types.get(key).ok_or(Error::UnknownKey(key.to_string()))?;
+            let column = renames.get(key).unwrap_or(key);
+            let (low_placeholder, high_placeholder) = (placeholder(), placeholder());
+            (
+                format!(
+                    "{} BETWEEN {} AND {}",
+                    column, low_placeholder, high_placeholder
+                ),
+                vec![ty.replace_and_return(low)?, ty.replace_and_return(high)?],
+            )
+        }
         Node::Wildcard(key, target) => (
-            format!("{} ILIKE ??", renames.get(key).unwrap_or(key)),
+            format!(
+                "{} ILIKE {}",
+                renames.get(key).unwrap_or(key),
+                placeholder()
+            ),
             vec![
                 types
                     .get(key)
                     .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(&target.replace("*", "%").replace("?", "_"))?,
+                    .replace_and_return(&wildcard::to_like(target))?,
             ],
         ),
         Node::Regex(key, target) => (
-            format!("{} = ??", renames.get(key).unwrap_or(key)),
+            format!("{} ~ {}", renames.get(key).unwrap_or(key), placeholder()),
             vec![
                 types
                     .get(key)
@@ -183,7 +237,11 @@ pub fn interpret_expression(
                 format!(
                     "{} IN ({})",
                     renames.get(key).unwrap_or(key),
-                    targets.iter().map(|_| "??").collect::<Vec<_>>().join(", ")
+                    targets
+                        .iter()
+                        .map(|_| placeholder())
+                        .collect::<Vec<_>>()
+                        .join(", ")
                 )
             };
             let mut binds = Vec::with_capacity(targets.len());
@@ -197,6 +255,25 @@ pub fn interpret_expression(
             }
             (sql, binds)
         }
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        Node::Length(key, op, length) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            (
+                format!(
+                    "char_length({}) {} {}",
+                    renames.get(key).unwrap_or(key),
+                    op.symbol(),
+                    placeholder()
+                ),
+                vec![PostgresType::BigInt(Some(*length as i64))],
+            )
+        }
         Node::Null(key) => {
             if !types.contains_key(key) {
                 return Err(Error::UnknownKey(key.to_string()));
@@ -209,20 +286,26 @@ pub fn interpret_expression(
     })
 }
 
+/// Interprets the expression with `??` placeholders, to be replaced by the caller.
+pub fn interpret_expression(
+    expression: &Expression,
+    renames: &PostgresRenames,
+    types: &PostgresTypes,
+) -> Result<(String, Vec<PostgresType>)> {
+    write(expression, renames, types, &mut || "??".to_string())
+}
+
+/// Interprets the expression with numbered placeholders starting at `$index`, e.g. `$1`, `$2`, ...
+/// for `index` 1.
 pub fn interpret(
     expression: &Expression,
     renames: &PostgresRenames,
     types: &PostgresTypes,
     index: usize,
 ) -> Result<(String, Vec<PostgresType>)> {
-    let (tmp_sql, params) = interpret_expression(expression, renames, types)?;
-    let mut buffer = String::new();
-    let splitted = tmp_sql.split("??").collect::<Vec<_>>();
-    for (i, s) in splitted.iter().enumerate() {
-        buffer.push_str(s);
-        if i < splitted.len() - 1 {
-            buffer.push_str(format!("${}", index + i).as_str());
-        }
-    }
-    Ok((buffer, params))
+    let mut next = index;
+    write(expression, renames, types, &mut || {
+        next += 1;
+        format!("${}", next - 1)
+    })
 }
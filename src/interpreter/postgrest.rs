@@ -0,0 +1,207 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+
+use crate::{Expression, Node, wildcard};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[error("Cannot find key {0} in types")]
+    UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostgrestType {
+    Boolean,
+    Integer,
+    Float,
+    StringLike,
+}
+impl PostgrestType {
+    pub fn to_postgrest_string(&self, s: &str) -> Result<String> {
+        match self {
+            PostgrestType::Boolean => Ok(s.parse::<bool>()?.to_string()),
+            PostgrestType::Integer => Ok(s.parse::<i64>()?.to_string()),
+            PostgrestType::Float => Ok(s.parse::<f64>()?.to_string()),
+            PostgrestType::StringLike => Ok(s.to_string()),
+        }
+    }
+}
+
+pub type PostgrestTypes = HashMap<String, PostgrestType>;
+
+/// Filter params, e.g. `("age", "gt.30")` for `age=gt.30`. They are not percent-encoded.
+pub type PostgrestParams = Vec<(String, String)>;
+
+fn value(types: &PostgrestTypes, key: &str, target: &str) -> Result<String> {
+    types
+        .get(key)
+        .ok_or(Error::UnknownKey(key.to_string()))?
+        .to_postgrest_string(target)
+}
+
+/// Quotes a value of an `in.(...)` list if it contains a reserved character.
+fn quote(value: &str) -> String {
+    if value.is_empty()
+        || value
+            .chars()
+            .any(|c| matches!(c, ',' | '.' | ':' | '(' | ')' | '"' | '\\') || c.is_whitespace())
+    {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes `%`, `_` and `\` of a `like` pattern. `*` is always a wildcard in PostgREST, so a
+/// literal `*` cannot be expressed.
+fn escape_like(target: &str) -> Result<String> {
+    let mut buffer = String::with_capacity(target.len());
+    for c in target.chars() {
+        if c == '*' {
+            return Err(Error::UnsupportedNode(
+                "case insensitive equal with a literal *".into(),
+            ));
+        }
+        if matches!(c, '%' | '_' | '\\') {
+            buffer.push('\\');
+        }
+        buffer.push(c);
+    }
+    Ok(buffer)
+}
+
+/// Translates a wildcard pattern for `like`, where `*` is the wildcard of any sequence, so a
+/// literal `*` cannot be expressed.
+fn wildcard_like(target: &str) -> Result<String> {
+    let mut like = String::with_capacity(target.len());
+    for token in wildcard::tokens(target) {
+        match token {
+            wildcard::Token::Literal('*') => {
+                return Err(Error::UnsupportedNode("wildcard with a literal *".into()));
+            }
+            wildcard::Token::Literal(c @ ('%' | '_' | '\\')) => {
+                like.push('\\');
+                like.push(c);
+            }
+            wildcard::Token::Literal(c) => like.push(c),
+            wildcard::Token::AnyOne => like.push('_'),
+            wildcard::Token::AnyMany => like.push('*'),
+        }
+    }
+    Ok(like)
+}
+
+fn write(
+    expression: &Expression,
+    types: &PostgrestTypes,
+    negated: bool,
+    params: &mut PostgrestParams,
+) -> Result<()> {
+    let (key, filter) = match &expression.node {
+        Node::And(left, right) => {
+            if negated {
+                return Err(Error::UnsupportedNode("not and".into()));
+            }
+            write(left, types, false, params)?;
+            return write(right, types, false, params);
+        }
+        Node::Or(..) => return Err(Error::UnsupportedNode("or".into())),
+        Node::Not(expr) => return write(expr, types, !negated, params),
+        // Matching everything needs no filter, but matching nothing cannot be expressed.
+        Node::MatchAll if negated => return Err(Error::UnsupportedNode("not match all".into())),
+        Node::MatchAll => return Ok(()),
+        Node::Between(key, low, high) => {
+            if negated {
+                return Err(Error::UnsupportedNode("not between".into()));
+            }
+            params.push((key.to_string(), format!("gte.{}", value(types, key, low)?)));
+            params.push((key.to_string(), format!("lte.{}", value(types, key, high)?)));
+            return Ok(());
+        }
+        Node::Equal(key, target) => (key, format!("eq.{}", value(types, key, target)?)),
+        Node::NotEqual(key, target) => (key, format!("neq.{}", value(types, key, target)?)),
+        Node::EqualCI(key, target) => (
+            key,
+            format!("ilike.{}", escape_like(&value(types, key, target)?)?),
+        ),
+        Node::Greater(key, target) => (key, format!("gt.{}", value(types, key, target)?)),
+        Node::Less(key, target) => (key, format!("lt.{}", value(types, key, target)?)),
+        Node::GreaterOrEqual(key, target) => (key, format!("gte.{}", value(types, key, target)?)),
+        Node::LessOrEqual(key, target) => (key, format!("lte.{}", value(types, key, target)?)),
+        Node::Wildcard(key, target) => (
+            key,
+            format!("like.{}", wildcard_like(&value(types, key, target)?)?),
+        ),
+        Node::Regex(key, target) => (key, format!("match.{}", value(types, key, target)?)),
+        Node::Any(key, targets) => {
+            let mut values = Vec::with_capacity(targets.len());
+            for target in targets.iter() {
+                values.push(quote(&value(types, key, target)?));
+            }
+            (key, format!("in.({})", values.join(",")))
+        }
+        Node::Null(key) => {
+            if !types.contains_key(key) {
+                return Err(Error::UnknownKey(key.to_string()));
+            }
+            (key, "is.null".to_string())
+        }
+        Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+        Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+        Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+        Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+        Node::Length(..) => return Err(Error::UnsupportedNode("length".into())),
+        Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+    };
+    let filter = if negated {
+        format!("not.{}", filter)
+    } else {
+        filter
+    };
+    params.push((key.to_string(), filter));
+    Ok(())
+}
+
+/// Interprets a conjunction of comparisons as PostgREST filter params, e.g. `age=gt.30`.
+///
+/// Negated comparisons are prefixed with `not.`. `Or`, and `Not` over `And`, cannot be expressed
+/// as plain filter params and fail with `Error::UnsupportedNode`.
+pub fn interpret_expression(
+    expression: &Expression,
+    types: &PostgrestTypes,
+) -> Result<PostgrestParams> {
+    let mut params = PostgrestParams::new();
+    write(expression, types, false, &mut params)?;
+    Ok(params)
+}
+
+pub fn interpret(expression: &Expression, types: &PostgrestTypes) -> Result<PostgrestParams> {
+    interpret_expression(expression, types)
+}
@@ -0,0 +1,176 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use sea_query::{
+    Alias, ColumnRef, Condition, Expr, Func, IntoColumnRef, LikeExpr, SimpleExpr, Value,
+};
+use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+
+use crate::{CmpOp, Expression, Node, wildcard};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Cannot parse to int: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Cannot parse to float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Cannot parse to bool: {0}")]
+    ParseBool(#[from] ParseBoolError),
+    #[error("Cannot find key {0} in types")]
+    UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SeaQueryType {
+    Boolean,
+    Integer,
+    Float,
+    StringLike,
+}
+impl SeaQueryType {
+    pub fn to_value(&self, s: &str) -> Result<Value> {
+        match self {
+            SeaQueryType::Boolean => Ok(s.parse::<bool>()?.into()),
+            SeaQueryType::Integer => Ok(s.parse::<i64>()?.into()),
+            SeaQueryType::Float => Ok(s.parse::<f64>()?.into()),
+            SeaQueryType::StringLike => Ok(s.into()),
+        }
+    }
+}
+
+pub type SeaQueryRenames = HashMap<String, String>;
+pub type SeaQueryTypes = HashMap<String, SeaQueryType>;
+
+struct Builder<'a> {
+    renames: &'a SeaQueryRenames,
+    types: &'a SeaQueryTypes,
+}
+impl Builder<'_> {
+    /// The column of the key. A rename may be qualified, e.g. `table_a.gender`, for a column of
+    /// that table.
+    fn column(&self, key: &str) -> Result<Expr> {
+        if !self.types.contains_key(key) {
+            return Err(Error::UnknownKey(key.to_string()));
+        }
+        let column: ColumnRef = match self.renames.get(key) {
+            Some(rename) => match rename.split_once('.') {
+                Some((table, column)) => (Alias::new(table), Alias::new(column)).into_column_ref(),
+                None => Alias::new(rename).into_column_ref(),
+            },
+            None => Alias::new(key).into_column_ref(),
+        };
+        Ok(Expr::col(column))
+    }
+
+    fn value(&self, key: &str, target: &str) -> Result<Value> {
+        self.types
+            .get(key)
+            .ok_or(Error::UnknownKey(key.to_string()))?
+            .to_value(target)
+    }
+
+    fn condition(&self, expression: &Expression) -> Result<Condition> {
+        let comparison: SimpleExpr = match &expression.node {
+            Node::And(left, right) => {
+                return Ok(Condition::all()
+                    .add(self.condition(left)?)
+                    .add(self.condition(right)?));
+            }
+            Node::Or(left, right) => {
+                return Ok(Condition::any()
+                    .add(self.condition(left)?)
+                    .add(self.condition(right)?));
+            }
+            Node::Not(expr) => return Ok(self.condition(expr)?.not()),
+            Node::MatchAll => Expr::val(1).eq(1),
+            Node::Equal(key, target) => self.column(key)?.eq(self.value(key, target)?),
+            Node::NotEqual(key, target) => self.column(key)?.ne(self.value(key, target)?),
+            Node::EqualCI(key, target) => Expr::expr(Func::lower(self.column(key)?))
+                .eq(self.value(key, &target.to_lowercase())?),
+            Node::Greater(key, target) => self.column(key)?.gt(self.value(key, target)?),
+            Node::Less(key, target) => self.column(key)?.lt(self.value(key, target)?),
+            Node::GreaterOrEqual(key, target) => self.column(key)?.gte(self.value(key, target)?),
+            Node::LessOrEqual(key, target) => self.column(key)?.lte(self.value(key, target)?),
+            Node::Between(key, low, high) => self
+                .column(key)?
+                .between(self.value(key, low)?, self.value(key, high)?),
+            Node::Wildcard(key, target) => match wildcard::literal(target) {
+                // Without any wildcard the target is compared with `=`, which can use an index.
+                Some(literal) => self.column(key)?.eq(self.value(key, &literal)?),
+                None => self
+                    .column(key)?
+                    .like(LikeExpr::new(wildcard::to_like(target)).escape('\\')),
+            },
+            // An empty list matches nothing, whether or not the key is known.
+            Node::Any(_, targets) if targets.is_empty() => Expr::val(1).eq(0),
+            Node::Any(key, targets) => {
+                let mut values = Vec::with_capacity(targets.len());
+                for target in targets.iter() {
+                    values.push(self.value(key, target)?);
+                }
+                self.column(key)?.is_in(values)
+            }
+            Node::Null(key) => self.column(key)?.is_null(),
+            Node::Length(key, op, length) => {
+                let value = *length as i64;
+                let length = Expr::expr(Func::char_length(self.column(key)?));
+                match op {
+                    CmpOp::Equal => length.eq(value),
+                    CmpOp::Greater => length.gt(value),
+                    CmpOp::Less => length.lt(value),
+                    CmpOp::GreaterOrEqual => length.gte(value),
+                    CmpOp::LessOrEqual => length.lte(value),
+                }
+            }
+            Node::Regex(..) => return Err(Error::UnsupportedNode("regex".into())),
+            Node::Fuzzy(..) => return Err(Error::UnsupportedNode("fuzzy".into())),
+            Node::FullText(..) => return Err(Error::UnsupportedNode("full text".into())),
+            Node::GeoWithin(..) => return Err(Error::UnsupportedNode("geo within".into())),
+            Node::Intersects(..) => return Err(Error::UnsupportedNode("intersects".into())),
+            Node::AnyParam(_, param) => return Err(Error::UnresolvedParam(param.to_string())),
+        };
+        Ok(Condition::all().add(comparison))
+    }
+}
+
+/// Builds a sea-query `Condition` from the expression, e.g. for `SelectStatement::cond_where`, so
+/// that it is rendered and bound for the backend of the statement.
+///
+/// `And` and `Or` become `Condition::all` and `Condition::any`, and `Not` negates its condition.
+/// Keys are renamed to columns by `renames` and targets typed by `types`. Wildcards are emitted as
+/// `LIKE` with `\` as the escape character, and `~` compares `LOWER(col)` to the lowercase target.
+/// Regex, fuzzy, full-text, geo and intersects comparisons fail with `Error::UnsupportedNode`.
+pub fn interpret_expression(
+    expression: &Expression,
+    renames: &SeaQueryRenames,
+    types: &SeaQueryTypes,
+) -> Result<Condition> {
+    Builder { renames, types }.condition(expression)
+}
+
+pub fn interpret(
+    expression: &Expression,
+    renames: &SeaQueryRenames,
+    types: &SeaQueryTypes,
+) -> Result<Condition> {
+    interpret_expression(expression, renames, types)
+}
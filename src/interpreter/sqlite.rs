@@ -14,10 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(feature = "datetime")]
 use chrono::{DateTime, ParseError, Utc};
-use std::{collections::HashMap, num::ParseFloatError, num::ParseIntError, str::ParseBoolError};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    num::ParseFloatError,
+    num::ParseIntError,
+    str::ParseBoolError,
+};
 
-use crate::{Expression, Node};
+use crate::{
+    Expression, MapLike, Node, geo,
+    path::{self, DEFAULT_DELIMITER},
+    wildcard::{self, WildcardConfig},
+};
+#[cfg(feature = "spans")]
+use crate::{Span, SpannedExpression};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -27,10 +41,20 @@ pub enum Error {
     ParseFloat(#[from] ParseFloatError),
     #[error("Cannot parse to bool: {0}")]
     ParseBool(#[from] ParseBoolError),
+    #[cfg(feature = "datetime")]
     #[error("Cannot parse to chrono: {0}")]
     ParseChrono(#[from] ParseError),
     #[error("Cannot find key {0} in types")]
     UnknownKey(String),
+    #[error("Cannot resolve param {0}")]
+    UnresolvedParam(String),
+    #[error("Cannot parse any elements: {0:?}")]
+    AnyElementErrors(Vec<(usize, String)>),
+    #[error("Unsupported node {0}")]
+    UnsupportedNode(String),
+    #[cfg(feature = "spans")]
+    #[error("{1} at {0:?}")]
+    At(Span, Box<Error>),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -40,6 +64,7 @@ pub enum SqliteType {
     BigInt(Option<i64>),
     Blob(Option<Vec<u8>>),
     Boolean(Option<bool>),
+    #[cfg(feature = "datetime")]
     DateTime(Option<DateTime<Utc>>),
     Integer(Option<i32>),
     Real(Option<f64>),
@@ -51,132 +76,865 @@ impl SqliteType {
             SqliteType::BigInt(_) => Ok(SqliteType::BigInt(Some(s.parse()?))),
             SqliteType::Blob(_) => Ok(SqliteType::Blob(Some(s.as_bytes().to_vec()))),
             SqliteType::Boolean(_) => Ok(SqliteType::Boolean(Some(s.parse()?))),
+            #[cfg(feature = "datetime")]
             SqliteType::DateTime(_) => Ok(SqliteType::DateTime(Some(s.parse()?))),
             SqliteType::Integer(_) => Ok(SqliteType::Integer(Some(s.parse()?))),
             SqliteType::Real(_) => Ok(SqliteType::Real(Some(s.parse()?))),
             SqliteType::Text(_) => Ok(SqliteType::Text(Some(s.to_string()))),
         }
     }
+
+    /// Renders the value as an SQL literal, e.g. `'it''s'` or `X'00ff'`.
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            SqliteType::BigInt(Some(i)) => i.to_string(),
+            SqliteType::Blob(Some(b)) => {
+                let hex = b
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>();
+                format!("X'{}'", hex)
+            }
+            SqliteType::Boolean(Some(b)) => if *b { "1" } else { "0" }.to_string(),
+            #[cfg(feature = "datetime")]
+            SqliteType::DateTime(Some(dt)) => {
+                format!("'{}'", dt.format("%F %T%.f%:z"))
+            }
+            SqliteType::Integer(Some(i)) => i.to_string(),
+            SqliteType::Real(Some(f)) if f.is_nan() => "NULL".to_string(),
+            SqliteType::Real(Some(f)) if f.is_infinite() => {
+                if *f > 0.0 { "9e999" } else { "-9e999" }.to_string()
+            }
+            SqliteType::Real(Some(f)) => format!("{:?}", f),
+            SqliteType::Text(Some(s)) => format!("'{}'", s.replace('\'', "''")),
+            _ => "NULL".to_string(),
+        }
+    }
 }
 
 pub type SqliteRenames = HashMap<String, String>;
 pub type SqliteTypes = HashMap<String, SqliteType>;
+pub type SqliteParams = HashMap<String, Vec<String>>;
+
+/// Equality is strict (`col = ?`) by default, so NULL columns never match.
+#[derive(Clone, Debug, Default)]
+pub struct SqliteOptions {
+    /// Emit `col IS ?` for `Equal` and `col IS NOT ?` for `NotEqual`.
+    pub null_safe_equal: bool,
+    /// Values NULL columns are treated as for `Equal` and `NotEqual`, emitted as
+    /// `COALESCE(col, ?) = ?`. Takes precedence over `null_safe_equal`.
+    pub null_defaults: HashMap<String, String>,
+    /// Values representing a missing value for `-`, per key, emitted as `(col IS NULL OR col = ?)`.
+    pub null_sentinels: HashMap<String, String>,
+    /// Include NULL columns in negated wildcard and regex matches, e.g.
+    /// `(col NOT LIKE ? OR col IS NULL)` instead of `(NOT col LIKE ?)`.
+    pub null_inclusive_negation: bool,
+    /// Maximum `editdist3` cost for `Fuzzy`, emitted as `editdist3(col, ?) < ?`. `editdist3` comes
+    /// from the spellfix1 extension, which must be loaded into the connection. `Fuzzy` is
+    /// unsupported when this is `None`.
+    pub spellfix_max_cost: Option<i32>,
+    /// FTS column (or table name) full-text terms are matched against, emitted as
+    /// `column MATCH ?`. Full-text terms are unsupported when this is `None`.
+    pub default_fts_column: Option<String>,
+    /// Keys whose `>`, `<`, `>=` and `<=` comparisons are wrapped in a date function on both sides,
+    /// e.g. `datetime(col) > datetime(?)`, so that values stored with different precision compare
+    /// chronologically.
+    pub date_functions: HashMap<String, SqliteDateFunction>,
+    /// Latitude and longitude columns of keys for `@`, in degrees. `@` is unsupported for other
+    /// keys.
+    pub geo_columns: HashMap<String, (String, String)>,
+    /// Columns holding JSON documents. A key whose first path segment (see `path::split`) is one
+    /// of them addresses a value inside the document, e.g. `address.city` is emitted as
+    /// `json_extract(address, '$.city')`, with the column renamed by the renames. A numeric
+    /// segment indexes into an array. Other keys, including dotted ones, are plain columns.
+    pub json_columns: HashSet<String>,
+    /// Delimiter of the path segments of keys into `json_columns`, `.` (`path::DEFAULT_DELIMITER`)
+    /// when `None`. An escaped delimiter belongs to its segment.
+    pub path_delimiter: Option<char>,
+    /// Wildcard characters of `*` patterns, `*` and `?` by default. Literal `%` and `_` are
+    /// escaped for `LIKE` whatever the config.
+    pub wildcard: WildcardConfig,
+    /// Emit `~` as `col = ? COLLATE NOCASE` instead of `col LIKE ? ESCAPE '\'`, so that it ignores
+    /// ASCII case whatever `PRAGMA case_sensitive_like` is set to, and can use an index with the
+    /// `NOCASE` collation. `NOCASE` only folds ASCII letters. Wildcards are unaffected: patterns
+    /// with a wildcard use `LIKE`, which does not use collating sequences, and the others are
+    /// always emitted as `col = ? COLLATE NOCASE`.
+    pub collate_nocase: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SqliteDateFunction {
+    Date,
+    DateTime,
+}
+impl SqliteDateFunction {
+    fn name(&self) -> &'static str {
+        match self {
+            SqliteDateFunction::Date => "date",
+            SqliteDateFunction::DateTime => "datetime",
+        }
+    }
+}
+
+/// Renders a JSON path for `json_extract` as the content of an SQL string literal. Labels other
+/// than plain identifiers are quoted, which cannot express a label containing `"`.
+fn json_path(segments: &[String]) -> String {
+    let mut path = String::from("$");
+    for segment in segments {
+        if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+            path.push_str(&format!("[{}]", segment));
+        } else if segment
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            path.push('.');
+            path.push_str(segment);
+        } else {
+            path.push_str(&format!(".\"{}\"", segment.replace('\'', "''")));
+        }
+    }
+    path
+}
+
+const LIKE_ESCAPE: &str = " ESCAPE '\\'";
+
+/// Escapes `%`, `_` and `\` so that they match literally in a `LIKE` pattern with `LIKE_ESCAPE`.
+fn escape_like(target: &str) -> String {
+    let mut buffer = String::with_capacity(target.len());
+    for c in target.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            buffer.push('\\');
+        }
+        buffer.push(c);
+    }
+    buffer
+}
+
+/// How values are written into the clause.
+#[derive(Clone, Copy, PartialEq)]
+enum Placeholders {
+    /// `?`, with a bind per value.
+    Positional,
+    /// `:p0`, `:p1`, ... numbered in order of appearance, with a bind per value.
+    Named,
+    /// `?1`, `?2`, ... with a bind per distinct value, reusing its index when it appears again.
+    Indexed,
+    /// Values inlined as SQL literals, without binds.
+    Inline,
+}
+
+struct Context<'a> {
+    renames: &'a dyn MapLike<String>,
+    types: &'a dyn MapLike<SqliteType>,
+    params: Option<&'a SqliteParams>,
+    options: &'a SqliteOptions,
+    placeholders: Placeholders,
+    /// The key of each bind, in order, when requested.
+    bind_keys: Option<RefCell<Vec<String>>>,
+    #[cfg(feature = "spans")]
+    leaves: std::cell::Cell<usize>,
+}
+
+impl Context<'_> {
+    fn column<'a>(&'a self, key: &'a str) -> Cow<'a, str> {
+        if !self.options.json_columns.is_empty() {
+            let delimiter = self.options.path_delimiter.unwrap_or(DEFAULT_DELIMITER);
+            let segments = path::split(key, delimiter);
+            if let [column, path @ ..] = segments.as_slice()
+                && !path.is_empty()
+                && self.options.json_columns.contains(column)
+            {
+                let column = self.renames.lookup(column).map_or(column, |rename| rename);
+                return Cow::Owned(format!("json_extract({}, '{}')", column, json_path(path)));
+            }
+        }
+        Cow::Borrowed(self.renames.lookup(key).map_or(key, String::as_str))
+    }
+
+    fn bind(&self, key: &str, target: &str) -> Result<SqliteType> {
+        self.types
+            .lookup(key)
+            .ok_or(Error::UnknownKey(key.to_string()))?
+            .replace_and_return(target)
+    }
+
+    /// Writes a placeholder for `value` and binds it, or writes `value` as a literal when inlining.
+    /// `key` is recorded for each new bind when collecting `bind_keys`.
+    fn push_value(
+        &self,
+        key: &str,
+        value: SqliteType,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) {
+        match self.placeholders {
+            Placeholders::Positional => sql.push('?'),
+            Placeholders::Named => {
+                sql.push_str(":p");
+                sql.push_str(&binds.len().to_string());
+            }
+            Placeholders::Indexed => {
+                sql.push('?');
+                if let Some(index) = binds.iter().position(|bind| *bind == value) {
+                    sql.push_str(&(index + 1).to_string());
+                    return;
+                }
+                sql.push_str(&(binds.len() + 1).to_string());
+            }
+            Placeholders::Inline => {
+                sql.push_str(&value.to_sql_literal());
+                return;
+            }
+        }
+        if let Some(bind_keys) = &self.bind_keys {
+            bind_keys.borrow_mut().push(key.to_string());
+        }
+        binds.push(value);
+    }
+
+    fn write_any(
+        &self,
+        key: &str,
+        targets: &[String],
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        if targets.is_empty() {
+            sql.push_str("FALSE");
+            return Ok(());
+        }
+        let ty = self
+            .types
+            .lookup(key)
+            .ok_or(Error::UnknownKey(key.to_string()))?;
+        let mut values = Vec::with_capacity(targets.len());
+        let mut errors = Vec::new();
+        for (i, target) in targets.iter().enumerate() {
+            match ty.replace_and_return(target) {
+                Ok(value) => values.push(value),
+                Err(err) => errors.push((i, err.to_string())),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::AnyElementErrors(errors));
+        }
+        sql.push_str(&self.column(key));
+        sql.push_str(" IN (");
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            self.push_value(key, value, sql, binds);
+        }
+        sql.push(')');
+        Ok(())
+    }
+
+    /// Approximates `Intersects` on a comma-joined column by matching each target as an element
+    /// with `LIKE`. Elements containing commas, or lists with spaces around the commas, are not
+    /// matched correctly.
+    fn write_intersects(
+        &self,
+        key: &str,
+        targets: &[String],
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        if targets.is_empty() {
+            sql.push_str("FALSE");
+            return Ok(());
+        }
+        let column = &*self.column(key);
+        sql.push('(');
+        for (i, target) in targets.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(" OR ");
+            }
+            let value = self.bind(key, &format!("%,{},%", escape_like(target)))?;
+            sql.push_str("',' || ");
+            sql.push_str(column);
+            sql.push_str(" || ',' LIKE ");
+            self.push_value(key, value, sql, binds);
+            sql.push_str(LIKE_ESCAPE);
+        }
+        sql.push(')');
+        Ok(())
+    }
+
+    /// Writes `col IS NULL`, or `col IS NOT NULL` rather than `(NOT col IS NULL)` if negated.
+    fn write_null(
+        &self,
+        key: &str,
+        negated: bool,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        if self.types.lookup(key).is_none() {
+            return Err(Error::UnknownKey(key.to_string()));
+        }
+        let column = &*self.column(key);
+        match self.options.null_sentinels.get(key) {
+            Some(sentinel) => {
+                let value = self.bind(key, sentinel)?;
+                sql.push_str(&if negated {
+                    format!("({} IS NOT NULL AND {} <> ", column, column)
+                } else {
+                    format!("({} IS NULL OR {} = ", column, column)
+                });
+                self.push_value(key, value, sql, binds);
+                sql.push(')');
+            }
+            None => {
+                sql.push_str(column);
+                sql.push_str(if negated { " IS NOT NULL" } else { " IS NULL" });
+            }
+        }
+        Ok(())
+    }
+
+    fn write_equality(
+        &self,
+        key: &str,
+        target: &str,
+        negated: bool,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        if let Some(default) = self.options.null_defaults.get(key) {
+            sql.push_str("COALESCE(");
+            sql.push_str(&self.column(key));
+            sql.push_str(", ");
+            self.push_value(key, self.bind(key, default)?, sql, binds);
+            sql.push_str(if negated { ") <> " } else { ") = " });
+        } else {
+            sql.push_str(&self.column(key));
+            sql.push_str(match (self.options.null_safe_equal, negated) {
+                (true, false) => " IS ",
+                (true, true) => " IS NOT ",
+                (false, false) => " = ",
+                (false, true) => " <> ",
+            });
+        }
+        self.push_value(key, self.bind(key, target)?, sql, binds);
+        Ok(())
+    }
+
+    fn write_ordering(
+        &self,
+        key: &str,
+        target: &str,
+        operator: &str,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        let value = self.bind(key, target)?;
+        let column = &*self.column(key);
+        match self.options.date_functions.get(key) {
+            Some(function) => {
+                let function = function.name();
+                sql.push_str(&format!(
+                    "{}({}) {} {}(",
+                    function, column, operator, function
+                ));
+                self.push_value(key, value, sql, binds);
+                sql.push(')');
+            }
+            None => {
+                sql.push_str(column);
+                sql.push(' ');
+                sql.push_str(operator);
+                sql.push(' ');
+                self.push_value(key, value, sql, binds);
+            }
+        }
+        Ok(())
+    }
+
+    /// `col BETWEEN ? AND ?`, wrapping the column and both bounds like `write_ordering` for keys
+    /// with a date function.
+    fn write_between(
+        &self,
+        key: &str,
+        low: &str,
+        high: &str,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        let low = self.bind(key, low)?;
+        let high = self.bind(key, high)?;
+        let column = &*self.column(key);
+        let function = self
+            .options
+            .date_functions
+            .get(key)
+            .map(|function| function.name());
+        let mut push_bound = |value, sql: &mut String| match function {
+            Some(function) => {
+                sql.push_str(function);
+                sql.push('(');
+                self.push_value(key, value, sql, binds);
+                sql.push(')');
+            }
+            None => self.push_value(key, value, sql, binds),
+        };
+        match function {
+            Some(function) => sql.push_str(&format!("{}({}) BETWEEN ", function, column)),
+            None => {
+                sql.push_str(column);
+                sql.push_str(" BETWEEN ");
+            }
+        }
+        push_bound(low, sql);
+        sql.push_str(" AND ");
+        push_bound(high, sql);
+        Ok(())
+    }
+
+    /// A bounding box prefilter, which can use indexes on the columns, followed by an
+    /// equirectangular distance check. The distance is approximate: it grows inaccurate for large
+    /// radii and near the poles, and the box does not wrap around the antimeridian.
+    fn write_within(
+        &self,
+        key: &str,
+        (lat, lon, radius): (f64, f64, f64),
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        let (lat_column, lon_column) = self
+            .options
+            .geo_columns
+            .get(key)
+            .ok_or(Error::UnsupportedNode("geo within".into()))?;
+        let d_lat = radius / geo::METERS_PER_DEGREE;
+        let scale = lat.to_radians().cos();
+        let d_lon = if scale > f64::EPSILON {
+            d_lat / scale
+        } else {
+            360.0
+        };
+        let parts = [
+            (format!("({} BETWEEN ", lat_column), lat - d_lat),
+            (" AND ".to_string(), lat + d_lat),
+            (format!(" AND {} BETWEEN ", lon_column), lon - d_lon),
+            (" AND ".to_string(), lon + d_lon),
+            (format!(" AND ({} - ", lat_column), lat),
+            (format!(") * ({} - ", lat_column), lat),
+            (format!(") + ({} - ", lon_column), lon),
+            (format!(") * ({} - ", lon_column), lon),
+            (") * ".to_string(), scale * scale),
+            (" <= ".to_string(), d_lat * d_lat),
+        ];
+        for (prefix, value) in parts {
+            sql.push_str(&prefix);
+            self.push_value(key, SqliteType::Real(Some(value)), sql, binds);
+        }
+        sql.push(')');
+        Ok(())
+    }
+
+    fn write_match(
+        &self,
+        key: &str,
+        target: &str,
+        regex: bool,
+        negated: bool,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        if regex {
+            let value = self.bind(key, target)?;
+            sql.push_str(&self.column(key));
+            sql.push_str(if negated { " NOT REGEXP " } else { " REGEXP " });
+            self.push_value(key, value, sql, binds);
+            return Ok(());
+        }
+        let target = self.options.wildcard.translate(target);
+        // Without any wildcard the target is compared with `=`, which can use an index. `NOCASE`
+        // ignores ASCII case like `LIKE` does.
+        if let Some(literal) = wildcard::literal(&target) {
+            let value = self.bind(key, &literal)?;
+            sql.push_str(&self.column(key));
+            sql.push_str(if negated { " <> " } else { " = " });
+            self.push_value(key, value, sql, binds);
+            sql.push_str(" COLLATE NOCASE");
+        } else {
+            let value = self.bind(key, &wildcard::to_like(&target))?;
+            sql.push_str(&self.column(key));
+            sql.push_str(if negated { " NOT LIKE " } else { " LIKE " });
+            self.push_value(key, value, sql, binds);
+            sql.push_str(LIKE_ESCAPE);
+        }
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        expression: &Expression,
+        sql: &mut String,
+        binds: &mut Vec<SqliteType>,
+    ) -> Result<()> {
+        match &expression.node {
+            Node::And(left, right) => {
+                sql.push('(');
+                self.write(left, sql, binds)?;
+                sql.push_str(" AND ");
+                self.write(right, sql, binds)?;
+                sql.push(')');
+            }
+            Node::Or(left, right) => {
+                sql.push('(');
+                self.write(left, sql, binds)?;
+                sql.push_str(" OR ");
+                self.write(right, sql, binds)?;
+                sql.push(')');
+            }
+            Node::Not(expr) => match &expr.node {
+                Node::Wildcard(key, target) | Node::Regex(key, target)
+                    if self.options.null_inclusive_negation =>
+                {
+                    let regex = matches!(expr.node, Node::Regex(..));
+                    sql.push('(');
+                    self.write_match(key, target, regex, true, sql, binds)?;
+                    sql.push_str(" OR ");
+                    sql.push_str(&self.column(key));
+                    sql.push_str(" IS NULL)");
+                    #[cfg(feature = "spans")]
+                    self.leaves.set(self.leaves.get() + 1);
+                }
+                Node::Null(key) => {
+                    self.write_null(key, true, sql, binds)?;
+                    #[cfg(feature = "spans")]
+                    self.leaves.set(self.leaves.get() + 1);
+                }
+                _ => {
+                    sql.push_str("(NOT ");
+                    self.write(expr, sql, binds)?;
+                    sql.push(')');
+                }
+            },
+            Node::MatchAll => sql.push_str("TRUE"),
+            Node::Equal(key, target) => self.write_equality(key, target, false, sql, binds)?,
+            Node::NotEqual(key, target) => self.write_equality(key, target, true, sql, binds)?,
+            Node::EqualCI(key, target) if self.options.collate_nocase => {
+                let value = self.bind(key, target)?;
+                sql.push_str(&self.column(key));
+                sql.push_str(" = ");
+                self.push_value(key, value, sql, binds);
+                sql.push_str(" COLLATE NOCASE");
+            }
+            Node::EqualCI(key, target) => {
+                let value = self.bind(key, &escape_like(target))?;
+                sql.push_str(&self.column(key));
+                sql.push_str(" LIKE ");
+                self.push_value(key, value, sql, binds);
+                sql.push_str(LIKE_ESCAPE);
+            }
+            Node::Greater(key, target) => self.write_ordering(key, target, ">", sql, binds)?,
+            Node::Less(key, target) => self.write_ordering(key, target, "<", sql, binds)?,
+            Node::GreaterOrEqual(key, target) => {
+                self.write_ordering(key, target, ">=", sql, binds)?
+            }
+            Node::LessOrEqual(key, target) => self.write_ordering(key, target, "<=", sql, binds)?,
+            Node::Between(key, low, high) => self.write_between(key, low, high, sql, binds)?,
+            Node::Wildcard(key, target) => {
+                self.write_match(key, target, false, false, sql, binds)?
+            }
+            Node::Regex(key, target) => self.write_match(key, target, true, false, sql, binds)?,
+            Node::Fuzzy(key, target) => {
+                let max_cost = self
+                    .options
+                    .spellfix_max_cost
+                    .ok_or(Error::UnsupportedNode("fuzzy".into()))?;
+                let value = self.bind(key, target)?;
+                sql.push_str("editdist3(");
+                sql.push_str(&self.column(key));
+                sql.push_str(", ");
+                self.push_value(key, value, sql, binds);
+                sql.push_str(") < ");
+                self.push_value(key, SqliteType::Integer(Some(max_cost)), sql, binds);
+            }
+            Node::FullText(term) => {
+                let column = self
+                    .options
+                    .default_fts_column
+                    .as_ref()
+                    .ok_or(Error::UnsupportedNode("full text".into()))?;
+                sql.push_str(column);
+                sql.push_str(" MATCH ");
+                // A full-text term has no key, so it is attributed to its column.
+                self.push_value(column, SqliteType::Text(Some(term.to_string())), sql, binds);
+            }
+            Node::GeoWithin(key, lat, lon, radius) => {
+                self.write_within(key, (*lat, *lon, *radius), sql, binds)?
+            }
+            Node::Any(key, targets) => self.write_any(key, targets, sql, binds)?,
+            Node::AnyParam(key, param) => {
+                let targets = self
+                    .params
+                    .and_then(|params| params.get(param))
+                    .ok_or(Error::UnresolvedParam(param.to_string()))?;
+                self.write_any(key, targets, sql, binds)?
+            }
+            Node::Intersects(key, targets) => self.write_intersects(key, targets, sql, binds)?,
+            Node::Length(key, op, length) => {
+                if self.types.lookup(key).is_none() {
+                    return Err(Error::UnknownKey(key.to_string()));
+                }
+                sql.push_str("LENGTH(");
+                sql.push_str(&self.column(key));
+                sql.push_str(") ");
+                sql.push_str(op.symbol());
+                sql.push(' ');
+                self.push_value(key, SqliteType::BigInt(Some(*length as i64)), sql, binds);
+            }
+            Node::Null(key) => self.write_null(key, false, sql, binds)?,
+        }
+        #[cfg(feature = "spans")]
+        if !matches!(
+            expression.node,
+            Node::And(..) | Node::Or(..) | Node::Not(..) | Node::MatchAll
+        ) {
+            self.leaves.set(self.leaves.get() + 1);
+        }
+        Ok(())
+    }
+}
+
+/// Writes the clause into `sql` and appends binds to `binds` without allocating intermediate
+/// strings for each node.
+pub fn interpret_into(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+    sql: &mut String,
+    binds: &mut Vec<SqliteType>,
+) -> Result<()> {
+    Context {
+        renames,
+        types,
+        params: None,
+        options: &SqliteOptions::default(),
+        placeholders: Placeholders::Positional,
+        bind_keys: None,
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    }
+    .write(expression, sql, binds)
+}
 
 pub fn interpret_expression(
     expression: &Expression,
-    renames: &SqliteRenames,
-    types: &SqliteTypes,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
 ) -> Result<(String, Vec<SqliteType>)> {
-    Ok(match &expression.node {
-        Node::And(left, right) => {
-            let (left_clause, mut left_types) = interpret_expression(left, renames, types)?;
-            let (right_clause, mut right_types) = interpret_expression(right, renames, types)?;
-            let clause = format!("({} AND {})", left_clause, right_clause);
-            left_types.append(&mut right_types);
-            (clause, left_types)
-        }
-        Node::Or(left, right) => {
-            let (left_clause, mut left_types) = interpret_expression(left, renames, types)?;
-            let (right_clause, mut right_types) = interpret_expression(right, renames, types)?;
-            let clause = format!("({} OR {})", left_clause, right_clause);
-            left_types.append(&mut right_types);
-            (clause, left_types)
-        }
-        Node::Not(expr) => {
-            let (clause, types) = interpret_expression(expr, renames, types)?;
-            (format!("(NOT {})", clause), types)
-        }
-        Node::Equal(key, target) => (
-            format!("{} = ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(target)?,
-            ],
-        ),
-        Node::EqualCI(key, target) => (
-            format!("{} LIKE ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(target)?,
-            ],
-        ),
-        Node::Greater(key, target) => (
-            format!("{} > ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(target)?,
-            ],
-        ),
-        Node::Less(key, target) => (
-            format!("{} < ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(target)?,
-            ],
-        ),
-        Node::Wildcard(key, target) => (
-            format!("{} LIKE ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(&target.replace("*", "%").replace("?", "_"))?,
-            ],
-        ),
-        Node::Regex(key, target) => (
-            format!("{} = ?", renames.get(key).unwrap_or(key)),
-            vec![
-                types
-                    .get(key)
-                    .ok_or(Error::UnknownKey(key.to_string()))?
-                    .replace_and_return(target)?,
-            ],
-        ),
-        Node::Any(key, targets) => {
-            let sql = if targets.is_empty() {
-                "FALSE".to_string()
-            } else {
-                format!(
-                    "{} IN ({})",
-                    renames.get(key).unwrap_or(key),
-                    targets.iter().map(|_| "?").collect::<Vec<_>>().join(", ")
-                )
-            };
-            let mut binds = Vec::with_capacity(targets.len());
-            for target in targets.iter() {
-                binds.push(
-                    types
-                        .get(key)
-                        .ok_or(Error::UnknownKey(key.to_string()))?
-                        .replace_and_return(target)?,
-                );
-            }
-            (sql, binds)
-        }
-        Node::Null(key) => {
-            if !types.contains_key(key) {
-                return Err(Error::UnknownKey(key.to_string()));
-            }
-            (
-                format!("{} IS NULL", renames.get(key).unwrap_or(key)),
-                vec![],
-            )
-        }
-    })
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    interpret_into(expression, renames, types, &mut sql, &mut binds)?;
+    Ok((sql, binds))
 }
 
 pub fn interpret(
     expression: &Expression,
-    renames: &SqliteRenames,
-    types: &SqliteTypes,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
 ) -> Result<(String, Vec<SqliteType>)> {
     interpret_expression(expression, renames, types)
 }
+
+/// Renders the clause with every bind inlined as an SQL literal, for logging only.
+///
+/// This is the output of `interpret_check`. The literals are escaped on a best-effort basis, so
+/// do not execute it: always execute the clause from `interpret` with its binds.
+pub fn interpret_debug(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<String> {
+    interpret_check(expression, renames, types)
+}
+
+/// Renders the expression as a boolean SQL expression without binds, with every value inlined as
+/// a typed literal, e.g. `(age > 18 AND name <> 'it''s')`.
+///
+/// This is meant for DDL such as `CHECK (...)` constraints, where binds are not available. Use
+/// `interpret` for runtime queries.
+pub fn interpret_check(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<String> {
+    let mut sql = String::new();
+    Context {
+        renames,
+        types,
+        params: None,
+        options: &SqliteOptions::default(),
+        placeholders: Placeholders::Inline,
+        bind_keys: None,
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    }
+    .write(expression, &mut sql, &mut Vec::new())?;
+    Ok(sql)
+}
+
+/// Same as `interpret`, but emits indexed parameters (`?1`, `?2`, ...) and binds each distinct
+/// value only once, reusing its index wherever the value appears again.
+///
+/// The driver must support SQLite's `?NNN` parameters, binding the returned values by index.
+pub fn interpret_indexed(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<(String, Vec<SqliteType>)> {
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    Context {
+        renames,
+        types,
+        params: None,
+        options: &SqliteOptions::default(),
+        placeholders: Placeholders::Indexed,
+        bind_keys: None,
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    }
+    .write(expression, &mut sql, &mut binds)?;
+    Ok((sql, binds))
+}
+
+/// Same as `interpret`, but emits named parameters (`:p0`, `:p1`, ...) numbered in the order they
+/// appear, along with the name of each bind, e.g. for drivers binding by name or for logging.
+///
+/// Every placeholder gets its own name, even when a key or value appears several times.
+pub fn interpret_named(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<(String, Vec<(String, SqliteType)>)> {
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    Context {
+        renames,
+        types,
+        params: None,
+        options: &SqliteOptions::default(),
+        placeholders: Placeholders::Named,
+        bind_keys: None,
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    }
+    .write(expression, &mut sql, &mut binds)?;
+    let binds = binds
+        .into_iter()
+        .enumerate()
+        .map(|(i, bind)| (format!(":p{}", i), bind))
+        .collect();
+    Ok((sql, binds))
+}
+
+/// Same as `interpret`, but returns the key each bind comes from along with it, e.g. to log
+/// `age => Integer(Some(18))` when debugging a query.
+///
+/// A comparison may push several binds, such as both bounds of `@`, which then share its key.
+pub fn interpret_keyed(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<(String, Vec<(String, SqliteType)>)> {
+    interpret_keyed_with_options(expression, renames, types, &SqliteOptions::default())
+}
+
+/// Same as `interpret_keyed`, with `options` as in `interpret_with_options`.
+pub fn interpret_keyed_with_options(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+    options: &SqliteOptions,
+) -> Result<(String, Vec<(String, SqliteType)>)> {
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    let context = Context {
+        renames,
+        types,
+        params: None,
+        options,
+        placeholders: Placeholders::Positional,
+        bind_keys: Some(Default::default()),
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    };
+    context.write(expression, &mut sql, &mut binds)?;
+    let keys = context.bind_keys.unwrap_or_default().into_inner();
+    Ok((sql, keys.into_iter().zip(binds).collect()))
+}
+
+/// Same as `interpret`, resolving `{param}` lists of `Any` from `params`.
+pub fn interpret_with_params(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+    params: &SqliteParams,
+) -> Result<(String, Vec<SqliteType>)> {
+    interpret_with_options(
+        expression,
+        renames,
+        types,
+        params,
+        &SqliteOptions::default(),
+    )
+}
+
+pub fn interpret_with_options(
+    expression: &Expression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+    params: &SqliteParams,
+    options: &SqliteOptions,
+) -> Result<(String, Vec<SqliteType>)> {
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    Context {
+        renames,
+        types,
+        params: Some(params),
+        options,
+        placeholders: Placeholders::Positional,
+        bind_keys: None,
+        #[cfg(feature = "spans")]
+        leaves: Default::default(),
+    }
+    .write(expression, &mut sql, &mut binds)?;
+    Ok((sql, binds))
+}
+
+/// Same as `interpret`, wrapping errors in `Error::At` with the span of the offending comparison.
+#[cfg(feature = "spans")]
+pub fn interpret_spanned(
+    spanned: &SpannedExpression,
+    renames: &impl MapLike<String>,
+    types: &impl MapLike<SqliteType>,
+) -> Result<(String, Vec<SqliteType>)> {
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    let context = Context {
+        renames,
+        types,
+        params: None,
+        options: &SqliteOptions::default(),
+        placeholders: Placeholders::Positional,
+        bind_keys: None,
+        leaves: Default::default(),
+    };
+    match context.write(&spanned.expression, &mut sql, &mut binds) {
+        Ok(()) => Ok((sql, binds)),
+        Err(err) => match spanned.span(context.leaves.get()) {
+            Some(span) => Err(Error::At(span, Box::new(err))),
+            None => Err(err),
+        },
+    }
+}
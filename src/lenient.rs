@@ -0,0 +1,173 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Forgiving parsing for search boxes.
+//!
+//! `Expression::parse_lenient` corrects trivial mistakes before parsing and reports each
+//! correction as a `Fixup`, so that a UI can show how the query was interpreted.
+
+use std::fmt;
+
+use crate::{Error, Expression};
+
+/// A correction applied by `Expression::parse_lenient`. Offsets are byte offsets into the original
+/// input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fixup {
+    /// An unquoted word, e.g. `Bob` in `("name" = Bob)`, was quoted.
+    QuotedWord { offset: usize, word: String },
+    /// An `&`, `|` or `!` with nothing after it was dropped.
+    DroppedOperator { offset: usize, operator: char },
+    /// A `)` without a matching `(` was dropped.
+    DroppedParen { offset: usize },
+    /// A string missing its closing quote was closed at the end of the input.
+    ClosedQuote,
+    /// This many `)` were missing at the end of the input and appended.
+    ClosedParens(usize),
+}
+
+impl fmt::Display for Fixup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fixup::QuotedWord { offset, word } => write!(f, "quoted {:?} at {}", word, offset),
+            Fixup::DroppedOperator { offset, operator } => {
+                write!(f, "dropped trailing {:?} at {}", operator, offset)
+            }
+            Fixup::DroppedParen { offset } => write!(f, "dropped unmatched ')' at {}", offset),
+            Fixup::ClosedQuote => f.write_str("closed unterminated quote"),
+            Fixup::ClosedParens(count) => write!(f, "closed {} unclosed '('", count),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// An `&`, `|` or `!` with nothing after it yet: its offset, the operator, and the length of the
+/// fixed input before it.
+type Pending = Option<(usize, char, usize)>;
+
+fn drop_pending(pending: &mut Pending, fixed: &mut String, fixups: &mut Vec<Fixup>) {
+    if let Some((offset, operator, len)) = pending.take() {
+        fixed.truncate(len);
+        fixups.push(Fixup::DroppedOperator { offset, operator });
+    }
+}
+
+/// Rewrites `input` with the corrections applied.
+fn fix(input: &str) -> (String, Vec<Fixup>) {
+    let mut fixed = String::with_capacity(input.len());
+    let mut fixups = Vec::new();
+    let mut depth = 0;
+    let mut pending: Pending = None;
+
+    let mut chars = input.char_indices().peekable();
+    let mut in_string = false;
+    while let Some((i, c)) = chars.next() {
+        if in_string {
+            fixed.push(c);
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => fixed.push(escaped),
+                    // The closing quote must not be escaped.
+                    None => {
+                        fixed.pop();
+                    }
+                },
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            ' ' | '\t' => {
+                fixed.push(c);
+                continue;
+            }
+            ')' => {
+                drop_pending(&mut pending, &mut fixed, &mut fixups);
+                if depth == 0 {
+                    fixups.push(Fixup::DroppedParen { offset: i });
+                } else {
+                    depth -= 1;
+                    fixed.push(c);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        pending = None;
+        match c {
+            '"' => {
+                in_string = true;
+                fixed.push(c);
+            }
+            '(' => {
+                depth += 1;
+                fixed.push(c);
+            }
+            '{' => {
+                // A param name is not quoted.
+                fixed.push(c);
+                while let Some((_, c)) = chars.next_if(|&(_, c)| c != '}') {
+                    fixed.push(c);
+                }
+            }
+            '&' | '|' | '!' => {
+                let not_equal = c == '!' && chars.peek().is_some_and(|&(_, next)| next == '=');
+                if !not_equal {
+                    pending = Some((i, c, fixed.len()));
+                }
+                fixed.push(c);
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|&(_, c)| is_word_char(c)) {
+                    word.push(c);
+                }
+                fixed.push('"');
+                fixed.push_str(&word);
+                fixed.push('"');
+                fixups.push(Fixup::QuotedWord { offset: i, word });
+            }
+            c => fixed.push(c),
+        }
+    }
+    if in_string {
+        fixed.push('"');
+        fixups.push(Fixup::ClosedQuote);
+    }
+    drop_pending(&mut pending, &mut fixed, &mut fixups);
+    if depth > 0 {
+        fixed.push_str(&")".repeat(depth));
+        fixups.push(Fixup::ClosedParens(depth));
+    }
+    (fixed, fixups)
+}
+
+impl Expression {
+    /// Parses `input`, first correcting trivial mistakes: unquoted words are quoted, a trailing
+    /// `&`, `|` or `!` is dropped, unmatched `)` are dropped and missing ones appended, and an
+    /// unterminated string is closed.
+    ///
+    /// Returns the applied corrections along with the result, which is still an error if the
+    /// corrected input does not parse. A valid query needs no corrections.
+    pub fn parse_lenient(input: &str) -> (Result<Self, Error>, Vec<Fixup>) {
+        let (fixed, fixups) = fix(input);
+        (fixed.parse(), fixups)
+    }
+}
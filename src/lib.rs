@@ -16,46 +16,752 @@
 
 #![forbid(unsafe_code)]
 
+pub mod annotate;
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod describe;
+pub mod diff;
+pub mod geo;
 pub mod interpreter;
+pub mod lenient;
+pub mod lucene;
 mod parser;
+pub mod path;
+pub mod rpn;
+pub mod schema;
+pub mod wildcard;
 
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    hash::BuildHasher,
+    str::FromStr,
+};
 
-use parser::comparison::Comparison;
+use parser::comparison::{Comparison, Located};
 use parser::relation::Relation;
 
+/// Lookup by key, so that interpreters are not tied to a specific map type.
+pub trait MapLike<V> {
+    fn lookup(&self, key: &str) -> Option<&V>;
+}
+impl<V, S: BuildHasher> MapLike<V> for HashMap<String, V, S> {
+    fn lookup(&self, key: &str) -> Option<&V> {
+        self.get(key)
+    }
+}
+impl<V> MapLike<V> for BTreeMap<String, V> {
+    fn lookup(&self, key: &str) -> Option<&V> {
+        self.get(key)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Parser error {0}")]
-    Parser(String),
+    /// `offset` is the byte offset the input could not be parsed at, and `remaining` the input from
+    /// there on.
+    #[error("Parser error at {offset}: {message}")]
+    Parser {
+        offset: usize,
+        remaining: String,
+        message: String,
+    },
+    #[error("Empty list for any of {0}")]
+    EmptyAny(String),
+    #[error("Invalid path {0:?}")]
+    InvalidPath(Vec<usize>),
+    #[error("Query of {len} bytes exceeds the maximum of {max}")]
+    TooLong { len: usize, max: usize },
+    #[error("Malformed RPN: {0}")]
+    MalformedRpn(String),
+    /// The query uses an operator whose feature is disabled, e.g. `$` without a regex engine.
+    #[error("Feature {0} is disabled")]
+    FeatureDisabled(&'static str),
 }
 
-#[derive(Debug)]
+/// Default of `ParseOptions::max_depth`, also used by `FromStr` and the other parsers.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Limits and syntax options of `Expression::parse_with`.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Maximum length of the query in bytes, checked before parsing.
+    pub max_len: Option<usize>,
+    /// Maximum nesting depth of groups and negations. Deeper queries fail with a parser error
+    /// instead of overflowing the stack.
+    pub max_depth: usize,
+    /// Parse a lone string without key and operator, e.g. `("laptop")`, as `Node::FullText`.
+    /// Otherwise it is a parser error.
+    pub full_text: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_len: None,
+            max_depth: DEFAULT_MAX_DEPTH,
+            full_text: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "args", rename_all = "snake_case")
+)]
 pub enum Node {
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
     Not(Box<Expression>),
     Equal(String, String),
+    NotEqual(String, String),
     EqualCI(String, String),
     Greater(String, String),
     Less(String, String),
+    GreaterOrEqual(String, String),
+    LessOrEqual(String, String),
     Wildcard(String, String),
     Regex(String, String),
+    Fuzzy(String, String),
     Any(String, Vec<String>),
+    AnyParam(String, String),
+    /// Whether the list of values shares at least one element with the targets.
+    Intersects(String, Vec<String>),
     Null(String),
+    FullText(String),
+    /// Key, and the low and high bounds of an inclusive range.
+    Between(String, String, String),
+    /// Key, latitude and longitude of the center in degrees, and radius in meters.
+    GeoWithin(String, f64, f64, f64),
+    /// Compares the length of the value in characters.
+    Length(String, CmpOp, usize),
+    /// Matches everything, written `()`, e.g. the conjunction of no expressions.
+    MatchAll,
 }
 
-#[derive(Debug)]
+impl Node {
+    /// The key a comparison compares. `None` for relations, full-text terms and `MatchAll`.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Node::And(..) | Node::Or(..) | Node::Not(_) | Node::FullText(_) | Node::MatchAll => {
+                None
+            }
+            Node::Equal(key, _)
+            | Node::NotEqual(key, _)
+            | Node::EqualCI(key, _)
+            | Node::Greater(key, _)
+            | Node::Less(key, _)
+            | Node::GreaterOrEqual(key, _)
+            | Node::LessOrEqual(key, _)
+            | Node::Wildcard(key, _)
+            | Node::Regex(key, _)
+            | Node::Fuzzy(key, _)
+            | Node::Any(key, _)
+            | Node::AnyParam(key, _)
+            | Node::Intersects(key, _)
+            | Node::Null(key)
+            | Node::Between(key, ..)
+            | Node::GeoWithin(key, ..)
+            | Node::Length(key, ..) => Some(key),
+        }
+    }
+}
+
+/// An ordering comparison, used by `Node::Length`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CmpOp {
+    Equal,
+    Greater,
+    Less,
+    GreaterOrEqual,
+    LessOrEqual,
+}
+impl CmpOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CmpOp::Equal => "=",
+            CmpOp::Greater => ">",
+            CmpOp::Less => "<",
+            CmpOp::GreaterOrEqual => ">=",
+            CmpOp::LessOrEqual => "<=",
+        }
+    }
+
+    pub fn compare<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            CmpOp::Equal => left == right,
+            CmpOp::Greater => left > right,
+            CmpOp::Less => left < right,
+            CmpOp::GreaterOrEqual => left >= right,
+            CmpOp::LessOrEqual => left <= right,
+        }
+    }
+
+    /// The operator matching exactly when this one does not, if any.
+    pub fn negated(&self) -> Option<Self> {
+        match self {
+            CmpOp::Equal => None,
+            CmpOp::Greater => Some(CmpOp::LessOrEqual),
+            CmpOp::Less => Some(CmpOp::GreaterOrEqual),
+            CmpOp::GreaterOrEqual => Some(CmpOp::Less),
+            CmpOp::LessOrEqual => Some(CmpOp::Greater),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Expression {
     pub node: Node,
 }
 
+impl Expression {
+    pub fn and(self, other: Expression) -> Self {
+        Self {
+            node: Node::And(Box::new(self), Box::new(other)),
+        }
+    }
+
+    pub fn or(self, other: Expression) -> Self {
+        Self {
+            node: Node::Or(Box::new(self), Box::new(other)),
+        }
+    }
+
+    /// Negates the expression, i.e. `!(self)`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self {
+            node: Node::Not(Box::new(self)),
+        }
+    }
+
+    pub fn equal(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::Equal(key.into(), target.into()).into()
+    }
+
+    pub fn not_equal(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::NotEqual(key.into(), target.into()).into()
+    }
+
+    pub fn equal_ci(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::EqualCI(key.into(), target.into()).into()
+    }
+
+    pub fn greater(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::Greater(key.into(), target.into()).into()
+    }
+
+    pub fn less(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::Less(key.into(), target.into()).into()
+    }
+
+    pub fn greater_or_equal(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::GreaterOrEqual(key.into(), target.into()).into()
+    }
+
+    pub fn less_or_equal(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::LessOrEqual(key.into(), target.into()).into()
+    }
+
+    /// Builds `key @ [low, high]`, an inclusive range.
+    pub fn between(
+        key: impl Into<String>,
+        low: impl Into<String>,
+        high: impl Into<String>,
+    ) -> Self {
+        Node::Between(key.into(), low.into(), high.into()).into()
+    }
+
+    pub fn wildcard(key: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Node::Wildcard(key.into(), pattern.into()).into()
+    }
+
+    pub fn regex(key: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Node::Regex(key.into(), pattern.into()).into()
+    }
+
+    pub fn fuzzy(key: impl Into<String>, target: impl Into<String>) -> Self {
+        Node::Fuzzy(key.into(), target.into()).into()
+    }
+
+    /// Builds `key ? {param}`, to be resolved before interpreting.
+    pub fn any_param(key: impl Into<String>, param: impl Into<String>) -> Self {
+        Node::AnyParam(key.into(), param.into()).into()
+    }
+
+    pub fn intersects(
+        key: impl Into<String>,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Node::Intersects(key.into(), targets.into_iter().map(Into::into).collect()).into()
+    }
+
+    pub fn null(key: impl Into<String>) -> Self {
+        Node::Null(key.into()).into()
+    }
+
+    pub fn full_text(term: impl Into<String>) -> Self {
+        Node::FullText(term.into()).into()
+    }
+
+    /// Builds `key @ "lat,lon,radius"` with the radius in meters. The coordinates are not checked
+    /// like parsed ones.
+    pub fn geo_within(key: impl Into<String>, lat: f64, lon: f64, radius: f64) -> Self {
+        Node::GeoWithin(key.into(), lat, lon, radius).into()
+    }
+
+    /// Builds `key # op "length"`.
+    pub fn length(key: impl Into<String>, op: CmpOp, length: usize) -> Self {
+        Node::Length(key.into(), op, length).into()
+    }
+
+    /// Builds `()`, matching everything.
+    pub fn match_all() -> Self {
+        Node::MatchAll.into()
+    }
+
+    /// Folds all expressions into a left-leaning chain of `And`.
+    ///
+    /// Returns `match_all` for an empty input, since there is no constraint at all.
+    pub fn conjoin_all(expressions: impl IntoIterator<Item = Expression>) -> Self {
+        expressions
+            .into_iter()
+            .reduce(Self::and)
+            .unwrap_or_else(Self::match_all)
+    }
+
+    /// Removes double negations and normalizes chains of the same operator.
+    ///
+    /// `!!x` becomes `x`, and nested `And`s (or `Or`s) are rebuilt as a left-leaning chain of
+    /// their operands in their original order, e.g. `(a & (b & c))` becomes `((a & b) & c)`.
+    /// Simplifying is idempotent and does not change what the expression matches.
+    pub fn simplify(self) -> Self {
+        match self.node {
+            Node::Not(expression) => match expression.simplify() {
+                Expression {
+                    node: Node::Not(inner),
+                } => *inner,
+                expression => Self {
+                    node: Node::Not(Box::new(expression)),
+                },
+            },
+            Node::And(..) => {
+                let mut operands = Vec::new();
+                self.collect_operands(true, &mut operands);
+                operands.into_iter().reduce(Self::and).unwrap()
+            }
+            Node::Or(..) => {
+                let mut operands = Vec::new();
+                self.collect_operands(false, &mut operands);
+                operands.into_iter().reduce(Self::or).unwrap()
+            }
+            _ => self,
+        }
+    }
+
+    /// Collects the simplified operands of a chain of `And` (or `Or`), including chains revealed
+    /// by removing double negations.
+    fn collect_operands(self, and: bool, operands: &mut Vec<Self>) {
+        match self.node {
+            Node::And(left, right) if and => {
+                left.collect_operands(and, operands);
+                right.collect_operands(and, operands);
+            }
+            Node::Or(left, right) if !and => {
+                left.collect_operands(and, operands);
+                right.collect_operands(and, operands);
+            }
+            node => {
+                let simplified = Self { node }.simplify();
+                match simplified.node {
+                    Node::And(..) if and => simplified.collect_operands(and, operands),
+                    Node::Or(..) if !and => simplified.collect_operands(and, operands),
+                    _ => operands.push(simplified),
+                }
+            }
+        }
+    }
+
+    /// Removes double negations anywhere in the expression, e.g. `!!x` becomes `x`, keeping its
+    /// structure otherwise.
+    pub fn strip_redundant_not(self) -> Self {
+        let node = match self.node {
+            Node::Not(expression) => match expression.strip_redundant_not() {
+                Expression {
+                    node: Node::Not(inner),
+                } => return *inner,
+                expression => Node::Not(Box::new(expression)),
+            },
+            Node::And(left, right) => Node::And(
+                Box::new(left.strip_redundant_not()),
+                Box::new(right.strip_redundant_not()),
+            ),
+            Node::Or(left, right) => Node::Or(
+                Box::new(left.strip_redundant_not()),
+                Box::new(right.strip_redundant_not()),
+            ),
+            node => node,
+        };
+        Self { node }
+    }
+
+    /// Rewrites the expression with the fewest `Not`s reachable by removing double negations and
+    /// applying De Morgan's laws, e.g. `!(!a | !b)` becomes `(a & b)` and `(!a & !b)` becomes
+    /// `!(a | b)`.
+    ///
+    /// Unlike pushing negations down to the comparisons, a negation may be moved up to cover a
+    /// whole `And` or `Or`. When both forms have as many negations, the original operator is kept.
+    pub fn combine_not(self) -> Self {
+        self.combined().0
+    }
+
+    /// The fewest-negation forms of the expression and of its negation, with their numbers of
+    /// `Not`s.
+    fn combined(self) -> (Self, usize, Self, usize) {
+        let not = |expression: Self| Self {
+            node: Node::Not(Box::new(expression)),
+        };
+        let and = matches!(self.node, Node::And(..));
+        match self.node {
+            Node::Not(expression) => {
+                let (positive, positive_count, negative, negative_count) = expression.combined();
+                (negative, negative_count, positive, positive_count)
+            }
+            Node::And(left, right) | Node::Or(left, right) => {
+                let same = if and { Self::and } else { Self::or };
+                let dual = if and { Self::or } else { Self::and };
+                let (left_positive, left_positive_count, left_negative, left_negative_count) =
+                    left.combined();
+                let (right_positive, right_positive_count, right_negative, right_negative_count) =
+                    right.combined();
+                let direct = left_positive_count + right_positive_count;
+                let flipped = left_negative_count + right_negative_count;
+                let (positive, positive_count) = if direct <= flipped + 1 {
+                    (same(left_positive.clone(), right_positive.clone()), direct)
+                } else {
+                    (
+                        not(dual(left_negative.clone(), right_negative.clone())),
+                        flipped + 1,
+                    )
+                };
+                let (negative, negative_count) = if flipped < direct + 1 {
+                    (dual(left_negative, right_negative), flipped)
+                } else {
+                    (not(same(left_positive, right_positive)), direct + 1)
+                };
+                (positive, positive_count, negative, negative_count)
+            }
+            node => {
+                let expression = Self { node };
+                (expression.clone(), 0, not(expression), 1)
+            }
+        }
+    }
+
+    /// Pushes every negation down to the comparisons with De Morgan's laws, removing double
+    /// negations, e.g. `!(("a" = "1") | ("b" > "2"))` becomes `(("a" != "1") & ("b" <= "2"))`.
+    ///
+    /// `=` and `!=`, and `>` and `<=` (or `<` and `>=`), are inverted into each other, as are the
+    /// operators of length comparisons. Other comparisons stay wrapped in `Not`. The inversions
+    /// hold under SQL semantics, where comparing NULL matches neither way; a missing key matches a
+    /// negated comparison but not the inverted one in `evaluate`, as does NULL with
+    /// `null_inclusive_negation` in `sqlite`.
+    pub fn push_negations(self) -> Self {
+        match self.node {
+            Node::Not(expression) => expression.negate(),
+            Node::And(left, right) => left.push_negations().and(right.push_negations()),
+            Node::Or(left, right) => left.push_negations().or(right.push_negations()),
+            node => Self { node },
+        }
+    }
+
+    /// The negation of the expression with negations pushed down.
+    fn negate(self) -> Self {
+        let node = match self.node {
+            Node::Not(expression) => return expression.push_negations(),
+            Node::And(left, right) => return left.negate().or(right.negate()),
+            Node::Or(left, right) => return left.negate().and(right.negate()),
+            Node::Equal(key, target) => Node::NotEqual(key, target),
+            Node::NotEqual(key, target) => Node::Equal(key, target),
+            Node::Greater(key, target) => Node::LessOrEqual(key, target),
+            Node::Less(key, target) => Node::GreaterOrEqual(key, target),
+            Node::GreaterOrEqual(key, target) => Node::Less(key, target),
+            Node::LessOrEqual(key, target) => Node::Greater(key, target),
+            Node::Length(key, op, length) => match op.negated() {
+                Some(op) => Node::Length(key, op, length),
+                None => Node::Not(Box::new(Self {
+                    node: Node::Length(key, op, length),
+                })),
+            },
+            node => Node::Not(Box::new(Self { node })),
+        };
+        Self { node }
+    }
+
+    /// Builds `key ? [targets]`, rejecting an empty list since it would never match.
+    ///
+    /// Use `any_empty_ok` when an empty list is intended.
+    pub fn any(
+        key: impl Into<String>,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, Error> {
+        let key = key.into();
+        let targets = targets.into_iter().map(Into::into).collect::<Vec<_>>();
+        if targets.is_empty() {
+            return Err(Error::EmptyAny(key));
+        }
+        Ok(Self {
+            node: Node::Any(key, targets),
+        })
+    }
+
+    /// Same as `any`, but an empty list is accepted and matches nothing.
+    pub fn any_empty_ok(
+        key: impl Into<String>,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            node: Node::Any(key.into(), targets.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Subtree at `path`, where each index selects a child: 0 for the left and 1 for the right
+    /// side of `And`/`Or`, and 0 for the operand of `Not`. An empty path is the expression itself.
+    pub fn get_at(&self, path: &[usize]) -> Result<&Self, Error> {
+        let mut expression = self;
+        for &index in path {
+            expression = match (&expression.node, index) {
+                (Node::And(left, _) | Node::Or(left, _) | Node::Not(left), 0) => left,
+                (Node::And(_, right) | Node::Or(_, right), 1) => right,
+                _ => return Err(Error::InvalidPath(path.to_vec())),
+            };
+        }
+        Ok(expression)
+    }
+
+    fn get_at_mut(&mut self, path: &[usize]) -> Result<&mut Self, Error> {
+        let mut expression = self;
+        for &index in path {
+            expression = match (&mut expression.node, index) {
+                (Node::And(left, _) | Node::Or(left, _) | Node::Not(left), 0) => left,
+                (Node::And(_, right) | Node::Or(_, right), 1) => right,
+                _ => return Err(Error::InvalidPath(path.to_vec())),
+            };
+        }
+        Ok(expression)
+    }
+
+    /// Applies `f` to every key, stopping at the first error.
+    pub fn try_map_keys<E>(
+        mut self,
+        mut f: impl FnMut(&str) -> Result<String, E>,
+    ) -> Result<Self, E> {
+        self.try_map_keys_in_place(&mut f)?;
+        Ok(self)
+    }
+
+    /// Infallible version of `try_map_keys`.
+    pub fn map_keys(self, mut f: impl FnMut(&str) -> String) -> Self {
+        match self.try_map_keys(|key| Ok::<_, std::convert::Infallible>(f(key))) {
+            Ok(expression) => expression,
+            Err(never) => match never {},
+        }
+    }
+
+    fn try_map_keys_in_place<E>(
+        &mut self,
+        f: &mut impl FnMut(&str) -> Result<String, E>,
+    ) -> Result<(), E> {
+        match &mut self.node {
+            Node::And(left, right) | Node::Or(left, right) => {
+                left.try_map_keys_in_place(f)?;
+                right.try_map_keys_in_place(f)
+            }
+            Node::Not(expression) => expression.try_map_keys_in_place(f),
+            Node::FullText(_) | Node::MatchAll => Ok(()),
+            Node::Equal(key, _)
+            | Node::NotEqual(key, _)
+            | Node::Between(key, ..)
+            | Node::GeoWithin(key, ..)
+            | Node::Length(key, ..)
+            | Node::EqualCI(key, _)
+            | Node::Greater(key, _)
+            | Node::Less(key, _)
+            | Node::GreaterOrEqual(key, _)
+            | Node::LessOrEqual(key, _)
+            | Node::Wildcard(key, _)
+            | Node::Regex(key, _)
+            | Node::Fuzzy(key, _)
+            | Node::Any(key, _)
+            | Node::AnyParam(key, _)
+            | Node::Intersects(key, _)
+            | Node::Null(key) => {
+                *key = f(key)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Every distinct key the expression compares, e.g. to reject unknown fields before
+    /// interpreting it. Full-text terms have no key.
+    pub fn keys(&self) -> BTreeSet<String> {
+        let mut keys = BTreeSet::new();
+        self.collect_keys(&mut keys);
+        keys
+    }
+
+    /// Number of levels of the tree, e.g. to reject overly complex queries. A single comparison
+    /// has depth 1, and each `And`, `Or` and `Not` adds one level above its deepest operand.
+    pub fn depth(&self) -> usize {
+        match &self.node {
+            Node::And(left, right) | Node::Or(left, right) => 1 + left.depth().max(right.depth()),
+            Node::Not(expression) => 1 + expression.depth(),
+            _ => 1,
+        }
+    }
+
+    /// Number of nodes in the tree, counting `And`, `Or` and `Not` as well as comparisons.
+    pub fn node_count(&self) -> usize {
+        match &self.node {
+            Node::And(left, right) | Node::Or(left, right) => {
+                1 + left.node_count() + right.node_count()
+            }
+            Node::Not(expression) => 1 + expression.node_count(),
+            _ => 1,
+        }
+    }
+
+    /// Every comparison of the expression from left to right, skipping `And`, `Or`, `Not` and
+    /// `MatchAll`, e.g. to highlight the terms of a query.
+    pub fn leaves(&self) -> impl Iterator<Item = &Node> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            while let Some(expression) = stack.pop() {
+                match &expression.node {
+                    Node::And(left, right) | Node::Or(left, right) => {
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                    Node::Not(expression) => stack.push(expression),
+                    Node::MatchAll => {}
+                    node => return Some(node),
+                }
+            }
+            None
+        })
+    }
+
+    /// Walks the expression with `visitor`, see `Visitor`.
+    pub fn accept(&self, visitor: &mut impl Visitor) {
+        visitor.visit_expression(self);
+    }
+
+    fn collect_keys(&self, keys: &mut BTreeSet<String>) {
+        match &self.node {
+            Node::And(left, right) | Node::Or(left, right) => {
+                left.collect_keys(keys);
+                right.collect_keys(keys);
+            }
+            Node::Not(expression) => expression.collect_keys(keys),
+            node => {
+                if let Some(key) = node.key()
+                    && !keys.contains(key)
+                {
+                    keys.insert(key.to_string());
+                }
+            }
+        }
+    }
+
+    /// The finite set of values `key` can take for the expression to match, e.g. to route a query
+    /// to the shards of those shard keys.
+    ///
+    /// Only `Equal` and `Any` constrain a key, intersected under `And` and united under `Or`.
+    /// `None` means any value may match, e.g. for ranges, negations, or an `Or` with a branch not
+    /// constraining `key`.
+    pub fn matched_values(&self, key: &str) -> Option<HashSet<String>> {
+        match &self.node {
+            Node::And(left, right) => match (left.matched_values(key), right.matched_values(key)) {
+                (Some(left), Some(right)) => Some(left.intersection(&right).cloned().collect()),
+                (Some(values), None) | (None, Some(values)) => Some(values),
+                (None, None) => None,
+            },
+            Node::Or(left, right) => {
+                let mut values = left.matched_values(key)?;
+                values.extend(right.matched_values(key)?);
+                Some(values)
+            }
+            Node::Equal(k, target) if k == key => Some(HashSet::from([target.clone()])),
+            Node::Any(k, targets) if k == key => Some(targets.iter().cloned().collect()),
+            _ => None,
+        }
+    }
+
+    /// The `Equal` comparisons every match must satisfy, as `(key, target)` pairs in their order in
+    /// the expression, e.g. to test against a bloom filter before evaluating.
+    ///
+    /// Only comparisons reachable through `And`s are included. Those under an `Or` or a `Not` do
+    /// not need to hold for a match, so they are left out.
+    pub fn equality_constraints(&self) -> Vec<(String, String)> {
+        let mut constraints = Vec::new();
+        self.collect_equality_constraints(&mut constraints);
+        constraints
+    }
+
+    fn collect_equality_constraints(&self, constraints: &mut Vec<(String, String)>) {
+        match &self.node {
+            Node::And(left, right) => {
+                left.collect_equality_constraints(constraints);
+                right.collect_equality_constraints(constraints);
+            }
+            Node::Equal(key, target) => constraints.push((key.clone(), target.clone())),
+            _ => {}
+        }
+    }
+
+    /// Replaces the subtree at `path` (see `get_at`) with `new`.
+    pub fn replace_at(mut self, path: &[usize], new: Expression) -> Result<Self, Error> {
+        *self.get_at_mut(path)? = new;
+        Ok(self)
+    }
+}
+
+impl From<Node> for Expression {
+    fn from(node: Node) -> Self {
+        Self { node }
+    }
+}
+
 impl From<Comparison> for Expression {
     fn from(c: Comparison) -> Self {
         match c {
             Comparison::IsEqual(c) => Self {
                 node: Node::Equal(c.left.0, c.right.0),
             },
+            Comparison::IsWithin(c) => Self {
+                node: Node::GeoWithin(c.left.0, c.right.lat, c.right.lon, c.right.radius),
+            },
+            Comparison::IsBetween(c) => Self {
+                node: Node::Between(c.left.0, c.right.low, c.right.high),
+            },
+            Comparison::IsLength(c) => Self {
+                node: Node::Length(c.left.0, c.op, c.right.0),
+            },
+            Comparison::IsNotEqual(c) => Self {
+                node: Node::NotEqual(c.left.0, c.right.0),
+            },
             Comparison::IsEqualCI(c) => Self {
                 node: Node::EqualCI(c.left.0, c.right.0),
             },
@@ -65,25 +771,50 @@ impl From<Comparison> for Expression {
             Comparison::IsLess(c) => Self {
                 node: Node::Less(c.left.0, c.right.0),
             },
+            Comparison::IsGreaterOrEqual(c) => Self {
+                node: Node::GreaterOrEqual(c.left.0, c.right.0),
+            },
+            Comparison::IsLessOrEqual(c) => Self {
+                node: Node::LessOrEqual(c.left.0, c.right.0),
+            },
             Comparison::IsWildcard(c) => Self {
                 node: Node::Wildcard(c.left.0, c.right.0),
             },
             Comparison::IsRegex(c) => Self {
                 node: Node::Regex(c.left.0, c.right.0),
             },
+            Comparison::IsFuzzy(c) => Self {
+                node: Node::Fuzzy(c.left.0, c.right.0),
+            },
             Comparison::IsAny(c) => Self {
                 node: Node::Any(c.left.0, c.right.0),
             },
+            Comparison::IsAnyParam(c) => Self {
+                node: Node::AnyParam(c.left.0, c.right.0),
+            },
+            Comparison::IsIntersects(c) => Self {
+                node: Node::Intersects(c.left.0, c.right.0),
+            },
             Comparison::IsNull(c) => Self {
                 node: Node::Null(c.0.0),
             },
+            Comparison::IsFullText(c) => Self {
+                node: Node::FullText(c.0.0),
+            },
         }
     }
 }
 
+impl From<Located> for Expression {
+    fn from(l: Located) -> Self {
+        l.comparison.into()
+    }
+}
+
 impl From<Box<Relation>> for Expression {
     fn from(relation: Box<Relation>) -> Self {
         match *relation {
+            Relation::All => Expression::match_all(),
             Relation::C(c) => c.into(),
             Relation::Rar { left, right } => Self {
                 node: Node::And(Box::new(left.into()), Box::new(right.into())),
@@ -123,9 +854,346 @@ impl FromStr for Expression {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(parser::relation::relation(s)
-            .map_err(|err| Error::Parser(err.to_string()))?
-            .1
-            .into())
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl Expression {
+    /// Same as `parse`, rejecting queries exceeding the limits of `options`.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, Error> {
+        if let Some(max) = options.max_len
+            && s.len() > max
+        {
+            return Err(Error::TooLong { len: s.len(), max });
+        }
+        let expression = parse_relation(s, options)?.into();
+        check_features(&expression)?;
+        Ok(expression)
+    }
+}
+
+/// Rejects regexes when no regex engine is enabled, since they could never match.
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+fn check_features(expression: &Expression) -> Result<(), Error> {
+    struct HasRegex(bool);
+    impl Visitor for HasRegex {
+        fn visit_regex(&mut self, _key: &str, _target: &str) {
+            self.0 = true;
+        }
+    }
+
+    let mut visitor = HasRegex(false);
+    expression.accept(&mut visitor);
+    if visitor.0 {
+        return Err(Error::FeatureDisabled("regex"));
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+fn check_features(_expression: &Expression) -> Result<(), Error> {
+    Ok(())
+}
+
+impl Error {
+    fn parser_at(input: &str, offset: usize, message: impl Into<String>) -> Self {
+        Self::Parser {
+            offset,
+            remaining: input[offset..].to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses `input`, reporting an error at the furthest position any alternative failed at.
+fn parse_relation(input: &str, options: &ParseOptions) -> Result<Box<Relation>, Error> {
+    let ((result, too_deep), furthest) = parser::furthest_failure(|| {
+        parser::with_full_text(options.full_text, || {
+            parser::limit_depth(options.max_depth, || parser::relation::search(input))
+        })
+    });
+    if let Some(remaining) = too_deep {
+        return Err(Error::parser_at(
+            input,
+            input.len() - remaining,
+            "max depth exceeded",
+        ));
+    }
+    let remaining = match result {
+        Ok((_, relation)) => return Ok(relation),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => err.input.len(),
+        Err(nom::Err::Incomplete(_)) => 0,
+    };
+    let offset = input.len() - furthest.unwrap_or(remaining);
+    let message = match input[offset..].chars().next() {
+        Some(c) => format!("unexpected {:?}", c),
+        None => "unexpected end of input".to_string(),
+    };
+    Err(Error::parser_at(input, offset, message))
+}
+
+/// Writes `s` as a quoted string, escaping `\`, `"` and line breaks and tabs.
+fn write_text(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '\\' | '"' => write!(f, "\\{}", c)?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Renders the node in the query syntax, parsing back into the same expression. Every relation
+/// and comparison is wrapped in parentheses, e.g. `(("a" = "1") & !("b" -))`.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (key, operator, target) = match self {
+            Node::And(left, right) => return write!(f, "({} & {})", left, right),
+            Node::Or(left, right) => return write!(f, "({} | {})", left, right),
+            Node::Not(expression) => return write!(f, "!{}", expression),
+            Node::MatchAll => return f.write_str("()"),
+            Node::Equal(key, target) => (key, "=", target),
+            Node::NotEqual(key, target) => (key, "!=", target),
+            Node::EqualCI(key, target) => (key, "~", target),
+            Node::Greater(key, target) => (key, ">", target),
+            Node::Less(key, target) => (key, "<", target),
+            Node::GreaterOrEqual(key, target) => (key, ">=", target),
+            Node::LessOrEqual(key, target) => (key, "<=", target),
+            Node::Wildcard(key, target) => (key, "*", target),
+            Node::Regex(key, target) => (key, "$", target),
+            Node::Fuzzy(key, target) => (key, "%", target),
+            Node::Any(key, targets) | Node::Intersects(key, targets) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                if matches!(self, Node::Any(..)) {
+                    f.write_str(" ? [")?;
+                } else {
+                    f.write_str(" ^ [")?;
+                }
+                for (i, target) in targets.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_text(f, target)?;
+                }
+                return f.write_str("])");
+            }
+            Node::AnyParam(key, param) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                return write!(f, " ? {{{}}})", param);
+            }
+            Node::Null(key) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                return f.write_str(" -)");
+            }
+            Node::FullText(term) => {
+                f.write_str("(")?;
+                write_text(f, term)?;
+                return f.write_str(")");
+            }
+            Node::Between(key, low, high) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                f.write_str(" @ [")?;
+                write_text(f, low)?;
+                f.write_str(", ")?;
+                write_text(f, high)?;
+                return f.write_str("])");
+            }
+            Node::GeoWithin(key, lat, lon, radius) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                return write!(f, " @ \"{},{},{}\")", lat, lon, radius);
+            }
+            Node::Length(key, op, length) => {
+                f.write_str("(")?;
+                write_text(f, key)?;
+                return write!(f, " # {} \"{}\")", op.symbol(), length);
+            }
+        };
+        f.write_str("(")?;
+        write_text(f, key)?;
+        write!(f, " {} ", operator)?;
+        write_text(f, target)?;
+        f.write_str(")")
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+/// Walks an expression tree. `visit_node` recurses into `And`, `Or` and `Not` and dispatches
+/// comparisons to their hook, all of which do nothing by default, so a visitor only overrides what
+/// it is interested in. An overridden `visit_and`, `visit_or` or `visit_not` decides itself whether
+/// to recurse, e.g. with `walk_expression`.
+pub trait Visitor {
+    fn visit_expression(&mut self, expression: &Expression) {
+        self.visit_node(&expression.node);
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    fn visit_and(&mut self, left: &Expression, right: &Expression) {
+        self.visit_expression(left);
+        self.visit_expression(right);
+    }
+
+    fn visit_or(&mut self, left: &Expression, right: &Expression) {
+        self.visit_expression(left);
+        self.visit_expression(right);
+    }
+
+    fn visit_not(&mut self, expression: &Expression) {
+        self.visit_expression(expression);
+    }
+
+    fn visit_equal(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_not_equal(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_equal_ci(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_greater(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_less(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_greater_or_equal(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_less_or_equal(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_wildcard(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_regex(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_fuzzy(&mut self, _key: &str, _target: &str) {}
+
+    fn visit_any(&mut self, _key: &str, _targets: &[String]) {}
+
+    fn visit_any_param(&mut self, _key: &str, _param: &str) {}
+
+    fn visit_intersects(&mut self, _key: &str, _targets: &[String]) {}
+
+    fn visit_null(&mut self, _key: &str) {}
+
+    fn visit_full_text(&mut self, _term: &str) {}
+
+    fn visit_between(&mut self, _key: &str, _low: &str, _high: &str) {}
+
+    fn visit_geo_within(&mut self, _key: &str, _lat: f64, _lon: f64, _radius: f64) {}
+
+    fn visit_length(&mut self, _key: &str, _op: CmpOp, _length: usize) {}
+
+    fn visit_match_all(&mut self) {}
+}
+
+/// The default traversal of `Visitor::visit_expression`.
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    walk_node(visitor, &expression.node);
+}
+
+/// The default traversal of `Visitor::visit_node`, calling the hook of the node.
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    match node {
+        Node::And(left, right) => visitor.visit_and(left, right),
+        Node::Or(left, right) => visitor.visit_or(left, right),
+        Node::Not(expression) => visitor.visit_not(expression),
+        Node::Equal(key, target) => visitor.visit_equal(key, target),
+        Node::NotEqual(key, target) => visitor.visit_not_equal(key, target),
+        Node::EqualCI(key, target) => visitor.visit_equal_ci(key, target),
+        Node::Greater(key, target) => visitor.visit_greater(key, target),
+        Node::Less(key, target) => visitor.visit_less(key, target),
+        Node::GreaterOrEqual(key, target) => visitor.visit_greater_or_equal(key, target),
+        Node::LessOrEqual(key, target) => visitor.visit_less_or_equal(key, target),
+        Node::Wildcard(key, target) => visitor.visit_wildcard(key, target),
+        Node::Regex(key, target) => visitor.visit_regex(key, target),
+        Node::Fuzzy(key, target) => visitor.visit_fuzzy(key, target),
+        Node::Any(key, targets) => visitor.visit_any(key, targets),
+        Node::AnyParam(key, param) => visitor.visit_any_param(key, param),
+        Node::Intersects(key, targets) => visitor.visit_intersects(key, targets),
+        Node::Null(key) => visitor.visit_null(key),
+        Node::FullText(term) => visitor.visit_full_text(term),
+        Node::Between(key, low, high) => visitor.visit_between(key, low, high),
+        Node::GeoWithin(key, lat, lon, radius) => {
+            visitor.visit_geo_within(key, *lat, *lon, *radius)
+        }
+        Node::Length(key, op, length) => visitor.visit_length(key, *op, *length),
+        Node::MatchAll => visitor.visit_match_all(),
+    }
+}
+
+/// Byte range of a comparison inside the parsed input.
+#[cfg(feature = "spans")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// An expression together with the spans of its comparisons, in left-to-right order.
+#[cfg(feature = "spans")]
+#[derive(Debug)]
+pub struct SpannedExpression {
+    pub expression: Expression,
+    pub spans: Vec<Span>,
+}
+
+#[cfg(feature = "spans")]
+impl SpannedExpression {
+    fn collect_spans(relation: &Relation, total: usize, spans: &mut Vec<Span>) {
+        let span = |l: &Located| Span {
+            start: total - l.remaining.0,
+            end: total - l.remaining.1,
+        };
+        match relation {
+            Relation::All => {}
+            Relation::C(c) | Relation::NC(c) => spans.push(span(c)),
+            Relation::Rar { left, right } | Relation::Ror { left, right } => {
+                Self::collect_spans(left, total, spans);
+                Self::collect_spans(right, total, spans);
+            }
+            Relation::Rac { left, right } | Relation::Roc { left, right } => {
+                Self::collect_spans(left, total, spans);
+                spans.push(span(right));
+            }
+            Relation::Car { left, right } | Relation::Cor { left, right } => {
+                spans.push(span(left));
+                Self::collect_spans(right, total, spans);
+            }
+            Relation::Cac { left, right } | Relation::Coc { left, right } => {
+                spans.push(span(left));
+                spans.push(span(right));
+            }
+            Relation::NR(r) => Self::collect_spans(r, total, spans),
+        }
+    }
+
+    /// Span of the `index`-th comparison in left-to-right order.
+    pub fn span(&self, index: usize) -> Option<Span> {
+        self.spans.get(index).copied()
+    }
+}
+
+#[cfg(feature = "spans")]
+impl FromStr for SpannedExpression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let relation = parse_relation(s, &ParseOptions::default())?;
+        let mut spans = Vec::new();
+        Self::collect_spans(&relation, s.len(), &mut spans);
+        let expression = relation.into();
+        check_features(&expression)?;
+        Ok(Self { expression, spans })
     }
 }
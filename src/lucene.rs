@@ -0,0 +1,340 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Conversion of a subset of the Lucene query syntax into an `Expression`.
+//!
+//! Supported are:
+//!
+//! * `field:value` as `=`, or as `*` when the value contains an unescaped `*` or `?`.
+//! * `field:"a phrase"` as `=`, and `field:/regex/` as `$` anchored as `^(?:regex)$`, since a
+//!   Lucene regex matches the whole term.
+//! * `field:[a TO b]` for inclusive and `field:{a TO b}` for exclusive ranges, which may be mixed
+//!   (`[a TO b}`) and open with `*`.
+//! * `field:*` and `field:[* TO *]`, matching any value that is not null.
+//! * Terms without a field as full-text terms.
+//! * `AND`/`&&`, `OR`/`||`, `NOT`/`!`/`-` and parentheses, where `NOT` binds tighter than `AND`,
+//!   which binds tighter than `OR`. Clauses without an operator in between are joined with `AND`.
+//!
+//! A backslash escapes any character of a value. Boosts, fuzziness (`~`), proximity and `+` are
+//! not supported.
+
+use crate::{DEFAULT_MAX_DEPTH, Error, Expression, Node};
+
+/// Converts a Lucene query into an equivalent expression.
+pub fn from_lucene(input: &str) -> Result<Expression, Error> {
+    let mut cursor = Cursor {
+        input,
+        position: 0,
+        depth: 0,
+    };
+    let expression = cursor.or()?;
+    cursor.space();
+    if cursor.position < input.len() {
+        return Err(cursor.error("unexpected input"));
+    }
+    Ok(expression)
+}
+
+const SPECIAL: &[char] = &['(', ')', '[', ']', '{', '}', ':', '"', '/'];
+
+struct Word {
+    text: String,
+    /// The text as a wildcard pattern, keeping escaped `*`, `?` and `\` escaped.
+    pattern: String,
+    wildcard: bool,
+}
+
+struct Cursor<'i> {
+    input: &'i str,
+    position: usize,
+    /// Nesting depth of groups and negations, at most `DEFAULT_MAX_DEPTH`.
+    depth: usize,
+}
+
+impl<'i> Cursor<'i> {
+    fn rest(&self) -> &'i str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn space(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.position += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Eats a keyword only if it is followed by a delimiter, so that `ORDER` is not `OR`.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let rest = self.rest();
+        let delimited = rest.strip_prefix(keyword).is_some_and(|after| {
+            after
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || c == '(')
+        });
+        if delimited {
+            self.position += keyword.len();
+        }
+        delimited
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::parser_at(self.input, self.position, message)
+    }
+
+    fn or(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.and()?;
+        loop {
+            self.space();
+            if self.keyword("OR") || self.eat("||") {
+                expression = expression.or(self.and()?);
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn and(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.unary()?;
+        loop {
+            self.space();
+            if self.keyword("AND") || self.eat("&&") {
+                expression = expression.and(self.unary()?);
+            } else if self.peek().is_none_or(|c| c == ')')
+                || self.rest().starts_with("||")
+                || self.keyword_ahead("OR")
+            {
+                return Ok(expression);
+            } else {
+                expression = expression.and(self.unary()?);
+            }
+        }
+    }
+
+    fn keyword_ahead(&mut self, keyword: &str) -> bool {
+        let position = self.position;
+        let found = self.keyword(keyword);
+        self.position = position;
+        found
+    }
+
+    fn unary(&mut self) -> Result<Expression, Error> {
+        self.space();
+        if self.keyword("NOT") || self.eat("!") || self.eat("-") {
+            return Ok(not(self.nested(Self::unary)?));
+        }
+        if self.eat("(") {
+            let expression = self.nested(Self::or)?;
+            self.space();
+            if !self.eat(")") {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(expression);
+        }
+        self.clause()
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Expression, Error>,
+    ) -> Result<Expression, Error> {
+        if self.depth == DEFAULT_MAX_DEPTH {
+            return Err(self.error("max depth exceeded"));
+        }
+        self.depth += 1;
+        let expression = parse(self);
+        self.depth -= 1;
+        expression
+    }
+
+    fn clause(&mut self) -> Result<Expression, Error> {
+        if self.peek() == Some('"') {
+            return Ok(leaf(Node::FullText(self.phrase()?)));
+        }
+        let word = self.word()?;
+        if !self.eat(":") {
+            if word.wildcard {
+                return Err(self.error("wildcards need a field"));
+            }
+            return Ok(leaf(Node::FullText(word.text)));
+        }
+        let field = word.text;
+        match self.peek() {
+            Some('"') => Ok(leaf(Node::Equal(field, self.phrase()?))),
+            Some('/') if cfg!(any(feature = "regex", feature = "fancy-regex")) => {
+                Ok(leaf(Node::Regex(field, format!("^(?:{})$", self.regex()?))))
+            }
+            Some('/') => Err(Error::FeatureDisabled("regex")),
+            Some('[' | '{') => self.range(field),
+            _ => {
+                let value = self.word()?;
+                if value.text == "*" && value.wildcard {
+                    Ok(exists(field))
+                } else if value.wildcard {
+                    Ok(leaf(Node::Wildcard(field, value.pattern)))
+                } else {
+                    Ok(leaf(Node::Equal(field, value.text)))
+                }
+            }
+        }
+    }
+
+    fn range(&mut self, field: String) -> Result<Expression, Error> {
+        let lower_inclusive = self.eat("[");
+        if !lower_inclusive {
+            self.eat("{");
+        }
+        self.space();
+        let lower = self.bound()?;
+        self.space();
+        if !self.keyword("TO") {
+            return Err(self.error("expected TO"));
+        }
+        self.space();
+        let upper = self.bound()?;
+        self.space();
+        let upper_inclusive = if self.eat("]") {
+            true
+        } else if self.eat("}") {
+            false
+        } else {
+            return Err(self.error("expected ']' or '}'"));
+        };
+        let bound = |value: String, inclusive: bool, greater: bool| {
+            leaf(match (greater, inclusive) {
+                (true, true) => Node::GreaterOrEqual(field.clone(), value),
+                (true, false) => Node::Greater(field.clone(), value),
+                (false, true) => Node::LessOrEqual(field.clone(), value),
+                (false, false) => Node::Less(field.clone(), value),
+            })
+        };
+        let lower = lower.map(|value| bound(value, lower_inclusive, true));
+        let upper = upper.map(|value| bound(value, upper_inclusive, false));
+        if lower.is_none() && upper.is_none() {
+            return Ok(exists(field));
+        }
+        Ok(Expression::conjoin_all(lower.into_iter().chain(upper)))
+    }
+
+    /// A range bound, `None` for `*`.
+    fn bound(&mut self) -> Result<Option<String>, Error> {
+        if self.peek() == Some('"') {
+            return self.phrase().map(Some);
+        }
+        let word = self.word()?;
+        if word.text == "*" && word.wildcard {
+            Ok(None)
+        } else if word.wildcard {
+            Err(self.error("wildcards are not allowed in ranges"))
+        } else {
+            Ok(Some(word.text))
+        }
+    }
+
+    fn word(&mut self) -> Result<Word, Error> {
+        let mut word = Word {
+            text: String::new(),
+            pattern: String::new(),
+            wildcard: false,
+        };
+        let mut chars = self.rest().char_indices();
+        let mut end = self.rest().len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => {
+                        if matches!(escaped, '*' | '?' | '\\') {
+                            word.pattern.push('\\');
+                        }
+                        word.text.push(escaped);
+                        word.pattern.push(escaped);
+                    }
+                    None => return Err(self.error("dangling escape")),
+                },
+                c if c.is_whitespace() || SPECIAL.contains(&c) => {
+                    end = i;
+                    break;
+                }
+                c => {
+                    word.wildcard |= c == '*' || c == '?';
+                    word.text.push(c);
+                    word.pattern.push(c);
+                }
+            }
+        }
+        if end == 0 {
+            return Err(self.error("expected a term"));
+        }
+        self.position += end;
+        Ok(word)
+    }
+
+    fn delimited(&mut self, delimiter: char) -> Result<String, Error> {
+        self.eat(&delimiter.to_string());
+        let mut text = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    // Backslashes are kept in regexes, where they are part of the pattern.
+                    Some((_, escaped)) if delimiter == '/' && escaped != '/' => {
+                        text.push('\\');
+                        text.push(escaped);
+                    }
+                    Some((_, escaped)) => text.push(escaped),
+                    None => break,
+                },
+                c if c == delimiter => {
+                    self.position += i + c.len_utf8();
+                    return Ok(text);
+                }
+                c => text.push(c),
+            }
+        }
+        Err(self.error(&format!("unterminated {}", delimiter)))
+    }
+
+    fn phrase(&mut self) -> Result<String, Error> {
+        self.delimited('"')
+    }
+
+    fn regex(&mut self) -> Result<String, Error> {
+        self.delimited('/')
+    }
+}
+
+fn leaf(node: Node) -> Expression {
+    Expression { node }
+}
+
+fn not(expression: Expression) -> Expression {
+    leaf(Node::Not(Box::new(expression)))
+}
+
+fn exists(field: String) -> Expression {
+    not(leaf(Node::Null(field)))
+}
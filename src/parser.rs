@@ -17,3 +17,106 @@
 pub mod atom;
 pub mod comparison;
 pub mod relation;
+
+use std::cell::Cell;
+
+thread_local! {
+    /// Length of the shortest remaining input any parser failed at during `furthest_failure`.
+    static FURTHEST: Cell<usize> = const { Cell::new(usize::MAX) };
+    /// How many more relations may be nested during `limit_depth`.
+    static DEPTH_LEFT: Cell<usize> = const { Cell::new(usize::MAX) };
+    /// Remaining length of the input where `DEPTH_LEFT` ran out.
+    static TOO_DEEP: Cell<Option<usize>> = const { Cell::new(None) };
+    /// Whether a lone string parses as a full-text term during `with_full_text`.
+    static FULL_TEXT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `parse`, also returning the remaining length at the furthest position any parser failed
+/// at, including alternatives that were eventually discarded. When the whole parse fails, that is
+/// where the input most likely went wrong.
+pub fn furthest_failure<T>(parse: impl FnOnce() -> T) -> (T, Option<usize>) {
+    FURTHEST.set(usize::MAX);
+    let result = parse();
+    let furthest = FURTHEST.replace(usize::MAX);
+    (result, (furthest != usize::MAX).then_some(furthest))
+}
+
+/// Runs `parse` with relations nested at most `max_depth` deep. Beyond that, the parse fails as a
+/// whole, also returning the remaining length where it went too deep.
+pub fn limit_depth<T>(max_depth: usize, parse: impl FnOnce() -> T) -> (T, Option<usize>) {
+    let depth_left = DEPTH_LEFT.replace(max_depth);
+    TOO_DEEP.set(None);
+    let result = parse();
+    DEPTH_LEFT.set(depth_left);
+    (result, TOO_DEEP.take())
+}
+
+/// Runs `parse` with lone strings parsing as full-text terms if `enabled`.
+pub fn with_full_text<T>(enabled: bool, parse: impl FnOnce() -> T) -> T {
+    let full_text = FULL_TEXT.replace(enabled);
+    let result = parse();
+    FULL_TEXT.set(full_text);
+    result
+}
+
+/// Whether lone strings parse as full-text terms, see `with_full_text`.
+pub fn full_text_enabled() -> bool {
+    FULL_TEXT.get()
+}
+
+/// Runs `parse` one level deeper, failing without backtracking when the depth is exhausted.
+pub fn nested<'a, O>(
+    input: &'a str,
+    parse: impl FnOnce(&'a str) -> IResult<&'a str, O>,
+) -> IResult<&'a str, O> {
+    let depth_left = DEPTH_LEFT.get();
+    if depth_left == 0 {
+        TOO_DEEP.set(Some(input.len()));
+        return Err(nom::Err::Failure(Error::new(input)));
+    }
+    DEPTH_LEFT.set(depth_left - 1);
+    let result = parse(input);
+    DEPTH_LEFT.set(depth_left);
+    result
+}
+
+/// Runs `parse` as a single token. If it succeeds, failures inside it are not recorded for
+/// `furthest_failure`, since they only mark where the token ended, e.g. the closing quote of a
+/// text. The token may still be rejected as a whole, e.g. a text that is not a valid number.
+pub fn token<I, O>(parse: impl FnOnce() -> IResult<I, O>) -> IResult<I, O> {
+    let furthest = FURTHEST.get();
+    let result = parse();
+    if result.is_ok() {
+        FURTHEST.set(furthest);
+    }
+    result
+}
+
+/// A parser error, recording its position for `furthest_failure`.
+#[derive(Debug)]
+pub struct Error<I> {
+    pub input: I,
+}
+impl<I: nom::Input> Error<I> {
+    fn new(input: I) -> Self {
+        let len = input.input_len();
+        FURTHEST.set(FURTHEST.get().min(len));
+        Self { input }
+    }
+}
+impl<I: nom::Input> nom::error::ParseError<I> for Error<I> {
+    fn from_error_kind(input: I, _: nom::error::ErrorKind) -> Self {
+        Self::new(input)
+    }
+
+    fn append(_: I, _: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+impl<I: nom::Input, E> nom::error::FromExternalError<I, E> for Error<I> {
+    fn from_external_error(input: I, _: nom::error::ErrorKind, _: E) -> Self {
+        Self::new(input)
+    }
+}
+
+pub type IResult<I, O> = nom::IResult<I, O, Error<I>>;
@@ -17,15 +17,17 @@
 use std::ops::Deref;
 
 use nom::{
-    IResult, Parser,
+    Parser,
     branch::alt,
-    bytes::complete::{escaped, tag},
-    character::complete::{none_of, one_of, space0},
-    combinator::map_res,
-    multi::separated_list0,
-    sequence::{delimited, pair},
+    bytes::complete::{is_not, tag, take_while_m_n, take_while1},
+    character::complete::{char, space0},
+    combinator::{map, map_opt, map_res, value},
+    multi::{fold_many0, separated_list0},
+    sequence::{delimited, pair, preceded},
 };
 
+use super::{IResult, token};
+
 #[derive(Debug)]
 pub struct Text(pub String);
 impl Deref for Text {
@@ -34,17 +36,55 @@ impl Deref for Text {
         self.0.as_str()
     }
 }
-pub fn text(input: &str) -> IResult<&str, Text> {
-    let esc = escaped(none_of("\\\""), '\\', one_of("\\\""));
-    let esc_or_empty = alt((esc, tag("")));
-    map_res(delimited(tag("\""), esc_or_empty, tag("\"")), |s: &str| {
-        Result::<Text, nom::Err<nom::error::Error<&str>>>::Ok(Text(
-            s.replace("\\\\", "\\").replace("\\\"", "\"").to_string(),
-        ))
+/// `\u{1F600}` with 1 to 6 hex digits, or `\u00E9` with exactly 4.
+fn unicode(input: &str) -> IResult<&str, char> {
+    let hex = |c: char| c.is_ascii_hexdigit();
+    let digits = alt((
+        delimited(char('{'), take_while_m_n(1, 6, hex), char('}')),
+        take_while_m_n(4, 4, hex),
+    ));
+    map_opt(preceded(char('u'), digits), |digits: &str| {
+        char::from_u32(u32::from_str_radix(digits, 16).ok()?)
     })
     .parse(input)
 }
 
+/// One of `\\`, `\"`, `\n`, `\t`, `\r` or a unicode escape. Any other escape is invalid.
+fn escape(input: &str) -> IResult<&str, char> {
+    preceded(
+        char('\\'),
+        alt((
+            value('\\', char('\\')),
+            value('"', char('"')),
+            value('\n', char('n')),
+            value('\t', char('t')),
+            value('\r', char('r')),
+            unicode,
+        )),
+    )
+    .parse(input)
+}
+
+enum Fragment<'a> {
+    Literal(&'a str),
+    Escaped(char),
+}
+
+pub fn text(input: &str) -> IResult<&str, Text> {
+    let fragment = alt((
+        map(is_not("\\\""), Fragment::Literal),
+        map(escape, Fragment::Escaped),
+    ));
+    let content = fold_many0(fragment, String::new, |mut text, fragment| {
+        match fragment {
+            Fragment::Literal(s) => text.push_str(s),
+            Fragment::Escaped(c) => text.push(c),
+        }
+        text
+    });
+    token(|| map(delimited(tag("\""), content, tag("\"")), Text).parse(input))
+}
+
 #[derive(Debug)]
 pub struct Array(pub Vec<String>);
 impl Deref for Array {
@@ -53,6 +93,8 @@ impl Deref for Array {
         self.0.as_slice()
     }
 }
+/// Elements are separated by exactly one comma, so empty positions (`,,`) and trailing commas are
+/// rejected. Any quoted text is a valid element, including whitespace only.
 pub fn array(input: &str) -> IResult<&str, Array> {
     let left = pair(tag("["), space0);
     let right = pair(space0, tag("]"));
@@ -68,6 +110,91 @@ pub fn array(input: &str) -> IResult<&str, Array> {
     .parse(input)
 }
 
+#[derive(Debug)]
+pub struct Param(pub String);
+impl Deref for Param {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.0.as_str()
+    }
+}
+pub fn param(input: &str) -> IResult<&str, Param> {
+    map_res(
+        delimited(
+            tag("{"),
+            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+            tag("}"),
+        ),
+        |s: &str| Result::<Param, nom::Err<nom::error::Error<&str>>>::Ok(Param(s.to_string())),
+    )
+    .parse(input)
+}
+
+/// A quoted `"lat,lon,radius"`, see `crate::geo`.
+#[derive(Debug)]
+pub struct Circle {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius: f64,
+}
+pub fn circle(input: &str) -> IResult<&str, Circle> {
+    map_opt(text, |text| {
+        let (lat, lon, radius) = crate::geo::parse_circle(&text)?;
+        Some(Circle { lat, lon, radius })
+    })
+    .parse(input)
+}
+
+/// An array of exactly two elements, the bounds of a range.
+#[derive(Debug)]
+pub struct Range {
+    pub low: String,
+    pub high: String,
+}
+pub fn range(input: &str) -> IResult<&str, Range> {
+    map_opt(array, |array| {
+        let [low, high] = <[String; 2]>::try_from(array.0).ok()?;
+        Some(Range { low, high })
+    })
+    .parse(input)
+}
+
+/// A quoted non-negative integer, e.g. a length.
+#[derive(Debug)]
+pub struct Count(pub usize);
+pub fn count(input: &str) -> IResult<&str, Count> {
+    map_opt(text, |text| {
+        if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+            text.parse().ok().map(Count)
+        } else {
+            None
+        }
+    })
+    .parse(input)
+}
+
+/// The comparison of a `Length` comparison.
+pub fn cmp_op(input: &str) -> IResult<&str, crate::CmpOp> {
+    alt((
+        map_res(tag(">="), |_| {
+            Result::<_, nom::Err<nom::error::Error<&str>>>::Ok(crate::CmpOp::GreaterOrEqual)
+        }),
+        map_res(tag("<="), |_| {
+            Result::<_, nom::Err<nom::error::Error<&str>>>::Ok(crate::CmpOp::LessOrEqual)
+        }),
+        map_res(tag("="), |_| {
+            Result::<_, nom::Err<nom::error::Error<&str>>>::Ok(crate::CmpOp::Equal)
+        }),
+        map_res(tag(">"), |_| {
+            Result::<_, nom::Err<nom::error::Error<&str>>>::Ok(crate::CmpOp::Greater)
+        }),
+        map_res(tag("<"), |_| {
+            Result::<_, nom::Err<nom::error::Error<&str>>>::Ok(crate::CmpOp::Less)
+        }),
+    ))
+    .parse(input)
+}
+
 macro_rules! operator {
     ($sname:ident, $fname:ident, $symbol:literal) => {
         #[derive(Debug)]
@@ -82,13 +209,20 @@ macro_rules! operator {
 }
 
 operator!(Equal, equal, "=");
+operator!(NotEqual, not_equal, "!=");
 operator!(EqualCI, equal_ci, "~");
+operator!(GreaterOrEqual, greater_or_equal, ">=");
+operator!(LessOrEqual, less_or_equal, "<=");
 operator!(Greater, greater, ">");
 operator!(Less, less, "<");
 operator!(Wildcard, wildcard, "*");
 operator!(Regex, regex, "$");
+operator!(Fuzzy, fuzzy, "%");
 operator!(Any, any, "?");
 operator!(Null, null, "-");
+operator!(Within, within, "@");
+operator!(Length, length, "#");
+operator!(Intersects, intersects, "^");
 operator!(And, and, "&");
 operator!(Or, or, "|");
 operator!(Not, not, "!");
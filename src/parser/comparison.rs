@@ -14,9 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use nom::{IResult, Parser, character::complete::space0, combinator::map_res};
+use nom::{
+    Parser,
+    character::complete::space0,
+    combinator::{map, map_res},
+    error::ParseError,
+};
 
-use super::atom::*;
+use super::{Error, IResult, atom::*, full_text_enabled};
 
 macro_rules! bi_comparison {
     ($sname:ident, $left_type:ty, $oper_type:ty, $right_type:ty, $fname:ident, $left_func:ident, $oper_func:ident, $right_func:ident) => {
@@ -38,6 +43,16 @@ macro_rules! bi_comparison {
 }
 
 bi_comparison!(IsEqual, Text, Equal, Text, is_equal, text, equal, text);
+bi_comparison!(
+    IsNotEqual,
+    Text,
+    NotEqual,
+    Text,
+    is_not_equal,
+    text,
+    not_equal,
+    text
+);
 bi_comparison!(
     IsEqualCI,
     Text,
@@ -52,6 +67,26 @@ bi_comparison!(
     IsGreater, Text, Greater, Text, is_greater, text, greater, text
 );
 bi_comparison!(IsLess, Text, Less, Text, is_less, text, less, text);
+bi_comparison!(
+    IsGreaterOrEqual,
+    Text,
+    GreaterOrEqual,
+    Text,
+    is_greater_or_equal,
+    text,
+    greater_or_equal,
+    text
+);
+bi_comparison!(
+    IsLessOrEqual,
+    Text,
+    LessOrEqual,
+    Text,
+    is_less_or_equal,
+    text,
+    less_or_equal,
+    text
+);
 bi_comparison!(
     IsWildcard,
     Text,
@@ -63,7 +98,25 @@ bi_comparison!(
     text
 );
 bi_comparison!(IsRegex, Text, Regex, Text, is_regex, text, regex, text);
+bi_comparison!(IsFuzzy, Text, Fuzzy, Text, is_fuzzy, text, fuzzy, text);
 bi_comparison!(IsAny, Text, Any, Array, is_any, text, any, array);
+bi_comparison!(
+    IsWithin, Text, Within, Circle, is_within, text, within, circle
+);
+bi_comparison!(IsAnyParam, Text, Any, Param, is_any_param, text, any, param);
+bi_comparison!(
+    IsBetween, Text, Within, Range, is_between, text, within, range
+);
+bi_comparison!(
+    IsIntersects,
+    Text,
+    Intersects,
+    Array,
+    is_intersects,
+    text,
+    intersects,
+    array
+);
 
 macro_rules! uni_comparison {
     ($sname:ident, $oper_type:ident, $target_type:ident, $fname:ident, $oper_func:ident, $target_func:ident) => {
@@ -83,6 +136,35 @@ macro_rules! uni_comparison {
 
 uni_comparison!(IsNull, Null, Text, is_null, null, text);
 
+/// A length comparison, e.g. `"name" # > "10"`.
+#[derive(Debug)]
+pub struct IsLength {
+    pub left: Text,
+    pub op: crate::CmpOp,
+    pub right: Count,
+}
+pub fn is_length(input: &str) -> IResult<&str, IsLength> {
+    map(
+        (text, space0, length, space0, cmp_op, space0, count),
+        |(left, _, _, _, op, _, right)| IsLength { left, op, right },
+    )
+    .parse(input)
+}
+
+/// A lone string without key or operator, i.e. a full-text search term. Only parsed within
+/// `with_full_text`.
+#[derive(Debug)]
+pub struct IsFullText(pub Text);
+pub fn is_full_text(input: &str) -> IResult<&str, IsFullText> {
+    if !full_text_enabled() {
+        return Err(nom::Err::Error(Error::from_error_kind(
+            input,
+            nom::error::ErrorKind::Fail,
+        )));
+    }
+    map(text, IsFullText).parse(input)
+}
+
 macro_rules! comparison {
     ($(($sname:ident, $fname:ident),)*) => {
         #[derive(Debug)]
@@ -97,18 +179,47 @@ macro_rules! comparison {
                 return Ok((rest, Comparison::$sname(matched)));
             }
             )*
-            Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Fail)))
+            Err(nom::Err::Error(Error::from_error_kind(input, nom::error::ErrorKind::Fail)))
         }
     };
 }
 
 comparison!(
     (IsEqual, is_equal),
+    (IsNotEqual, is_not_equal),
     (IsEqualCI, is_equal_ci),
+    // Before `>` and `<`, which would otherwise match the first character of `>=` and `<=`.
+    (IsGreaterOrEqual, is_greater_or_equal),
+    (IsLessOrEqual, is_less_or_equal),
     (IsGreater, is_greater),
     (IsLess, is_less),
     (IsWildcard, is_wildcard),
     (IsRegex, is_regex),
+    (IsFuzzy, is_fuzzy),
     (IsAny, is_any),
+    (IsAnyParam, is_any_param),
+    (IsIntersects, is_intersects),
     (IsNull, is_null),
+    (IsWithin, is_within),
+    (IsBetween, is_between),
+    (IsLength, is_length),
+    (IsFullText, is_full_text),
 );
+
+#[derive(Debug)]
+pub struct Located {
+    pub comparison: Comparison,
+    /// Lengths of the remaining input before and after the comparison, which locate it inside the
+    /// whole input.
+    pub remaining: (usize, usize),
+}
+pub fn located_comparison(input: &str) -> IResult<&str, Located> {
+    let (rest, comparison) = comparison(input)?;
+    Ok((
+        rest,
+        Located {
+            comparison,
+            remaining: (input.len(), rest.len()),
+        },
+    ))
+}
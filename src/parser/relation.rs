@@ -15,30 +15,35 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use nom::{
-    IResult, Parser, branch::alt, bytes::complete::tag, character::complete::space0,
-    combinator::map_res,
+    Parser,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space0,
+    combinator::{eof, map, map_res},
 };
 
-use super::{atom::*, comparison::*};
+use super::{IResult, atom::*, comparison::*, nested};
 
 #[derive(Debug)]
 pub enum Relation {
-    C(Comparison),
+    /// `()`, matching everything.
+    All,
+    C(Located),
     Rar {
         left: Box<Relation>,
         right: Box<Relation>,
     },
     Rac {
         left: Box<Relation>,
-        right: Comparison,
+        right: Located,
     },
     Car {
-        left: Comparison,
+        left: Located,
         right: Box<Relation>,
     },
     Cac {
-        left: Comparison,
-        right: Comparison,
+        left: Located,
+        right: Located,
     },
     Ror {
         left: Box<Relation>,
@@ -46,18 +51,18 @@ pub enum Relation {
     },
     Roc {
         left: Box<Relation>,
-        right: Comparison,
+        right: Located,
     },
     Cor {
-        left: Comparison,
+        left: Located,
         right: Box<Relation>,
     },
     Coc {
-        left: Comparison,
-        right: Comparison,
+        left: Located,
+        right: Located,
     },
     NR(Box<Relation>),
-    NC(Comparison),
+    NC(Located),
 }
 
 fn group_start(input: &str) -> IResult<&str, &str> {
@@ -68,158 +73,104 @@ fn group_end(input: &str) -> IResult<&str, &str> {
     tag(")")(input)
 }
 
-fn c(input: &str) -> IResult<&str, Box<Relation>> {
-    map_res(
-        (group_start, space0, comparison, space0, group_end),
-        |(_, _, c, _, _): (&str, &str, Comparison, &str, &str)| {
-            Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(Box::new(Relation::C(c)))
-        },
-    )
-    .parse(input)
+/// An operand of `&` or `|`.
+enum Operand {
+    C(Located),
+    R(Box<Relation>),
 }
 
-macro_rules! bi_relation {
-    ($fname:ident, $left_func:ident, $oper_func:ident, $right_func:ident, $left_type:ty, $oper_type:ident, $right_type:ty, $relation:ident) => {
-        fn $fname(input: &str) -> IResult<&str, Box<Relation>> {
-            map_res(
-                (
-                    group_start,
-                    space0,
-                    $left_func,
-                    space0,
-                    $oper_func,
-                    space0,
-                    $right_func,
-                    space0,
-                    group_end,
-                ),
-                |(_, _, left, _, _, _, right, _, _): (
-                    &str,
-                    &str,
-                    $left_type,
-                    &str,
-                    $oper_type,
-                    &str,
-                    $right_type,
-                    &str,
-                    &str,
-                )| {
-                    Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(Box::new(
-                        Relation::$relation { left, right },
-                    ))
-                },
-            )
-            .parse(input)
-        }
-    };
+fn operand(input: &str) -> IResult<&str, Operand> {
+    alt((
+        map(located_comparison, Operand::C),
+        map(relation, Operand::R),
+    ))
+    .parse(input)
 }
 
-bi_relation!(
-    rar,
-    relation,
-    and,
-    relation,
-    Box<Relation>,
+enum Operator {
     And,
-    Box<Relation>,
-    Rar
-);
-bi_relation!(
-    rac,
-    relation,
-    and,
-    comparison,
-    Box<Relation>,
-    And,
-    Comparison,
-    Rac
-);
-bi_relation!(
-    car,
-    comparison,
-    and,
-    relation,
-    Comparison,
-    And,
-    Box<Relation>,
-    Car
-);
-bi_relation!(
-    cac, comparison, and, comparison, Comparison, And, Comparison, Cac
-);
-bi_relation!(
-    ror,
-    relation,
-    or,
-    relation,
-    Box<Relation>,
-    Or,
-    Box<Relation>,
-    Ror
-);
-bi_relation!(
-    roc,
-    relation,
-    or,
-    comparison,
-    Box<Relation>,
-    Or,
-    Comparison,
-    Roc
-);
-bi_relation!(
-    cor,
-    comparison,
-    or,
-    relation,
-    Comparison,
     Or,
-    Box<Relation>,
-    Cor
-);
-bi_relation!(
-    coc, comparison, or, comparison, Comparison, Or, Comparison, Coc
-);
+}
+
+fn operator(input: &str) -> IResult<&str, Operator> {
+    alt((map(and, |_| Operator::And), map(or, |_| Operator::Or))).parse(input)
+}
 
-macro_rules! uni_relation {
-    ($fname:ident, $oper_func:ident, $target_func:ident, $oper_type:ty, $target_type:ty, $relation:ident) => {
-        fn $fname(input: &str) -> IResult<&str, Box<Relation>> {
-            map_res(
-                (
-                    group_start,
-                    space0,
-                    $oper_func,
-                    space0,
-                    $target_func,
-                    space0,
-                    group_end,
-                ),
-                |(_, _, _, _, target, _, _): (
-                    &str,
-                    &str,
-                    $oper_type,
-                    &str,
-                    $target_type,
-                    &str,
-                    &str,
-                )| {
-                    Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(Box::new(
-                        Relation::$relation(target),
-                    ))
-                },
-            )
-            .parse(input)
-        }
+/// `( )`, `( comparison )`, `( ! comparison )`, `( ! relation )` or
+/// `( operand operator operand )`.
+///
+/// The left operand is parsed once and then dispatched on what follows it, so that the parse time
+/// stays linear in the length of the input however the groups are nested.
+fn group(input: &str) -> IResult<&str, Box<Relation>> {
+    let (input, _) = (group_start, space0).parse(input)?;
+    if let Some(rest) = input.strip_prefix(')') {
+        return Ok((rest, Box::new(Relation::All)));
+    }
+    // A negated comparison cannot be the left operand, since `!` applies to a relation there.
+    if let Ok((rest, (_, _, c, _, _))) =
+        (not, space0, located_comparison, space0, group_end).parse(input)
+    {
+        return Ok((rest, Box::new(Relation::NC(c))));
+    }
+    // `( ! relation )` is a group on its own, but `( relation )` is not.
+    let negated = input.starts_with('!');
+    let (input, left) = operand(input)?;
+    let (input, _) = space0(input)?;
+    let left = match left {
+        Operand::R(r) if negated => match group_end(input) {
+            Ok((rest, _)) => return Ok((rest, r)),
+            Err(_) => Operand::R(r),
+        },
+        Operand::C(c) => match group_end(input) {
+            Ok((rest, _)) => return Ok((rest, Box::new(Relation::C(c)))),
+            Err(_) => Operand::C(c),
+        },
+        left => left,
     };
+    let (input, (operator, _, right, _, _)) =
+        (operator, space0, operand, space0, group_end).parse(input)?;
+    let relation = match (operator, left, right) {
+        (Operator::And, Operand::R(left), Operand::R(right)) => Relation::Rar { left, right },
+        (Operator::And, Operand::R(left), Operand::C(right)) => Relation::Rac { left, right },
+        (Operator::And, Operand::C(left), Operand::R(right)) => Relation::Car { left, right },
+        (Operator::And, Operand::C(left), Operand::C(right)) => Relation::Cac { left, right },
+        (Operator::Or, Operand::R(left), Operand::R(right)) => Relation::Ror { left, right },
+        (Operator::Or, Operand::R(left), Operand::C(right)) => Relation::Roc { left, right },
+        (Operator::Or, Operand::C(left), Operand::R(right)) => Relation::Cor { left, right },
+        (Operator::Or, Operand::C(left), Operand::C(right)) => Relation::Coc { left, right },
+    };
+    Ok((input, Box::new(relation)))
 }
 
-uni_relation!(nr, not, relation, Not, Box<Relation>, NR);
-uni_relation!(nc, not, comparison, Not, Comparison, NC);
+fn n(input: &str) -> IResult<&str, Box<Relation>> {
+    map_res(
+        (not, space0, relation),
+        |(_, _, target): (Not, &str, Box<Relation>)| {
+            Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(Box::new(Relation::NR(
+                target,
+            )))
+        },
+    )
+    .parse(input)
+}
 
 pub fn relation(input: &str) -> IResult<&str, Box<Relation>> {
+    nested(input, |input| alt((group, n)).parse(input))
+}
+
+/// A comparison without parentheses, which must be the whole input.
+fn bare_comparison(input: &str) -> IResult<&str, Box<Relation>> {
     map_res(
-        alt((c, rar, rac, car, cac, ror, roc, cor, coc, nr, nc)),
-        |r: Box<Relation>| Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(r),
+        (located_comparison, space0, eof),
+        |(c, _, _): (Located, &str, &str)| {
+            Result::<Box<Relation>, nom::Err<nom::error::Error<&str>>>::Ok(Box::new(Relation::C(c)))
+        },
     )
     .parse(input)
 }
+
+/// Top-level entry point. Either a relation or a single bare comparison, e.g. `"a" = "b"`.
+/// Sub-expressions of a relation still need their parentheses.
+pub fn search(input: &str) -> IResult<&str, Box<Relation>> {
+    alt((relation, bare_comparison)).parse(input)
+}
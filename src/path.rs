@@ -0,0 +1,46 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Splitting keys into paths for nested records.
+//!
+//! A key such as `address.city` is split at the delimiter, `.` by default. A delimiter preceded
+//! by `\` belongs to the segment instead, and `\\` is a literal backslash, so `a\.b.c` is the path
+//! `["a.b", "c"]`. Escapes are resolved before splitting, and a `\` before any other character is
+//! kept as is. Note that inside a quoted query string the backslash itself has to be escaped, e.g.
+//! `("a\\.b" = "1")`.
+
+pub const DEFAULT_DELIMITER: char = '.';
+
+/// Splits `key` into path segments at unescaped `delimiter`s.
+pub fn split(key: &str, delimiter: char) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut chars = key.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars
+                .peek()
+                .is_some_and(|&next| next == delimiter || next == '\\') =>
+            {
+                segment.extend(chars.next());
+            }
+            c if c == delimiter => segments.push(std::mem::take(&mut segment)),
+            c => segment.push(c),
+        }
+    }
+    segments.push(segment);
+    segments
+}
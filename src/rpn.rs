@@ -0,0 +1,108 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Postfix (reverse Polish) encoding of expressions.
+//!
+//! Comparisons are operands and `And`, `Or` and `Not` pop their operands off the stack, so
+//! `(("a" = "1") & !("b" -))` is encoded as `[a = 1, b -, Not, And]`.
+
+use crate::{Error, Expression, Node};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RpnToken {
+    /// A comparison, never `Node::And`, `Node::Or` or `Node::Not`.
+    Comparison(Node),
+    And,
+    Or,
+    Not,
+}
+
+impl Expression {
+    pub fn to_rpn(&self) -> Vec<RpnToken> {
+        let mut tokens = Vec::new();
+        self.write_rpn(&mut tokens);
+        tokens
+    }
+
+    fn write_rpn(&self, tokens: &mut Vec<RpnToken>) {
+        match &self.node {
+            Node::And(left, right) => {
+                left.write_rpn(tokens);
+                right.write_rpn(tokens);
+                tokens.push(RpnToken::And);
+            }
+            Node::Or(left, right) => {
+                left.write_rpn(tokens);
+                right.write_rpn(tokens);
+                tokens.push(RpnToken::Or);
+            }
+            Node::Not(expression) => {
+                expression.write_rpn(tokens);
+                tokens.push(RpnToken::Not);
+            }
+            node => tokens.push(RpnToken::Comparison(node.clone())),
+        }
+    }
+
+    /// Rebuilds the expression from its postfix encoding. Fails with `Error::MalformedRpn` if an
+    /// operator lacks operands, more than one expression is left over, or a `Comparison` holds a
+    /// relation.
+    pub fn from_rpn(tokens: &[RpnToken]) -> Result<Self, Error> {
+        let mut stack = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            let node = match token {
+                RpnToken::Comparison(Node::And(..) | Node::Or(..) | Node::Not(_)) => {
+                    return Err(Error::MalformedRpn(format!(
+                        "relation as comparison at {}",
+                        index
+                    )));
+                }
+                RpnToken::Comparison(node) => node.clone(),
+                RpnToken::Not => {
+                    let expression = stack.pop().ok_or_else(|| missing(index))?;
+                    Node::Not(Box::new(expression))
+                }
+                RpnToken::And | RpnToken::Or => {
+                    let right = stack.pop().ok_or_else(|| missing(index))?;
+                    let left = stack.pop().ok_or_else(|| missing(index))?;
+                    if *token == RpnToken::And {
+                        Node::And(Box::new(left), Box::new(right))
+                    } else {
+                        Node::Or(Box::new(left), Box::new(right))
+                    }
+                }
+            };
+            stack.push(Expression { node });
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(expression), true) => Ok(expression),
+            (None, _) => Err(Error::MalformedRpn("empty".into())),
+            (Some(_), false) => Err(Error::MalformedRpn(format!(
+                "{} expressions left over",
+                stack.len() + 1
+            ))),
+        }
+    }
+}
+
+fn missing(index: usize) -> Error {
+    Error::MalformedRpn(format!("missing operand at {}", index))
+}
@@ -0,0 +1,373 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+#[cfg(feature = "evaluate")]
+use crate::interpreter::evaluate::{self, EvaluateRule, Queryable};
+#[cfg(feature = "sqlite")]
+use crate::interpreter::sqlite::{self, SqliteType};
+use crate::{CmpOp, Error, Expression, MapLike, Node};
+
+/// Type of the values of a key, used to compare targets of a query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueType {
+    Boolean,
+    /// Compared chronologically when the `datetime` feature is enabled (RFC 3339), otherwise as
+    /// text.
+    DateTime,
+    Float,
+    Integer,
+    Text,
+}
+
+pub type Schema = HashMap<String, ValueType>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    #[cfg(feature = "datetime")]
+    DateTime(chrono::DateTime<chrono::FixedOffset>),
+    Float(f64),
+    Integer(i64),
+    Text(String),
+}
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.partial_cmp(b),
+            #[cfg(feature = "datetime")]
+            (Value::DateTime(a), Value::DateTime(b)) => a.partial_cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl ValueType {
+    /// Returns `None` if `s` is not a valid value of this type.
+    pub fn parse(&self, s: &str) -> Option<Value> {
+        match self {
+            ValueType::Boolean => s.parse().ok().map(Value::Boolean),
+            #[cfg(feature = "datetime")]
+            ValueType::DateTime => chrono::DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(Value::DateTime),
+            #[cfg(not(feature = "datetime"))]
+            ValueType::DateTime => Some(Value::Text(s.to_string())),
+            ValueType::Float => s.parse().ok().map(Value::Float),
+            ValueType::Integer => s.parse().ok().map(Value::Integer),
+            ValueType::Text => Some(Value::Text(s.to_string())),
+        }
+    }
+
+    /// Whether the operator of a comparison makes sense for this type, e.g. `>` on text is fine but
+    /// `*` on integers is not.
+    pub fn allows(&self, node: &Node) -> bool {
+        match node {
+            Node::EqualCI(..)
+            | Node::Wildcard(..)
+            | Node::Regex(..)
+            | Node::Fuzzy(..)
+            | Node::GeoWithin(..)
+            | Node::Length(..)
+            | Node::Intersects(..) => *self == ValueType::Text,
+            Node::Greater(..)
+            | Node::Less(..)
+            | Node::GreaterOrEqual(..)
+            | Node::LessOrEqual(..)
+            | Node::Between(..) => *self != ValueType::Boolean,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    #[error(transparent)]
+    Parser(#[from] Error),
+    #[error("Unknown key {0}")]
+    UnknownKey(String),
+    #[error("Operator {operator} is not allowed for {key}")]
+    Operator { key: String, operator: &'static str },
+    #[error("Invalid value {value} for {key}")]
+    Value { key: String, value: String },
+}
+
+#[derive(Default)]
+struct Constraint {
+    allowed: Option<Vec<Value>>,
+    lower: Option<Value>,
+    upper: Option<Value>,
+}
+impl Constraint {
+    fn allow(&mut self, values: Vec<Value>) {
+        self.allowed = Some(match self.allowed.take() {
+            Some(allowed) => allowed.into_iter().filter(|v| values.contains(v)).collect(),
+            None => values,
+        });
+    }
+
+    fn is_empty(&self) -> bool {
+        if let (Some(lower), Some(upper)) = (&self.lower, &self.upper)
+            && lower.partial_cmp(upper).is_some_and(Ordering::is_ge)
+        {
+            return true;
+        }
+        self.allowed.as_ref().is_some_and(|allowed| {
+            !allowed.iter().any(|v| {
+                self.lower
+                    .as_ref()
+                    .is_none_or(|lower| v.partial_cmp(lower) != Some(Ordering::Less) && v != lower)
+                    && self.upper.as_ref().is_none_or(|upper| {
+                        v.partial_cmp(upper) != Some(Ordering::Greater) && v != upper
+                    })
+            })
+        })
+    }
+}
+
+fn conjuncts<'a>(expression: &'a Expression, leaves: &mut Vec<&'a Expression>) {
+    match &expression.node {
+        Node::And(left, right) => {
+            conjuncts(left, leaves);
+            conjuncts(right, leaves);
+        }
+        _ => leaves.push(expression),
+    }
+}
+
+impl Expression {
+    /// Detects conjunctions that can never match, e.g. `("age" > "30") & ("age" < "10")` or two
+    /// different equalities on the same key.
+    ///
+    /// This is conservative: `false` means no obvious contradiction was found, not that the
+    /// expression can match. Keys missing from the schema are compared as text, and targets that
+    /// cannot be parsed into the key's type are ignored.
+    pub fn is_contradiction(&self, schema: &impl MapLike<ValueType>) -> bool {
+        let mut leaves = Vec::new();
+        conjuncts(self, &mut leaves);
+        let mut constraints = HashMap::<&str, Constraint>::new();
+        for leaf in leaves {
+            let parse = |key: &str, target: &str| {
+                schema.lookup(key).unwrap_or(&ValueType::Text).parse(target)
+            };
+            match &leaf.node {
+                Node::Or(left, right)
+                    if left.is_contradiction(schema) && right.is_contradiction(schema) =>
+                {
+                    return true;
+                }
+                Node::Equal(key, target) => {
+                    if let Some(value) = parse(key, target) {
+                        constraints.entry(key).or_default().allow(vec![value]);
+                    }
+                }
+                Node::Any(key, targets) => {
+                    let values = targets
+                        .iter()
+                        .map(|target| parse(key, target))
+                        .collect::<Option<Vec<_>>>();
+                    if let Some(values) = values {
+                        constraints.entry(key).or_default().allow(values);
+                    }
+                }
+                Node::Greater(key, target) => {
+                    if let Some(value) = parse(key, target) {
+                        let constraint = constraints.entry(key).or_default();
+                        if constraint
+                            .lower
+                            .as_ref()
+                            .is_none_or(|lower| value.partial_cmp(lower) == Some(Ordering::Greater))
+                        {
+                            constraint.lower = Some(value);
+                        }
+                    }
+                }
+                Node::Less(key, target) => {
+                    if let Some(value) = parse(key, target) {
+                        let constraint = constraints.entry(key).or_default();
+                        if constraint
+                            .upper
+                            .as_ref()
+                            .is_none_or(|upper| value.partial_cmp(upper) == Some(Ordering::Less))
+                        {
+                            constraint.upper = Some(value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        constraints.values().any(Constraint::is_empty)
+    }
+}
+
+pub(crate) fn operator(node: &Node) -> &'static str {
+    match node {
+        Node::And(..) => "&",
+        Node::Or(..) => "|",
+        Node::Not(..) => "!",
+        Node::Equal(..) => "=",
+        Node::NotEqual(..) => "!=",
+        Node::EqualCI(..) => "~",
+        Node::Greater(..) => ">",
+        Node::Less(..) => "<",
+        Node::GreaterOrEqual(..) => ">=",
+        Node::LessOrEqual(..) => "<=",
+        Node::Wildcard(..) => "*",
+        Node::Regex(..) => "$",
+        Node::Fuzzy(..) => "%",
+        Node::Any(..) | Node::AnyParam(..) => "?",
+        Node::Intersects(..) => "^",
+        Node::Null(..) => "-",
+        Node::Between(..) | Node::GeoWithin(..) => "@",
+        Node::Length(_, op, _) => match op {
+            CmpOp::Equal => "#=",
+            CmpOp::Greater => "#>",
+            CmpOp::Less => "#<",
+            CmpOp::GreaterOrEqual => "#>=",
+            CmpOp::LessOrEqual => "#<=",
+        },
+        Node::FullText(..) => "",
+        Node::MatchAll => "()",
+    }
+}
+
+impl Expression {
+    /// Checks that every key is in the schema, every operator is allowed for the key's type and
+    /// every target is a valid value of that type. Regex and wildcard patterns and full-text terms
+    /// are not checked as values.
+    pub fn validate(&self, schema: &impl MapLike<ValueType>) -> Result<(), ValidationError> {
+        let bounds;
+        let (key, targets) = match &self.node {
+            Node::And(left, right) | Node::Or(left, right) => {
+                left.validate(schema)?;
+                return right.validate(schema);
+            }
+            Node::Not(expression) => return expression.validate(schema),
+            Node::FullText(_) | Node::MatchAll => return Ok(()),
+            Node::Equal(key, target)
+            | Node::NotEqual(key, target)
+            | Node::Greater(key, target)
+            | Node::Less(key, target)
+            | Node::GreaterOrEqual(key, target)
+            | Node::LessOrEqual(key, target) => (key, std::slice::from_ref(target)),
+            Node::Any(key, targets) => (key, targets.as_slice()),
+            Node::Between(key, low, high) => {
+                bounds = [low.clone(), high.clone()];
+                (key, bounds.as_slice())
+            }
+            Node::EqualCI(key, _)
+            | Node::Wildcard(key, _)
+            | Node::Regex(key, _)
+            | Node::Fuzzy(key, _)
+            | Node::AnyParam(key, _)
+            | Node::Intersects(key, _)
+            | Node::GeoWithin(key, ..)
+            | Node::Length(key, ..)
+            | Node::Null(key) => (key, [].as_slice()),
+        };
+        let value_type = schema
+            .lookup(key)
+            .ok_or_else(|| ValidationError::UnknownKey(key.to_string()))?;
+        if !value_type.allows(&self.node) {
+            return Err(ValidationError::Operator {
+                key: key.to_string(),
+                operator: operator(&self.node),
+            });
+        }
+        match targets
+            .iter()
+            .find(|target| value_type.parse(target).is_none())
+        {
+            Some(target) => Err(ValidationError::Value {
+                key: key.to_string(),
+                value: target.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses and validates `input` against `schema` in one go.
+    pub fn parse_and_validate<'a>(
+        input: &str,
+        schema: &'a Schema,
+    ) -> Result<ValidatedExpression<'a>, ValidationError> {
+        let expression = input.parse::<Expression>()?;
+        expression.validate(schema)?;
+        Ok(ValidatedExpression { expression, schema })
+    }
+}
+
+/// An expression known to be valid against a schema, see `Expression::parse_and_validate`.
+#[derive(Debug)]
+pub struct ValidatedExpression<'a> {
+    expression: Expression,
+    schema: &'a Schema,
+}
+
+impl ValidatedExpression<'_> {
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub fn into_expression(self) -> Expression {
+        self.expression
+    }
+
+    pub fn schema(&self) -> &Schema {
+        self.schema
+    }
+
+    /// Interprets into SQLite with the bind types derived from the schema.
+    #[cfg(feature = "sqlite")]
+    pub fn to_sqlite(
+        &self,
+        renames: &impl MapLike<String>,
+    ) -> sqlite::Result<(String, Vec<SqliteType>)> {
+        let types = self
+            .schema
+            .iter()
+            .map(|(key, value_type)| {
+                let sqlite_type = match value_type {
+                    ValueType::Boolean => SqliteType::Boolean(None),
+                    #[cfg(feature = "datetime")]
+                    ValueType::DateTime => SqliteType::DateTime(None),
+                    // Compared as text like `ValueType::parse` does.
+                    #[cfg(not(feature = "datetime"))]
+                    ValueType::DateTime => SqliteType::Text(None),
+                    ValueType::Float => SqliteType::Real(None),
+                    ValueType::Integer => SqliteType::BigInt(None),
+                    ValueType::Text => SqliteType::Text(None),
+                };
+                (key.clone(), sqlite_type)
+            })
+            .collect::<HashMap<_, _>>();
+        sqlite::interpret(&self.expression, renames, &types)
+    }
+
+    #[cfg(feature = "evaluate")]
+    pub fn evaluate<R: MapLike<EvaluateRule> + ?Sized, Q: Queryable + ?Sized>(
+        &self,
+        rules: &R,
+        pairs: &Q,
+    ) -> bool {
+        evaluate::interpret(&self.expression, rules, pairs)
+    }
+}
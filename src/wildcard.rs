@@ -0,0 +1,170 @@
+// This library implements GSP (General Search Parser)
+// Copyright (C) 2026  Hakukaze Shikano
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Wildcard patterns, where `*` matches any sequence of characters and `?` any single character.
+//!
+//! `\*`, `\?` and `\\` match `*`, `?` and `\` literally. Any other `\` is a literal backslash.
+//!
+//! Other wildcard characters, e.g. SQL's `%` and `_`, can be configured with `WildcardConfig`.
+//! Interpreters translate patterns into the default syntax with `WildcardConfig::translate`.
+
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Literal(char),
+    /// `?`
+    AnyOne,
+    /// `*`
+    AnyMany,
+}
+
+/// The characters standing for any sequence and any single character in a pattern. `None`
+/// disables that wildcard, so the character is always literal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WildcardConfig {
+    pub any_many: Option<char>,
+    pub any_one: Option<char>,
+}
+impl Default for WildcardConfig {
+    fn default() -> Self {
+        Self {
+            any_many: Some('*'),
+            any_one: Some('?'),
+        }
+    }
+}
+impl WildcardConfig {
+    /// `%` for any sequence and `_` for any single character, as in SQL's `LIKE`.
+    pub fn sql() -> Self {
+        Self {
+            any_many: Some('%'),
+            any_one: Some('_'),
+        }
+    }
+
+    /// Tokenizes a pattern written with these wildcards. A `\` before a wildcard character or
+    /// another `\` makes it literal.
+    pub fn tokens(&self, pattern: &str) -> Vec<Token> {
+        let is_special = |c| c == '\\' || Some(c) == self.any_many || Some(c) == self.any_one;
+        let mut tokens = Vec::with_capacity(pattern.len());
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '\\' => match chars.next_if(|&next| is_special(next)) {
+                    Some(escaped) => Token::Literal(escaped),
+                    None => Token::Literal('\\'),
+                },
+                c if Some(c) == self.any_many => Token::AnyMany,
+                c if Some(c) == self.any_one => Token::AnyOne,
+                c => Token::Literal(c),
+            });
+        }
+        tokens
+    }
+
+    /// Rewrites a pattern written with these wildcards into the default syntax, escaping literal
+    /// `*`, `?` and `\`. The pattern is returned as is for the default config.
+    pub fn translate<'a>(&self, pattern: &'a str) -> Cow<'a, str> {
+        if *self == Self::default() {
+            return Cow::Borrowed(pattern);
+        }
+        let mut translated = String::with_capacity(pattern.len());
+        for token in self.tokens(pattern) {
+            match token {
+                Token::Literal(c @ ('*' | '?' | '\\')) => {
+                    translated.push('\\');
+                    translated.push(c);
+                }
+                Token::Literal(c) => translated.push(c),
+                Token::AnyOne => translated.push('?'),
+                Token::AnyMany => translated.push('*'),
+            }
+        }
+        Cow::Owned(translated)
+    }
+}
+
+pub fn tokens(pattern: &str) -> Vec<Token> {
+    WildcardConfig::default().tokens(pattern)
+}
+
+/// The literal text of a pattern without any wildcard, or `None` if it has one.
+pub fn literal(pattern: &str) -> Option<String> {
+    tokens(pattern)
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(c) => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Translates the pattern for `LIKE` with `\` as the escape character, escaping literal `%`, `_`
+/// and `\`.
+pub fn to_like(pattern: &str) -> String {
+    let mut like = String::with_capacity(pattern.len());
+    for token in tokens(pattern) {
+        match token {
+            Token::Literal(c @ ('%' | '_' | '\\')) => {
+                like.push('\\');
+                like.push(c);
+            }
+            Token::Literal(c) => like.push(c),
+            Token::AnyOne => like.push('_'),
+            Token::AnyMany => like.push('%'),
+        }
+    }
+    like
+}
+
+/// Whether the whole `value` matches the pattern, case-sensitively.
+pub fn is_match(pattern: &str, value: &str) -> bool {
+    let tokens = tokens(pattern);
+    let chars = value.chars().collect::<Vec<_>>();
+    let (mut t, mut c) = (0, 0);
+    // Position after the last `*` and the character it was last matched up to, for backtracking.
+    let mut star = None;
+    while c < chars.len() {
+        match tokens.get(t) {
+            Some(Token::AnyMany) => {
+                t += 1;
+                star = Some((t, c));
+                continue;
+            }
+            Some(Token::AnyOne) => {
+                t += 1;
+                c += 1;
+                continue;
+            }
+            Some(Token::Literal(literal)) if *literal == chars[c] => {
+                t += 1;
+                c += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match star {
+            Some((after, matched)) => {
+                t = after;
+                c = matched + 1;
+                star = Some((after, c));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|token| *token == Token::AnyMany)
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "arena")]
+
+use flp_gsp::{
+    Error, Expression, ParseOptions,
+    arena::{ArenaExpression, Bump},
+};
+
+const QUERIES: [&str; 16] = [
+    r#"("name" = "Jack")"#,
+    r#"(("age" > "18") & ("sex" ? ["male", "Male"]))"#,
+    r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#,
+    r#"!(("name" = "a\"b\\c") | ("score" < "1.5"))"#,
+    r#"(!("a" $ "^x") & ("b" % "fuzy"))"#,
+    r#"(("status" ? {allowed_statuses}) & ("tags" ? [ ]))"#,
+    r#"("laptop" & ("price" < "1000"))"#,
+    r#"(  "a"   =   "1"  |  !  ( "b" - )  )"#,
+    r#"(("age">="18") & ("age" <= "30"))"#,
+    r#"(!("a" != "b") & ("c" = "!="))"#,
+    r#""status" = "active" "#,
+    r#"(("name" # > "10") & ("code" #<="3"))"#,
+    r#"(("tags" ^ ["a", "b"]) | ("tags"^[]))"#,
+    r#"(("age" @ ["18", "65"]) & ("location"@"1,2,3"))"#,
+    r#"("a\tb" ? ["\u{1F600}\u00e9", "x\r\n"])"#,
+    r#"(( ) | !())"#,
+];
+
+#[test]
+fn test_arena_matches_default_parse() {
+    let bump = Bump::new();
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    for query in QUERIES {
+        let arena = ArenaExpression::parse_in_with(query, &options, &bump);
+        if cfg!(not(any(feature = "regex", feature = "fancy-regex"))) && query.contains(" $ ") {
+            assert!(
+                matches!(arena, Err(Error::FeatureDisabled("regex"))),
+                "{}",
+                query
+            );
+            continue;
+        }
+        let arena = arena.unwrap();
+        assert_eq!(
+            Expression::from(arena),
+            Expression::parse_with(query, &options).unwrap(),
+            "{}",
+            query
+        );
+    }
+}
+
+#[test]
+fn test_arena_invalid() {
+    let bump = Bump::new();
+    for query in [
+        "",
+        r#"("name" = )"#,
+        r#"("name" = "Jack""#,
+        r#"(! "a" = "1" & ("b" = "2"))"#,
+        r#"("a" ? ["1",, "2"])"#,
+        r#"("a" = "\x")"#,
+        r#"("a" = "\u{110000}")"#,
+        r#"("a" = "\u12")"#,
+        r#"("a" # > "x")"#,
+        r#"("a" # "1")"#,
+        r#"("a" @ ["1"])"#,
+        r#"("a" @ ["1", "2", "3"])"#,
+        r#""a" = "b" & ("c" = "d")"#,
+        r#"(("k" = "v"))"#,
+        r#"("laptop" & ("price" < "1000"))"#,
+        r#"((! ("k" = "v")))"#,
+        "(())",
+    ] {
+        assert!(
+            ArenaExpression::parse_in(query, &bump).is_err(),
+            "{}",
+            query
+        );
+        assert!(query.parse::<Expression>().is_err(), "{}", query);
+    }
+
+    let deep = format!(r#"{}("a" = "b")"#, "!(".repeat(10_000));
+    assert!(ArenaExpression::parse_in(&deep, &bump).is_err());
+}
+
+#[cfg(feature = "evaluate")]
+#[test]
+fn test_arena_interpret() {
+    use flp_gsp::{arena::ArenaNode, interpreter::evaluate::*};
+
+    let bump = Bump::new();
+    let query = r#"(("age" > "18") & ("sex" ? ["male", "Male"]))"#;
+    let arena = ArenaExpression::parse_in(query, &bump).unwrap();
+    assert!(matches!(arena.node, ArenaNode::And(..)));
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("age".into(), EvaluateRule::default());
+    rules.insert("sex".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("age".into(), "20".into());
+    pairs.insert("sex".into(), "Male".into());
+    assert_eq!(
+        interpret(&Expression::from(arena), &rules, &pairs),
+        interpret(&query.parse::<Expression>().unwrap(), &rules, &pairs)
+    );
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "bpf")]
+
+use flp_gsp::{Expression, interpreter::bpf::*};
+
+fn fields() -> BpfFields {
+    let mut fields = BpfFields::new();
+    fields.insert("src_ip".into(), BpfField::SrcHost);
+    fields.insert("net".into(), BpfField::DstNet);
+    fields.insert("port".into(), BpfField::Port);
+    fields.insert("dst_port".into(), BpfField::DstPort);
+    fields.insert("proto".into(), BpfField::Protocol);
+    fields.insert("len".into(), BpfField::Length);
+    fields
+}
+
+#[test]
+fn test_bpf() {
+    let fields = fields();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &fields).unwrap();
+
+    assert_eq!(
+        interpret(r#"(("proto" = "tcp") & (("src_ip" = "10.0.0.1") | ("port" ? ["53", "853"])))"#),
+        "(tcp and (src host 10.0.0.1 or (port 53 or port 853)))"
+    );
+    assert_eq!(
+        interpret(r#"(! ("net" = "192.168.0.0/16") & ("proto" != "udp"))"#),
+        "((not dst net 192.168.0.0/16) and (not udp))"
+    );
+    assert_eq!(
+        interpret(r#"("dst_port" @ ["8000", "8080"])"#),
+        "dst portrange 8000-8080"
+    );
+    assert_eq!(interpret(r#"("port" > "1023")"#), "portrange 1024-65535");
+    assert_eq!(interpret("()"), "len >= 0");
+    assert_eq!(interpret(r#"("port" < "1024")"#), "portrange 0-1023");
+    assert_eq!(
+        interpret(r#"(("len" >= "64") & ("len" = "1500"))"#),
+        "(len >= 64 and len = 1500)"
+    );
+}
+
+#[test]
+fn test_bpf_errors() {
+    let fields = fields();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &fields);
+
+    assert!(matches!(
+        interpret(r#""src_ip" * "10.0.*""#),
+        Err(Error::UnsupportedNode(_))
+    ));
+    #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+    assert!(matches!(
+        interpret(r#""src_ip" $ "^10\\.""#),
+        Err(Error::UnsupportedNode(_))
+    ));
+    assert!(matches!(
+        interpret(r#""src_ip" > "10.0.0.1""#),
+        Err(Error::ParseInt(_))
+    ));
+    assert!(matches!(
+        interpret(r#""net" @ ["1", "2"]"#),
+        Err(Error::UnsupportedNode(_))
+    ));
+    assert!(matches!(
+        interpret(r#""host" = "10.0.0.1""#),
+        Err(Error::UnknownKey(key)) if key == "host"
+    ));
+    assert!(matches!(
+        interpret(r#""port" = "65536""#),
+        Err(Error::ParseInt(_))
+    ));
+    assert!(matches!(
+        interpret(r#""port" > "65535""#),
+        Err(Error::InvalidValue(_))
+    ));
+    // An empty list matches nothing, even on an unknown key.
+    assert_eq!(interpret(r#"("missing" ? [])"#).unwrap(), "len < 0");
+    // Values cannot inject primitives.
+    assert!(matches!(
+        interpret(r#""src_ip" = "1.2.3.4 or tcp""#),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        interpret(r#""proto" = "tcp or udp""#),
+        Err(Error::InvalidValue(_))
+    ));
+}
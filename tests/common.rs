@@ -18,3 +18,13 @@ pub struct Person {
     pub age: u8,
     pub sex: Sex,
 }
+
+/// A deterministic xorshift generator starting at `seed`, returning numbers below its argument.
+pub fn xorshift(mut seed: u64) -> impl FnMut(u64) -> u64 {
+    move |n| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed % n
+    }
+}
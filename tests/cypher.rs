@@ -0,0 +1,67 @@
+#![cfg(feature = "cypher")]
+
+use flp_gsp::{Expression, interpreter::cypher::*};
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_cypher() {
+    let s = r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & ("name" * "J?c*" | "e-mail" $ "@example\\.com$"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut types = CypherTypes::new();
+    types.insert("age".into(), CypherType::Integer);
+    types.insert("sex".into(), CypherType::StringLike);
+    types.insert("name".into(), CypherType::StringLike);
+    types.insert("e-mail".into(), CypherType::StringLike);
+
+    let (predicate, params) = interpret(&expression, "n", &types).unwrap();
+    assert_eq!(
+        predicate,
+        "((((NOT n.age IS NULL) AND (NOT n.age > $p0)) AND (n.sex IN $p1 OR toLower(n.sex) = toLower($p2))) AND (n.name =~ $p3 OR n.`e-mail` =~ $p4))"
+    );
+
+    let mut expected = CypherParams::new();
+    expected.insert("p0".into(), CypherValue::Integer(18));
+    expected.insert(
+        "p1".into(),
+        CypherValue::List(vec![
+            CypherValue::String("male".into()),
+            CypherValue::String("Male".into()),
+        ]),
+    );
+    expected.insert("p2".into(), CypherValue::String("Female".into()));
+    expected.insert("p3".into(), CypherValue::String("(?s)J.c.*".into()));
+    expected.insert("p4".into(), CypherValue::String(r"@example\.com$".into()));
+    assert_eq!(params, expected);
+}
+
+#[test]
+fn test_cypher_errors() {
+    let mut types = CypherTypes::new();
+    types.insert("age".into(), CypherType::Integer);
+    types.insert("name".into(), CypherType::StringLike);
+
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), "n", &types);
+    assert!(matches!(
+        interpret(r#""nick" = "Bob""#),
+        Err(Error::UnknownKey(key)) if key == "nick"
+    ));
+    assert!(matches!(
+        interpret(r#""age" = "old""#),
+        Err(Error::ParseInt(_))
+    ));
+    assert!(matches!(
+        interpret(r#""name" % "Bob""#),
+        Err(Error::UnsupportedNode(_))
+    ));
+
+    // An empty list matches nothing, even on an unknown key.
+    let (predicate, params) = interpret(r#"("missing" ? [])"#).unwrap();
+    assert_eq!(predicate, "false");
+    assert!(params.is_empty());
+    assert_eq!(interpret("()").unwrap().0, "true");
+
+    // Literal characters of a wildcard are escaped in its regex.
+    let (_, params) = interpret(r#""name" * "a.b\\*""#).unwrap();
+    assert_eq!(params["p0"], CypherValue::String(r"(?s)a\.b\*".into()));
+}
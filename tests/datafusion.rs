@@ -0,0 +1,101 @@
+#![cfg(feature = "datafusion")]
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int32Array, StringArray},
+    record_batch::RecordBatch,
+};
+use datafusion_common::DFSchema;
+use datafusion_expr::execution_props::ExecutionProps;
+use datafusion_physical_expr::create_physical_expr;
+use flp_gsp::{Expression, interpreter::datafusion::*};
+
+fn batch() -> RecordBatch {
+    RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["Jack", "Joe", "Jill", "50%"])) as ArrayRef,
+        ),
+        (
+            "age",
+            Arc::new(Int32Array::from(vec![18, 20, 30, 40])) as ArrayRef,
+        ),
+        (
+            "active",
+            Arc::new(BooleanArray::from(vec![true, false, true, false])) as ArrayRef,
+        ),
+        (
+            "score",
+            Arc::new(Float64Array::from(vec![Some(1.5), None, Some(3.0), None])) as ArrayRef,
+        ),
+    ])
+    .unwrap()
+}
+
+fn names(s: &str) -> Vec<String> {
+    let batch = batch();
+    let expr = interpret(&s.parse::<Expression>().unwrap(), &batch.schema()).unwrap();
+    let schema = DFSchema::try_from(batch.schema().as_ref().clone()).unwrap();
+    let mask = create_physical_expr(&expr, &schema, &ExecutionProps::new())
+        .unwrap()
+        .evaluate(&batch)
+        .unwrap()
+        .into_array(batch.num_rows())
+        .unwrap();
+    let mask = mask.as_boolean();
+    let names = batch.column_by_name("name").unwrap().as_string::<i32>();
+    (0..batch.num_rows())
+        .filter(|&i| mask.is_valid(i) && mask.value(i))
+        .map(|i| names.value(i).to_string())
+        .collect()
+}
+
+#[test]
+fn test_datafusion() {
+    let schema = batch().schema();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &schema).unwrap();
+    assert_eq!(
+        interpret(r#"(("age" > "19") & ("name" ? ["Joe", "Jill"]))"#).to_string(),
+        r#"age > Int32(19) AND name IN ([Utf8("Joe"), Utf8("Jill")])"#
+    );
+    assert_eq!(interpret(r#"("score" -)"#).to_string(), "score IS NULL");
+
+    assert_eq!(names(r#"("age" > "19")"#), vec!["Joe", "Jill", "50%"]);
+    assert_eq!(names(r#"("age" @ ["20", "30"])"#), vec!["Joe", "Jill"]);
+    assert_eq!(
+        names(r#"(("active" = "true") & ("age" < "25"))"#),
+        vec!["Jack"]
+    );
+    assert_eq!(names(r#"("name" ? ["Joe", "Jill"])"#), vec!["Joe", "Jill"]);
+    assert_eq!(names(r#"("name" ? [])"#), Vec::<String>::new());
+    assert_eq!(names("()"), vec!["Jack", "Joe", "Jill", "50%"]);
+    assert_eq!(names(r#"("name" * "J?c*")"#), vec!["Jack"]);
+    assert_eq!(names(r#"("name" * "5_%")"#), Vec::<String>::new());
+    assert_eq!(names(r#"("name" * "50%")"#), vec!["50%"]);
+    assert_eq!(names(r#"("name" ~ "JOE")"#), vec!["Joe"]);
+    assert_eq!(names(r#"("name" $ "^J.ll$")"#), vec!["Jill"]);
+    assert_eq!(names(r#"("score" -)"#), vec!["Joe", "50%"]);
+    assert_eq!(names(r#"(! ("score" $ "^1"))"#), vec!["Joe", "Jill", "50%"]);
+    assert_eq!(names(r#"(! ("score" > "2"))"#), vec!["Jack"]);
+    assert_eq!(names(r#"("age" ~ "20")"#), vec!["Joe"]);
+    assert_eq!(names(r#"("name" # > "3")"#), vec!["Jack", "Jill"]);
+}
+
+#[test]
+fn test_datafusion_errors() {
+    let schema = batch().schema();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &schema);
+    assert!(matches!(
+        interpret(r#"("nmae" = "Jack")"#),
+        Err(Error::UnknownKey(_))
+    ));
+    assert!(matches!(
+        interpret(r#"("age" = "abc")"#),
+        Err(Error::DataFusion(_))
+    ));
+    assert!(matches!(
+        interpret(r#"("name" % "Jak")"#),
+        Err(Error::UnsupportedNode(_))
+    ));
+}
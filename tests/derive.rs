@@ -0,0 +1,51 @@
+#![cfg(feature = "derive")]
+
+use flp_gsp::{Expression, interpreter::evaluate::*};
+
+#[derive(Queryable)]
+struct Person {
+    name: String,
+    #[gsp(rename = "years")]
+    age: u8,
+    nickname: Option<String>,
+    #[gsp(skip)]
+    #[allow(dead_code)]
+    password: String,
+}
+
+#[test]
+fn test_derive_queryable() {
+    let person = Person {
+        name: "Jack".into(),
+        age: 18,
+        nickname: None,
+        password: "secret".into(),
+    };
+    assert_eq!(person.field("name").as_deref(), Some("Jack"));
+    assert_eq!(person.field("years").as_deref(), Some("18"));
+    assert_eq!(person.field("age"), None);
+    assert_eq!(person.field("nickname"), None);
+    assert_eq!(person.field("password"), None);
+
+    let mut rules = EvaluateRules::new();
+    for key in ["name", "years", "password"] {
+        rules.insert(key.into(), EvaluateRule::default());
+    }
+    let persons = [
+        person,
+        Person {
+            name: "Joe".into(),
+            age: 20,
+            nickname: Some("Jo".into()),
+            password: "secret".into(),
+        },
+    ];
+    let count = |s: &str| {
+        let expression = s.parse::<Expression>().unwrap();
+        let predicate = expression.predicate::<Person, _>(&rules);
+        persons.iter().filter(|person| predicate(person)).count()
+    };
+    assert_eq!(count(r#"("name" = "Jack")"#), 1);
+    assert_eq!(count(r#"("years" ? ["18", "20"])"#), 2);
+    assert_eq!(count(r#"("password" = "secret")"#), 0);
+}
@@ -1,12 +1,18 @@
 #![cfg(feature = "evaluate")]
 
-use flp_gsp::{Expression, interpreter::evaluate::*};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+};
+
+use flp_gsp::{Expression, Node, ParseOptions, interpreter::evaluate::*};
 
 mod common;
 
 use common::*;
 
 #[test]
+#[allow(clippy::field_reassign_with_default)]
 fn test_evaluate() {
     let s =
         r#"(((! "age" > "18") & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#;
@@ -71,9 +77,959 @@ fn test_evaluate() {
     assert_eq!(names, vec!["JacKkkk", "Joc"]);
 }
 
+impl Queryable for Person {
+    fn field(&self, key: &str) -> Option<Cow<'_, str>> {
+        match key {
+            "name" => Some(Cow::Borrowed(&self.name)),
+            "age" => Some(Cow::Owned(self.age.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_predicate() {
+    let s = r#"(("name" * "J*") & ("age" < "20"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    rules.insert(
+        "age".into(),
+        EvaluateRule {
+            is_less_than: |value, target| {
+                value.parse::<u8>().unwrap() < target.parse::<u8>().unwrap()
+            },
+            ..Default::default()
+        },
+    );
+
+    let persons = [
+        Person {
+            name: "Jack".into(),
+            age: 18,
+            sex: Sex::Male,
+        },
+        Person {
+            name: "Jill".into(),
+            age: 25,
+            sex: Sex::Female,
+        },
+        Person {
+            name: "Bob".into(),
+            age: 10,
+            sex: Sex::Other,
+        },
+    ];
+
+    let names = persons
+        .into_iter()
+        .filter(expression.predicate(&rules))
+        .map(|p| p.name)
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["Jack"]);
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_regex_flags() {
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("log".into(), "first line\nERROR: second line".into());
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("log".into(), EvaluateRule::default());
+
+    let inline = r#"("log" $ "(?im)^error:")"#.parse::<Expression>().unwrap();
+    assert!(interpret(&inline, &rules, &pairs));
+
+    let anchored = r#"("log" $ "^ERROR:")"#.parse::<Expression>().unwrap();
+    assert!(!interpret(&anchored, &rules, &pairs));
+
+    rules.insert(
+        "log".into(),
+        EvaluateRule {
+            regex_flags: RegexFlags {
+                multi_line: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+    assert!(interpret(&anchored, &rules, &pairs));
+
+    let overridden = r#"("log" $ "(?-m)^ERROR:")"#.parse::<Expression>().unwrap();
+    assert!(!interpret(&overridden, &rules, &pairs));
+}
+
+#[test]
+fn test_any_param() {
+    let expression = r#"("status" ? {allowed_statuses})"#.parse::<Expression>().unwrap();
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("status".into(), EvaluateRule::default());
+
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("status".into(), "open".into());
+
+    let mut params = EvaluateParams::new();
+    params.insert(
+        "allowed_statuses".into(),
+        vec!["open".into(), "pending".into()],
+    );
+    assert!(interpret_with_params(&expression, &rules, &pairs, &params).unwrap());
+
+    params.insert("allowed_statuses".into(), vec!["closed".into()]);
+    assert!(!interpret_with_params(&expression, &rules, &pairs, &params).unwrap());
+
+    let err = interpret_with_params(&expression, &rules, &pairs, &EvaluateParams::new());
+    assert!(matches!(err, Err(Error::UnresolvedParam(p)) if p == "allowed_statuses"));
+
+    // Without params the whole expression fails instead of the comparison being false, so a
+    // negation does not match everything.
+    let negated = r#"(! ("status" ? {allowed_statuses}))"#.parse::<Expression>().unwrap();
+    assert!(!interpret(&negated, &rules, &pairs));
+    assert!(!interpret(&expression, &rules, &pairs));
+}
+
+#[test]
+fn test_case_insensitive_order() {
+    let less = r#"("fruit" < "Banana")"#.parse::<Expression>().unwrap();
+    let greater = r#"("fruit" > "Banana")"#.parse::<Expression>().unwrap();
+
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("fruit".into(), "apple".into());
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("fruit".into(), EvaluateRule::default());
+    assert!(!interpret(&less, &rules, &pairs));
+    assert!(interpret(&greater, &rules, &pairs));
+
+    rules.insert("fruit".into(), EvaluateRule::case_insensitive());
+    assert!(interpret(&less, &rules, &pairs));
+    assert!(!interpret(&greater, &rules, &pairs));
+
+    pairs.insert("fruit".into(), "banana".into());
+    assert!(!interpret(&less, &rules, &pairs));
+    assert!(!interpret(&greater, &rules, &pairs));
+}
+
+#[test]
+fn test_eq_ignore_case() {
+    assert!(eq_ignore_case("JaCk", "jAcK"));
+    assert!(!eq_ignore_case("Jack", "Jacks"));
+    assert!(eq_ignore_case("ÉCOLE", "école"));
+    assert!(eq_ignore_case("ΟΔΟΣ", "οδος"));
+    // `İ` lowercases to `i` with a combining dot above, so it does not equal a plain `i`.
+    assert!(!eq_ignore_case("İ", "i"));
+    assert!(eq_ignore_case("İ", "i\u{307}"));
+    assert!(!eq_ignore_case("ß", "ss"));
+
+    let expression = r#"("name" ~ "ÉCOLE")"#.parse::<Expression>().unwrap();
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "école".into());
+    assert!(interpret(&expression, &rules, &pairs));
+}
+
+#[test]
+fn test_btree_map() {
+    let expression = r#"(("name" = "Jack") & ("sex" ? ["Male", "Other"]))"#
+        .parse::<Expression>()
+        .unwrap();
+
+    let mut rules = BTreeMap::new();
+    rules.insert("name".to_string(), EvaluateRule::default());
+    rules.insert("sex".to_string(), EvaluateRule::default());
+
+    let mut pairs = BTreeMap::new();
+    pairs.insert("name".to_string(), "Jack".to_string());
+    pairs.insert("sex".to_string(), Sex::Male.into());
+    assert!(interpret(&expression, &rules, &pairs));
+
+    pairs.insert("sex".to_string(), Sex::Female.into());
+    assert!(!interpret(&expression, &rules, &pairs));
+}
+
+#[test]
+fn test_fuzzy() {
+    let expression = r#"("name" % "Jhon")"#.parse::<Expression>().unwrap();
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+
+    let matched = ["John", "Jhon", "Joan", "Jonathan", "Bob"]
+        .into_iter()
+        .filter(|name| {
+            let mut pairs = EvaluatePairs::new();
+            pairs.insert("name".into(), name.to_string());
+            interpret(&expression, &rules, &pairs)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(matched, vec!["John", "Jhon", "Joan"]);
+
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
+
 #[test]
 fn test_invalid() {
     let s = r#"("="")"#;
     let expression = s.parse::<Expression>();
     assert!(expression.is_err());
 }
+
+#[test]
+fn test_evaluate_full_text() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("price".into(), EvaluateRule::default());
+
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("title".into(), "Gaming Laptop 15".into());
+    pairs.insert("description".into(), "Fast and light".into());
+    pairs.insert("price".into(), "5".into());
+
+    let options = EvaluateOptions {
+        full_text_fields: vec!["title".into(), "description".into()],
+        ..Default::default()
+    };
+    let params = EvaluateParams::new();
+    let parse_options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    let matches = |s: &str, options: &EvaluateOptions| {
+        let expression = Expression::parse_with(s, &parse_options).unwrap();
+        interpret_with_options(&expression, &rules, &pairs, &params, options).unwrap()
+    };
+
+    assert!(matches(r#"("laptop")"#, &options));
+    assert!(matches(r#"("light" & ("price" < "6"))"#, &options));
+    assert!(!matches(r#"("laptop" & ("price" > "6"))"#, &options));
+    assert!(!matches(r#"("desktop")"#, &options));
+    assert!(!matches(r#"("laptop")"#, &EvaluateOptions::default()));
+}
+
+#[test]
+fn test_evaluate_or_equal() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("age".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("age".into(), "18".into());
+
+    let matches = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    assert!(matches(r#"("age" >= "18")"#));
+    assert!(matches(r#"("age" <= "18")"#));
+    assert!(!matches(r#"("age" > "18")"#));
+    assert!(!matches(r#"("age" >= "19")"#));
+}
+
+#[test]
+fn test_evaluate_not_equal() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("status".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("status".into(), "active".into());
+
+    let matches = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    assert!(matches(r#"("status" != "closed")"#));
+    assert!(!matches(r#"("status" != "active")"#));
+    assert!(!matches(r#"("missing" != "active")"#));
+}
+
+#[test]
+fn test_evaluate_length() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    // 5 characters but 10 bytes.
+    pairs.insert("name".into(), "ünïcö".into());
+
+    let matches = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    assert!(matches(r#"("name" # = "5")"#));
+    assert!(!matches(r#"("name" # = "10")"#));
+    assert!(matches(r#"("name" # > "4")"#));
+    assert!(!matches(r#"("name" # > "5")"#));
+    assert!(matches(r#"("name" # < "6")"#));
+    assert!(!matches(r#"("name" # < "5")"#));
+    assert!(matches(r#"("name" # >= "5")"#));
+    assert!(matches(r#"("name" # <= "5")"#));
+    assert!(!matches(r#"("missing" # >= "0")"#));
+}
+
+#[test]
+fn test_evaluate_intersects() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("tags".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("tags".into(), "rust,parser,sql".into());
+
+    let matches = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    // Intersecting.
+    assert!(matches(r#"("tags" ^ ["go", "sql"])"#));
+    assert!(matches(r#"("tags" ^ ["rust"])"#));
+    // Disjoint.
+    assert!(!matches(r#"("tags" ^ ["go", "java"])"#));
+    assert!(!matches(r#"("tags" ^ ["rust,parser"])"#));
+    assert!(!matches(r#"("tags" ^ ["pars"])"#));
+    assert!(!matches(r#"("tags" ^ [])"#));
+    assert!(!matches(r#"("missing" ^ ["rust"])"#));
+}
+
+#[test]
+fn test_evaluate_between() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("age".into(), EvaluateRule::numeric());
+    rules.insert("name".into(), EvaluateRule::default());
+    let matches = |age: &str, s: &str| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("age".into(), age.into());
+        pairs.insert("name".into(), age.into());
+        interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs)
+    };
+
+    // Both bounds are inclusive.
+    let s = r#"("age" @ ["18", "65"])"#;
+    assert!(matches("18", s));
+    assert!(matches("40", s));
+    assert!(matches("65", s));
+    assert!(!matches("17", s));
+    assert!(!matches("65.5", s));
+    assert!(!matches("100", s));
+    // An empty range never matches.
+    assert!(!matches("40", r#"("age" @ ["65", "18"])"#));
+    // Textual by default.
+    assert!(matches("100", r#"("name" @ ["1", "2"])"#));
+    assert!(!matches("9", r#"("name" @ ["1", "2"])"#));
+}
+
+#[test]
+fn test_evaluate_multi() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("tags".into(), EvaluateRule::default());
+    rules.insert("empty".into(), EvaluateRule::default());
+    let mut pairs = EvaluateMultiPairs::new();
+    pairs.insert("tags".into(), vec!["rust".into(), "sql".into()]);
+    pairs.insert("empty".into(), vec![]);
+
+    let matches = |s: &str| interpret_multi(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    // One of the two values matches.
+    assert!(matches(r#"("tags" = "sql")"#));
+    assert!(matches(r#"("tags" ? ["go", "rust"])"#));
+    assert!(matches(r#"("tags" * "r*")"#));
+    assert!(matches(r#"(("tags" = "rust") & ("tags" = "sql"))"#));
+    // Neither matches.
+    assert!(!matches(r#"("tags" = "go")"#));
+    assert!(!matches(r#"("tags" * "go*")"#));
+    // `Not` negates the combined result.
+    assert!(!matches(r#"!("tags" = "sql")"#));
+    assert!(matches(r#"!("tags" = "go")"#));
+    assert!(matches(r#"(!("tags" = "go") | ("tags" = "go"))"#));
+    // Empty lists are null.
+    assert!(matches(r#"("empty" -)"#));
+    assert!(!matches(r#"("tags" -)"#));
+    assert!(!matches(r#"("empty" = "")"#));
+    assert!(!matches(r#"("missing" -)"#));
+}
+
+#[test]
+fn test_evaluate_multi_is_present() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("tags".into(), EvaluateRule::default());
+    rules.insert("empty".into(), EvaluateRule::default());
+    rules.insert("nulls".into(), EvaluateRule::default());
+    rules.insert("missing".into(), EvaluateRule::default());
+    let mut pairs = EvaluateMultiPairs::new();
+    pairs.insert("tags".into(), vec!["null".into(), "rust".into()]);
+    pairs.insert("empty".into(), vec![]);
+    pairs.insert("nulls".into(), vec!["null".into(), "none".into()]);
+
+    let matches = |s: &str| interpret_multi(&s.parse::<Expression>().unwrap(), &rules, &pairs);
+    // Present if any value is not null.
+    assert!(matches(r#"(! "tags" -)"#));
+    assert!(!matches(r#"(! "nulls" -)"#));
+    assert!(!matches(r#"(! "empty" -)"#));
+    // A missing key is neither null nor present, as in `interpret`.
+    assert!(!matches(r#"("missing" -)"#));
+    assert!(!matches(r#"(! "missing" -)"#));
+    let mut single = EvaluatePairs::new();
+    single.insert("tags".into(), "rust".into());
+    let expression = r#"(! "missing" -)"#.parse::<Expression>().unwrap();
+    assert!(!interpret(&expression, &rules, &single));
+}
+
+#[test]
+fn test_evaluate_null_sentinels() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("score".into(), EvaluateRule::default());
+    let options = EvaluateOptions {
+        null_sentinels: [("score".into(), HashSet::from(["-1".into()]))].into(),
+        ..Default::default()
+    };
+    let expression = r#"("score" -)"#.parse::<Expression>().unwrap();
+    let matches = |score: &str, options: &EvaluateOptions| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("score".into(), score.into());
+        interpret_with_options(&expression, &rules, &pairs, &EvaluateParams::new(), options)
+            .unwrap()
+    };
+
+    assert!(matches("-1", &options));
+    assert!(matches("null", &options));
+    assert!(!matches("10", &options));
+    assert!(!matches("-1", &EvaluateOptions::default()));
+}
+
+#[test]
+fn test_evaluate_wildcard_config() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "Jack*".into());
+    let sql = EvaluateOptions {
+        wildcard: flp_gsp::wildcard::WildcardConfig::sql(),
+        ..Default::default()
+    };
+    let matches = |s: &str, options: &EvaluateOptions| {
+        let expression = s.parse::<Expression>().unwrap();
+        interpret_with_options(&expression, &rules, &pairs, &EvaluateParams::new(), options)
+            .unwrap()
+    };
+
+    assert!(matches(r#"("name" * "J_c%")"#, &sql));
+    assert!(matches(r#"("name" * "%*")"#, &sql));
+    assert!(!matches(r#"("name" * "J?c*")"#, &sql));
+    assert!(matches(r#"("name" * "J?c*")"#, &EvaluateOptions::default()));
+    assert!(!matches(
+        r#"("name" * "J_c%")"#,
+        &EvaluateOptions::default()
+    ));
+}
+
+#[test]
+fn test_evaluate_is_present() {
+    let null = r#"("score" -)"#.parse::<Expression>().unwrap();
+    let present = r#"!("score" -)"#.parse::<Expression>().unwrap();
+    let mut rules = EvaluateRules::new();
+    rules.insert("score".into(), EvaluateRule::default());
+    let evaluate = |score: Option<&str>, options: &EvaluateOptions| {
+        let mut pairs = EvaluatePairs::new();
+        if let Some(score) = score {
+            pairs.insert("score".into(), score.into());
+        }
+        let params = EvaluateParams::new();
+        (
+            interpret_with_options(&null, &rules, &pairs, &params, options).unwrap(),
+            interpret_with_options(&present, &rules, &pairs, &params, options).unwrap(),
+        )
+    };
+
+    let options = EvaluateOptions::default();
+    assert_eq!(evaluate(Some("null"), &options), (true, false));
+    assert_eq!(evaluate(Some("10"), &options), (false, true));
+    // A missing key is neither null nor present.
+    assert_eq!(evaluate(None, &options), (false, false));
+    assert!(!interpret_iterative(
+        &present,
+        &rules,
+        &EvaluatePairs::new()
+    ));
+
+    let options = EvaluateOptions {
+        unknown_key: UnknownKeyPolicy::True,
+        ..Default::default()
+    };
+    assert_eq!(evaluate(None, &options), (true, true));
+}
+
+#[test]
+fn test_evaluate_defaults() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("archived".into(), EvaluateRule::default());
+    rules.insert("owner".into(), EvaluateRule::default());
+    let options = EvaluateOptions {
+        defaults: [("archived".into(), "false".into())].into(),
+        ..Default::default()
+    };
+    let matches = |s: &str, pairs: &EvaluatePairs, options: &EvaluateOptions| {
+        let expression = s.parse::<Expression>().unwrap();
+        interpret_with_options(&expression, &rules, pairs, &EvaluateParams::new(), options).unwrap()
+    };
+
+    let mut archived = EvaluatePairs::new();
+    archived.insert("archived".into(), "true".into());
+    let missing = EvaluatePairs::new();
+
+    // Configured default.
+    assert!(matches(r#"("archived" = "false")"#, &missing, &options));
+    assert!(!matches(r#"("archived" = "false")"#, &archived, &options));
+    assert!(!matches(
+        r#"("archived" = "false")"#,
+        &missing,
+        &EvaluateOptions::default()
+    ));
+    // Unconfigured missing key.
+    assert!(!matches(r#"("owner" = "bob")"#, &missing, &options));
+    assert!(!matches(r#"("owner" != "bob")"#, &missing, &options));
+    assert!(matches(r#"!("owner" = "bob")"#, &missing, &options));
+}
+
+#[test]
+fn test_evaluate_unknown_key() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    rules.insert("owner".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "Jack".into());
+    pairs.insert("nmae".into(), "Jack".into());
+    let evaluate = |s: &str, unknown_key: UnknownKeyPolicy| {
+        let options = EvaluateOptions {
+            unknown_key,
+            ..Default::default()
+        };
+        let expression = s.parse::<Expression>().unwrap();
+        interpret_with_options(
+            &expression,
+            &rules,
+            &pairs,
+            &EvaluateParams::new(),
+            &options,
+        )
+    };
+
+    // `owner` is missing from the record, and `nmae` has no rule.
+    for s in [r#"("owner" = "bob")"#, r#"("nmae" = "Jack")"#] {
+        assert!(!evaluate(s, UnknownKeyPolicy::False).unwrap(), "{}", s);
+        assert!(evaluate(s, UnknownKeyPolicy::True).unwrap(), "{}", s);
+        assert!(
+            matches!(
+                evaluate(s, UnknownKeyPolicy::Error),
+                Err(Error::UnknownKey(_))
+            ),
+            "{}",
+            s
+        );
+    }
+    assert!(matches!(
+        evaluate(r#"(("name" = "Jack") & ("owner" -))"#, UnknownKeyPolicy::Error),
+        Err(Error::UnknownKey(key)) if key == "owner"
+    ));
+
+    // Known keys are unaffected.
+    let s = r#"(("name" = "Jack") | ("owner" = "bob"))"#;
+    assert!(evaluate(s, UnknownKeyPolicy::Error).unwrap());
+    let s = r#"(("name" = "Bob") & ("owner" != "bob"))"#;
+    assert!(!evaluate(s, UnknownKeyPolicy::True).unwrap());
+    assert!(
+        evaluate(
+            r#"(("name" = "Jack") & ("owner" != "bob"))"#,
+            UnknownKeyPolicy::True
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_evaluate_empty_any() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "Jack".into());
+    let mut params = EvaluateParams::new();
+    params.insert("none".into(), vec![]);
+
+    // An empty list matches nothing, even for a key missing from the rules and the record.
+    for unknown_key in [
+        UnknownKeyPolicy::False,
+        UnknownKeyPolicy::True,
+        UnknownKeyPolicy::Error,
+    ] {
+        let options = EvaluateOptions {
+            unknown_key,
+            ..Default::default()
+        };
+        for s in [
+            r#"("name" ? [])"#,
+            r#"("missing" ? [])"#,
+            r#"("missing" ? {none})"#,
+        ] {
+            let expression = s.parse::<Expression>().unwrap();
+            assert!(
+                !interpret_with_options(&expression, &rules, &pairs, &params, &options).unwrap(),
+                "{}",
+                s
+            );
+        }
+        let expression = r#"(! ("missing" ? []))"#.parse::<Expression>().unwrap();
+        assert!(interpret_with_options(&expression, &rules, &pairs, &params, &options).unwrap());
+    }
+
+    let expression = r#"("missing" ? [])"#.parse::<Expression>().unwrap();
+    let mut multi = EvaluateMultiPairs::new();
+    multi.insert("missing".into(), vec!["a".into()]);
+    assert!(!interpret_multi(&expression, &rules, &multi));
+    assert!(!interpret_typed(&expression, &EvaluateTypedPairs::new()).unwrap());
+}
+
+#[test]
+fn test_evaluate_match_all() {
+    let rules = EvaluateRules::new();
+    let pairs = EvaluatePairs::new();
+    let all = Expression::conjoin_all(Vec::new());
+    assert!(interpret(&all, &rules, &pairs));
+    assert!(interpret_iterative(&all, &rules, &pairs));
+    assert!(interpret_multi(&all, &rules, &EvaluateMultiPairs::new()));
+    assert!(interpret_typed(&all, &EvaluateTypedPairs::new()).unwrap());
+    assert!(!interpret(&all.not(), &rules, &pairs));
+}
+
+#[test]
+fn test_evaluate_numeric() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("score".into(), EvaluateRule::numeric());
+    rules.insert("name".into(), EvaluateRule::default());
+    let matches = |score: &str, s: &str| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("score".into(), score.into());
+        pairs.insert("name".into(), score.into());
+        interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs)
+    };
+
+    assert!(matches("9", r#"("score" < "100")"#));
+    assert!(!matches("9", r#"("score" > "100")"#));
+    assert!(matches("9", r#"("name" > "100")"#));
+    assert!(matches("-5", r#"("score" < "-2")"#));
+    assert!(matches(
+        "-2.5",
+        r#"(("score" >= "-3") & ("score" <= "-2.5"))"#
+    ));
+    assert!(matches("1e3", r#"("score" > "999")"#));
+
+    // Non-numeric values fall back to text comparison.
+    assert!(matches("abc", r#"("score" > "100")"#));
+    assert!(matches("abc", r#"("score" < "abd")"#));
+    assert!(!matches("NaN", r#"("score" < "1")"#));
+}
+
+#[test]
+fn test_evaluate_geo_within() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("location".into(), EvaluateRule::default());
+    let matches = |location: &str, s: &str| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("location".into(), location.into());
+        interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs)
+    };
+
+    let query = r#"("location" @ "37.7,-122.4,5km")"#;
+    assert!(matches("37.7,-122.4", query));
+    assert!(matches("37.72, -122.41", query));
+    assert!(!matches("37.8044,-122.2712", query));
+    assert!(matches(
+        "37.8044,-122.2712",
+        r#"("location" @ "37.7,-122.4,20km")"#
+    ));
+    assert!(matches(
+        "37.8044,-122.2712",
+        r#"("location" @ "37.7,-122.4,15mi")"#
+    ));
+    assert!(!matches(
+        "37.8044,-122.2712",
+        r#"("location" @ "37.7,-122.4,5000")"#
+    ));
+
+    // Unparsable record values never match.
+    assert!(!matches("somewhere", query));
+    assert!(!matches("137.7,-122.4", query));
+}
+
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+#[test]
+fn test_evaluate_without_regex() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "Jack".into());
+
+    // The parser rejects regexes, but an expression may still be built by hand.
+    let expression = Expression {
+        node: Node::Regex("name".into(), "^J".into()),
+    };
+    assert!(!interpret(&expression, &rules, &pairs));
+    assert!(CompiledRegexes::new(&expression, &rules).is_empty());
+    let expression = Expression {
+        node: Node::Not(Box::new(expression)),
+    };
+    assert!(interpret(&expression, &rules, &pairs));
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_evaluate_regex_lookaround() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("password".into(), EvaluateRule::default());
+    let matches = |password: &str, s: &str| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("password".into(), password.into());
+        interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs)
+    };
+    let lookahead = r#"("password" $ "^(?=.*\\d)(?=.*[a-z]).{8,}$")"#;
+    let backreference = r#"("password" $ "(\\w)\\1")"#;
+
+    #[cfg(feature = "fancy-regex")]
+    {
+        assert!(matches("secret123", lookahead));
+        assert!(!matches("secretabc", lookahead));
+        assert!(!matches("sec123", lookahead));
+        assert!(matches("aab", backreference));
+        assert!(!matches("abc", backreference));
+    }
+    // The default engine rejects lookaround and backreferences, so they never match.
+    #[cfg(not(feature = "fancy-regex"))]
+    {
+        assert!(!matches("secret123", lookahead));
+        assert!(!matches("aab", backreference));
+    }
+    assert!(matches("secret123", r#"("password" $ "\\d+$")"#));
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_evaluate_cancellation() {
+    use std::{
+        sync::atomic::AtomicBool,
+        time::{Duration, Instant},
+    };
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let records = (0..2_000)
+        .map(|i| {
+            let mut pairs = EvaluatePairs::new();
+            pairs.insert("name".into(), format!("name{}", i));
+            pairs
+        })
+        .collect::<Vec<_>>();
+    let expression =
+        r#"(("name" $ "^name1\\d*$") | ("name" % "name"))"#.parse::<Expression>().unwrap();
+    let filter = |cancellation: &Cancellation| {
+        filter_cancellable(
+            &expression,
+            &rules,
+            &records,
+            &EvaluateParams::new(),
+            &EvaluateOptions::default(),
+            cancellation,
+        )
+    };
+
+    let start = Instant::now();
+    let deadline = Cancellation {
+        deadline: Some(Instant::now()),
+        ..Default::default()
+    };
+    assert!(matches!(filter(&deadline), Err(Error::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    let flag = AtomicBool::new(true);
+    let flagged = Cancellation {
+        flag: Some(&flag),
+        ..Default::default()
+    };
+    assert!(matches!(filter(&flagged), Err(Error::Cancelled)));
+
+    let matched = filter(&Cancellation::default()).unwrap();
+    // `name1`, `name1x`, `name1xx` and `name1xxx`, and `name0` to `name99` by edit distance.
+    assert_eq!(matched.len(), 1_111 + 9 + 80);
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_evaluate_compiled_regexes() {
+    let mut rules = EvaluateRules::new();
+    // Compiled regexes are matched directly, never through the rule.
+    rules.insert(
+        "name".into(),
+        EvaluateRule {
+            is_match_regex: |_, _| panic!("regex compiled per record"),
+            regex_flags: RegexFlags {
+                case_insensitive: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+    let expression =
+        r#"((("name" $ "^NAME1\\d*$") | ("name" $ "^name1\\d*$")) | ("name" $ "^name9\\d*$"))"#
+            .parse::<Expression>()
+            .unwrap();
+    let regexes = CompiledRegexes::new(&expression, &rules);
+    assert_eq!(regexes.len(), 3);
+
+    let matched = (0..10_000)
+        .filter(|i| {
+            let mut pairs = EvaluatePairs::new();
+            pairs.insert("name".into(), format!("name{}", i));
+            interpret_compiled(&expression, &rules, &pairs, &regexes)
+        })
+        .count();
+    assert_eq!(matched, 2 * 1_111);
+
+    let invalid = r#"("name" $ "(unclosed")"#.parse::<Expression>().unwrap();
+    let regexes = CompiledRegexes::new(&invalid, &rules);
+    assert_eq!(regexes.len(), 1);
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("name".into(), "(unclosed".into());
+    assert!(!interpret_compiled(&invalid, &rules, &pairs, &regexes));
+}
+
+#[test]
+fn test_evaluate_iterative() {
+    let mut rules = EvaluateRules::new();
+    for key in ["a", "b", "c"] {
+        rules.insert(key.into(), EvaluateRule::default());
+    }
+    rules.insert(
+        "boom".into(),
+        EvaluateRule {
+            is_equal: |_, _| panic!("not short-circuited"),
+            ..Default::default()
+        },
+    );
+    let pairs = BTreeMap::from([
+        ("a".to_string(), "1".to_string()),
+        ("b".to_string(), "2".to_string()),
+        ("c".to_string(), "".to_string()),
+    ]);
+
+    for s in [
+        r#"(("a" = "2") & ("boom" = "1"))"#,
+        r#"(("a" = "1") | ("boom" = "1"))"#,
+        r#"!(!("b" > "1") & ("boom" = "1"))"#,
+    ] {
+        let expression = s.parse::<Expression>().unwrap();
+        assert_eq!(
+            interpret_iterative(&expression, &rules, &pairs),
+            interpret(&expression, &rules, &pairs),
+            "{}",
+            s
+        );
+    }
+
+    // Random trees.
+    let mut next = xorshift(0x9e37_79b9_7f4a_7c15);
+    fn generate(depth: u32, next: &mut dyn FnMut(u64) -> u64) -> Expression {
+        let key = ["a", "b", "c", "d"][next(4) as usize].to_string();
+        let target = ["", "1", "2", "*"][next(4) as usize].to_string();
+        let node = match if depth == 0 { 3 + next(6) } else { next(9) } {
+            0 => Node::And(
+                Box::new(generate(depth - 1, next)),
+                Box::new(generate(depth - 1, next)),
+            ),
+            1 => Node::Or(
+                Box::new(generate(depth - 1, next)),
+                Box::new(generate(depth - 1, next)),
+            ),
+            2 => Node::Not(Box::new(generate(depth - 1, next))),
+            3 => Node::Equal(key, target),
+            4 => Node::Greater(key, target),
+            5 => Node::Wildcard(key, target),
+            6 => Node::Null(key),
+            7 => Node::Any(key, vec![target, "2".into()]),
+            _ => Node::LessOrEqual(key, target),
+        };
+        Expression { node }
+    }
+    for _ in 0..500 {
+        let expression = generate(6, &mut next);
+        assert_eq!(
+            interpret_iterative(&expression, &rules, &pairs),
+            interpret(&expression, &rules, &pairs),
+            "{}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn test_evaluate_iterative_deep() {
+    let mut rules = EvaluateRules::new();
+    rules.insert("a".into(), EvaluateRule::default());
+    let pairs = BTreeMap::from([("a".to_string(), "1".to_string())]);
+
+    let leaf = || Expression {
+        node: Node::Equal("a".into(), "1".into()),
+    };
+    let mut expression = leaf();
+    for i in 0..5_000 {
+        expression = Expression {
+            node: if i % 2 == 0 {
+                Node::And(Box::new(expression), Box::new(leaf()))
+            } else {
+                Node::Not(Box::new(Expression {
+                    node: Node::Not(Box::new(expression)),
+                }))
+            },
+        };
+    }
+
+    // Far too small a stack for the recursive evaluator at this depth.
+    std::thread::scope(|scope| {
+        let matched = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(scope, || interpret_iterative(&expression, &rules, &pairs))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(matched);
+    });
+}
+
+#[test]
+fn test_evaluate_typed() {
+    let mut pairs = EvaluateTypedPairs::new();
+    pairs.insert("count".into(), EvaluateType::Integer(Some(100)));
+    pairs.insert("score".into(), EvaluateType::Real(Some(2.5)));
+    pairs.insert("name".into(), EvaluateType::Text(Some("Jack".into())));
+    pairs.insert("deleted".into(), EvaluateType::Boolean(None));
+
+    let matches = |s: &str| interpret_typed(&s.parse::<Expression>().unwrap(), &pairs).unwrap();
+    // Textually, "100" < "9".
+    assert!(matches(r#"("count" > "9")"#));
+    assert!(!matches(r#"("count" < "9")"#));
+    assert!(matches(r#"("count" @ ["10", "100"])"#));
+    assert!(matches(r#"("count" ? ["1", "100"])"#));
+    assert!(matches(r#"("score" >= "2.25")"#));
+    assert!(matches(r#"(("name" = "Jack") & ("name" * "J*"))"#));
+    assert!(matches(r#"("count" * "1*")"#));
+    assert!(matches(r#"("deleted" -)"#));
+    assert!(!matches(r#"("deleted" != "true")"#));
+    assert!(matches(r#"(! ("deleted" = "true"))"#));
+
+    let error = |s: &str| interpret_typed(&s.parse::<Expression>().unwrap(), &pairs).unwrap_err();
+    assert!(matches!(error(r#"("count" > "many")"#), Error::ParseInt(_)));
+    assert!(matches!(error(r#"("score" = "x")"#), Error::ParseFloat(_)));
+    assert!(matches!(error(r#"("age" = "1")"#), Error::UnknownKey(key) if key == "age"));
+}
+
+#[cfg(feature = "datetime")]
+#[test]
+fn test_evaluate_typed_datetime() {
+    let mut pairs = EvaluateTypedPairs::new();
+    let created = "2024-03-01T09:30:00Z".parse().unwrap();
+    pairs.insert("created".into(), EvaluateType::DateTime(Some(created)));
+
+    let matches = |s: &str| interpret_typed(&s.parse::<Expression>().unwrap(), &pairs).unwrap();
+    assert!(matches(r#"("created" > "2024-02-29T23:00:00+00:00")"#));
+    // Ordered by the instant, not the text.
+    assert!(!matches(r#"("created" < "2024-03-01T10:00:00+01:00")"#));
+    assert!(matches(r#"("created" = "2024-03-01T10:30:00+01:00")"#));
+    assert!(matches!(
+        interpret_typed(
+            &r#"("created" > "yesterday")"#.parse::<Expression>().unwrap(),
+            &pairs
+        ),
+        Err(Error::ParseChrono(_))
+    ));
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "evaluate-async")]
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use flp_gsp::{
+    Expression,
+    interpreter::{
+        evaluate::{
+            EvaluateOptions, EvaluatePairs, EvaluateParams, EvaluateRule, UnknownKeyPolicy,
+        },
+        evaluate_async::*,
+    },
+};
+
+/// A rule for `?` asking a slow remote service, counting the calls. `"down"` fails.
+fn remote(calls: Arc<AtomicUsize>) -> AsyncEvaluateRule<String> {
+    AsyncEvaluateRule {
+        is_in: list(move |value, targets| {
+            let calls = calls.clone();
+            Box::pin(async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                if value == "down" {
+                    return Err("service unavailable".to_string());
+                }
+                Ok(targets.iter().any(|target| target == value))
+            })
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_evaluate_async() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut rules = AsyncEvaluateRules::new();
+    rules.insert("group".into(), remote(calls.clone()));
+    rules.insert("age".into(), EvaluateRule::numeric().into());
+    rules.insert("name".into(), AsyncEvaluateRule::default());
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("group".into(), "admin".into());
+    pairs.insert("age".into(), "9".into());
+    pairs.insert("name".into(), "Jack".into());
+
+    let matches = async |s: &str| {
+        interpret(&s.parse::<Expression>().unwrap(), &rules, &pairs)
+            .await
+            .unwrap()
+    };
+    assert!(matches(r#"(("age" < "10") & ("group" ? ["admin", "staff"]))"#).await);
+    assert!(matches(r#"(("name" ~ "jack") & (! "name" -))"#).await);
+    assert!(!matches(r#"("group" ? ["staff"])"#).await);
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+
+    // The left side decides, so the remote rule is never awaited.
+    assert!(!matches(r#"(("age" > "10") & ("group" ? ["admin"]))"#).await);
+    assert!(matches(r#"(("name" = "Jack") | ("group" ? ["admin"]))"#).await);
+    assert!(!matches(r#"("group" ? [])"#).await);
+    assert!(matches("()").await);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_evaluate_async_errors() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut rules = AsyncEvaluateRules::new();
+    rules.insert("group".into(), remote(calls.clone()));
+    let mut pairs = EvaluatePairs::new();
+    pairs.insert("group".into(), "down".into());
+
+    let expression = r#"("group" ? ["admin"])"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &rules, &pairs).await,
+        Err(Error::Rule(err)) if err == "service unavailable"
+    ));
+
+    let options = EvaluateOptions {
+        unknown_key: UnknownKeyPolicy::Error,
+        ..Default::default()
+    };
+    let expression = r#"("owner" ? ["bob"])"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret_with_options(&expression, &rules, &pairs, &EvaluateParams::new(), &options).await,
+        Err(Error::UnknownKey(key)) if key == "owner"
+    ));
+    let expression = r#"(! ("group" ? {groups}))"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &rules, &pairs).await,
+        Err(Error::UnresolvedParam(param)) if param == "groups"
+    ));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
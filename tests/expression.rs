@@ -0,0 +1,754 @@
+use flp_gsp::{
+    CmpOp, Expression, Node, ParseOptions, Visitor,
+    describe::Phrases,
+    schema::{Schema, ValueType},
+};
+
+fn parse(s: &str) -> Expression {
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    Expression::parse_with(s, &options).unwrap()
+}
+
+#[test]
+fn test_conjoin_all() {
+    assert_eq!(Expression::conjoin_all(Vec::new()), Expression::match_all());
+
+    let one = Expression::conjoin_all(vec![parse(r#"("a" = "1")"#)]);
+    assert!(matches!(one.node, Node::Equal(ref k, _) if k == "a"));
+
+    let several = Expression::conjoin_all(vec![
+        parse(r#"("a" = "1")"#),
+        parse(r#"("b" = "2")"#),
+        parse(r#"("c" = "3")"#),
+    ]);
+    match several.node {
+        Node::And(left, right) => {
+            assert!(matches!(left.node, Node::And(_, _)));
+            assert!(matches!(right.node, Node::Equal(ref k, _) if k == "c"));
+        }
+        _ => panic!("expected And, got {:?}", several.node),
+    }
+
+    let tenant =
+        parse(r#"("tenant" = "t1")"#).and(parse(r#"("a" = "1")"#).or(parse(r#"("b" = "2")"#)));
+    match tenant.node {
+        Node::And(_, right) => assert!(matches!(right.node, Node::Or(_, _))),
+        _ => panic!("expected And, got {:?}", tenant.node),
+    }
+}
+
+#[test]
+fn test_describe() {
+    let expression =
+        parse(r#"(("name" = "Bob") & (! ("age" > "30") | "sex" ? ["male", "other"]))"#);
+    assert_eq!(
+        expression.describe(&Phrases::default()),
+        r#"(name is "Bob" and (not age is greater than "30" or sex is one of male, other))"#
+    );
+
+    let phrases = Phrases {
+        and: "{left}かつ{right}".into(),
+        or: "{left}または{right}".into(),
+        not: "「{value}」ではない".into(),
+        equal: "{key}が「{value}」".into(),
+        greater: "{key}が「{value}」より大きい".into(),
+        any: "{key}が{value}のいずれか".into(),
+        list_separator: "、".into(),
+        ..Default::default()
+    };
+    assert_eq!(
+        expression.describe(&phrases),
+        "nameが「Bob」かつ「ageが「30」より大きい」ではないまたはsexがmale、otherのいずれか"
+    );
+
+    let braces = parse(r#"("name" = "{key} {value}")"#);
+    assert_eq!(
+        braces.describe(&Phrases::default()),
+        r#"name is "{key} {value}""#
+    );
+}
+
+#[test]
+fn test_contradiction() {
+    let mut schema = Schema::new();
+    schema.insert("age".into(), ValueType::Integer);
+    schema.insert("status".into(), ValueType::Text);
+
+    let contradictions = [
+        r#"(("age" > "30") & ("age" < "10"))"#,
+        r#"(("age" > "30") & ("age" < "30"))"#,
+        r#"(("status" = "a") & ("status" = "b"))"#,
+        r#"(("age" = "9") & ("age" > "10"))"#,
+        r#"(("age" ? ["1", "2"]) & ("age" = "3"))"#,
+        r#"(("age" ? ["1", "20"]) & (("age" > "5") & ("age" < "10")))"#,
+        r#"(("status" = "a") & ((("age" > "9") & ("age" < "1")) | ("age" ? [])))"#,
+        r#"("status" ? [])"#,
+    ];
+    for s in contradictions {
+        assert!(parse(s).is_contradiction(&schema), "{}", s);
+    }
+
+    let satisfiable = [
+        r#"(("age" > "9") & ("age" < "100"))"#,
+        r#"(("status" = "a") | ("status" = "b"))"#,
+        r#"(("status" = "a") & ("age" = "3"))"#,
+        r#"(("age" = "010") & ("age" = "10"))"#,
+        r#"(("age" ? ["1", "20"]) & ("age" > "5"))"#,
+        r#"(! (("age" > "30") & ("age" < "10")))"#,
+        r#"(("age" > "abc") & ("age" < "10"))"#,
+    ];
+    for s in satisfiable {
+        assert!(!parse(s).is_contradiction(&schema), "{}", s);
+    }
+
+    let text = parse(r#"(("age" > "9") & ("age" < "100"))"#);
+    assert!(text.is_contradiction(&Schema::new()));
+}
+
+#[test]
+fn test_any_builder() {
+    let any = Expression::any("sex", ["male", "female"]).unwrap();
+    assert!(matches!(any.node, Node::Any(ref k, ref t) if k == "sex" && t.len() == 2));
+
+    let err = Expression::any("sex", Vec::<String>::new());
+    assert!(matches!(err, Err(flp_gsp::Error::EmptyAny(ref k)) if k == "sex"));
+
+    let empty = Expression::any_empty_ok("sex", Vec::<String>::new());
+    assert!(matches!(empty.node, Node::Any(_, ref t) if t.is_empty()));
+
+    let parsed = parse(r#"("sex" ? [])"#);
+    assert!(matches!(parsed.node, Node::Any(_, ref t) if t.is_empty()));
+}
+
+#[test]
+fn test_parse_and_validate() {
+    use flp_gsp::schema::ValidationError;
+
+    let mut schema = Schema::new();
+    schema.insert("name".into(), ValueType::Text);
+    schema.insert("age".into(), ValueType::Integer);
+    schema.insert("active".into(), ValueType::Boolean);
+
+    let err = Expression::parse_and_validate(r#"("name" = "#, &schema);
+    assert!(matches!(err, Err(ValidationError::Parser(_))));
+
+    let err = Expression::parse_and_validate(r#"("nmae" = "Bob")"#, &schema);
+    assert!(matches!(err, Err(ValidationError::UnknownKey(ref k)) if k == "nmae"));
+
+    let err = Expression::parse_and_validate(r#"("age" * "1*")"#, &schema);
+    assert!(matches!(
+        err,
+        Err(ValidationError::Operator { ref key, operator: "*" }) if key == "age"
+    ));
+    let err = Expression::parse_and_validate(r#"("active" > "true")"#, &schema);
+    assert!(matches!(err, Err(ValidationError::Operator { .. })));
+
+    let err = Expression::parse_and_validate(r#"("age" ? ["1", "x"])"#, &schema);
+    assert!(matches!(
+        err,
+        Err(ValidationError::Value { ref key, ref value }) if key == "age" && value == "x"
+    ));
+
+    let validated =
+        Expression::parse_and_validate(r#"(("name" * "B*") & ("age" > "18"))"#, &schema).unwrap();
+    assert!(matches!(validated.expression().node, Node::And(..)));
+
+    #[cfg(feature = "sqlite")]
+    {
+        use flp_gsp::interpreter::sqlite::{SqliteRenames, SqliteType};
+
+        let (clause, binds) = validated.to_sqlite(&SqliteRenames::new()).unwrap();
+        assert_eq!(clause, r"(name LIKE ? ESCAPE '\' AND age > ?)");
+        assert_eq!(
+            binds,
+            vec![
+                SqliteType::Text(Some("B%".into())),
+                SqliteType::BigInt(Some(18))
+            ]
+        );
+    }
+
+    #[cfg(feature = "evaluate")]
+    {
+        use flp_gsp::interpreter::evaluate::{EvaluatePairs, EvaluateRule, EvaluateRules};
+
+        let mut rules = EvaluateRules::new();
+        rules.insert("name".into(), EvaluateRule::default());
+        rules.insert("age".into(), EvaluateRule::default());
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("name".into(), "Bob".into());
+        pairs.insert("age".into(), "20".into());
+        assert!(validated.evaluate(&rules, &pairs));
+    }
+}
+
+#[test]
+fn test_replace_at() {
+    let expression = parse(r#"((("a" = "1") | ("b" = "2")) & (! ("c" = "3")))"#);
+    assert!(matches!(
+        expression.get_at(&[]).unwrap().node,
+        Node::And(..)
+    ));
+    assert!(matches!(
+        expression.get_at(&[0, 1]).unwrap().node,
+        Node::Equal(ref k, _) if k == "b"
+    ));
+    assert!(matches!(
+        expression.get_at(&[1, 0]).unwrap().node,
+        Node::Equal(ref k, _) if k == "c"
+    ));
+    assert!(matches!(
+        expression.get_at(&[1, 1]),
+        Err(flp_gsp::Error::InvalidPath(ref p)) if p == &[1, 1]
+    ));
+    assert!(expression.get_at(&[0, 0, 0]).is_err());
+    assert!(expression.get_at(&[2]).is_err());
+
+    let edited = expression
+        .replace_at(&[0], parse(r#"("d" > "4")"#))
+        .unwrap();
+    assert_eq!(
+        edited.describe(&Phrases::default()),
+        r#"(d is greater than "4" and not c is "3")"#
+    );
+    assert!(edited.replace_at(&[0, 0], parse(r#"("e" -)"#)).is_err());
+}
+
+#[test]
+fn test_annotated() {
+    use flp_gsp::annotate::Annotated;
+
+    #[derive(Debug, PartialEq)]
+    enum Origin {
+        Default,
+        User,
+    }
+
+    fn rename(expression: Expression) -> Expression {
+        let node = match expression.node {
+            Node::And(left, right) => Node::And(Box::new(rename(*left)), Box::new(rename(*right))),
+            Node::Equal(key, target) => Node::Equal(format!("t.{}", key), target),
+            node => node,
+        };
+        Expression { node }
+    }
+
+    let mut annotated = Annotated::new(parse(r#"(("tenant" = "t1") & ("name" = "Bob"))"#));
+    annotated.annotate(&[0], Origin::Default).unwrap();
+    annotated.annotate(&[1], Origin::User).unwrap();
+    assert!(annotated.annotate(&[2], Origin::User).is_err());
+
+    let annotated = annotated.map(rename);
+    assert!(matches!(
+        annotated.expression().get_at(&[0]).unwrap().node,
+        Node::Equal(ref k, _) if k == "t.tenant"
+    ));
+    assert_eq!(annotated.meta(&[0]), Some(&Origin::Default));
+    assert_eq!(annotated.meta(&[1]), Some(&Origin::User));
+    assert_eq!(annotated.meta(&[]), None);
+
+    let annotated = annotated
+        .replace_at(&[1], parse(r#"("age" > "1")"#))
+        .unwrap();
+    assert_eq!(
+        annotated.annotations().collect::<Vec<_>>(),
+        vec![(&[0][..], &Origin::Default)]
+    );
+    assert_eq!(
+        annotated.into_expression(),
+        parse(r#"(("t.tenant" = "t1") & ("age" > "1"))"#)
+    );
+}
+
+#[test]
+fn test_try_map_keys() {
+    let aliases = [("nm", "name"), ("yrs", "age")];
+    let resolve = |key: &str| {
+        aliases
+            .iter()
+            .find(|(alias, _)| *alias == key)
+            .map(|(_, key)| key.to_string())
+            .ok_or_else(|| format!("unknown key {}", key))
+    };
+
+    let mapped = parse(r#"(("nm" = "Bob") & (! ("yrs" >= "18")))"#)
+        .try_map_keys(resolve)
+        .unwrap();
+    assert_eq!(
+        mapped.describe(&Phrases::default()),
+        r#"(name is "Bob" and not age is at least "18")"#
+    );
+
+    let mut seen = Vec::new();
+    let err = parse(r#"(("nm" = "Bob") | (("zz" -) & ("yrs" < "3")))"#).try_map_keys(|key| {
+        seen.push(key.to_string());
+        resolve(key)
+    });
+    assert_eq!(err.unwrap_err(), "unknown key zz");
+    assert_eq!(seen, vec!["nm", "zz"]);
+
+    let mapped = parse(r#"("laptop" & ("name" ? ["a"]))"#).map_keys(|key| format!("t.{}", key));
+    assert_eq!(
+        mapped.describe(&Phrases::default()),
+        r#"(contains "laptop" and t.name is one of a)"#
+    );
+}
+
+#[test]
+fn test_diff() {
+    use flp_gsp::diff::{Change, Condition};
+
+    let condition = |key: &str, operator, negated, value: &str| Condition {
+        key: key.into(),
+        operator,
+        negated,
+        value: value.into(),
+    };
+
+    let old = parse(r#"(("status" = "active") & ("age" > "18"))"#);
+    let new = parse(r#"(("status" = "active") & (("age" > "21") & !("tags" ? ["a", "b"])))"#);
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            Change::ValueChanged {
+                old: condition("age", ">", false, "18"),
+                new: condition("age", ">", false, "21"),
+            },
+            Change::Added(condition("tags", "?", true, r#"["a", "b"]"#)),
+        ]
+    );
+    assert_eq!(
+        new.diff(&old),
+        vec![
+            Change::ValueChanged {
+                old: condition("age", ">", false, "21"),
+                new: condition("age", ">", false, "18"),
+            },
+            Change::Removed(condition("tags", "?", true, r#"["a", "b"]"#)),
+        ]
+    );
+
+    // Only conditions are compared, not how they are combined.
+    assert!(
+        old.diff(&parse(r#"(("age" > "18") | "status" = "active")"#))
+            .is_empty()
+    );
+    // A negated condition is another kind of condition.
+    assert_eq!(
+        old.diff(&parse(r#"(("status" = "active") & !("age" > "18"))"#)),
+        vec![
+            Change::Removed(condition("age", ">", false, "18")),
+            Change::Added(condition("age", ">", true, "18")),
+        ]
+    );
+}
+
+#[test]
+fn test_clone_eq() {
+    let expression = parse(r#"(!("a" = "1") & (("b" > "2") | ("c" ? ["x", "y"])))"#);
+    assert_eq!(expression.clone(), expression);
+    assert_eq!(
+        expression,
+        parse(r#"((! "a" = "1") & ("b" > "2" | "c" ? ["x", "y"]))"#)
+    );
+    assert_ne!(
+        expression,
+        parse(r#"(!("a" = "1") & (("b" > "2") | ("c" ? ["y", "x"])))"#)
+    );
+    assert_ne!(expression, parse(r#"(!("a" = "1") & ("b" > "2"))"#));
+}
+
+#[test]
+fn test_keys() {
+    let expression = parse(
+        r#"(((! "age" -) & ("age" > "18")) & (("sex" ? ["male", "Male"] | "name" ~ "Jack") | (!("name" * "J*") & ("laptop" & ("tags" ? {tags})))))"#,
+    );
+    assert_eq!(
+        expression.keys().into_iter().collect::<Vec<_>>(),
+        vec!["age", "name", "sex", "tags"]
+    );
+    assert!(parse(r#"("laptop")"#).keys().is_empty());
+}
+
+#[test]
+fn test_depth_and_node_count() {
+    let leaf = parse(r#""name" = "Bob""#);
+    assert_eq!(leaf.depth(), 1);
+    assert_eq!(leaf.node_count(), 1);
+
+    let balanced = parse(r#"((("a" = "1") & ("b" = "2")) | (("c" = "3") & ("d" = "4")))"#);
+    assert_eq!(balanced.depth(), 3);
+    assert_eq!(balanced.node_count(), 7);
+
+    let unbalanced = parse(r#"!("a" = "1" & !("b" -))"#);
+    assert_eq!(unbalanced.depth(), 4);
+    assert_eq!(unbalanced.node_count(), 5);
+}
+
+#[test]
+fn test_leaves() {
+    let expression = parse(
+        r#"((("a" = "1") | !("b" * "x*")) & (("c" ? ["2", "3"]) & !(("laptop") | ("d" -))))"#,
+    );
+    assert_eq!(
+        expression.leaves().cloned().collect::<Vec<_>>(),
+        vec![
+            Node::Equal("a".into(), "1".into()),
+            Node::Wildcard("b".into(), "x*".into()),
+            Node::Any("c".into(), vec!["2".into(), "3".into()]),
+            Node::FullText("laptop".into()),
+            Node::Null("d".into()),
+        ]
+    );
+
+    let leaf = parse(r#""a" = "1""#);
+    assert_eq!(leaf.leaves().collect::<Vec<_>>(), [&leaf.node]);
+}
+
+#[test]
+fn test_to_sql_like_string() {
+    let cases = [
+        (r#"("name" = "Bob")"#, "name = 'Bob'"),
+        (r#"("name" != "O'Brien")"#, "name <> 'O''Brien'"),
+        (r#"("name" ~ "bob")"#, "name ILIKE 'bob'"),
+        (r#"("age" > "30")"#, "age > '30'"),
+        (r#"("age" < "30")"#, "age < '30'"),
+        (r#"("age" >= "30")"#, "age >= '30'"),
+        (r#"("age" <= "30")"#, "age <= '30'"),
+        (r#"("name" * "J?c*")"#, "name LIKE 'J_c%'"),
+        (r#"("name" % "Bob")"#, "name SOUNDS LIKE 'Bob'"),
+        (r#"("sex" ? ["male", "other"])"#, "sex IN ('male', 'other')"),
+        (r#"("role" ? {roles})"#, "role IN :roles"),
+        (r#"("tags" ^ ["a", "b"])"#, "tags INTERSECTS ('a', 'b')"),
+        (r#"("deleted_at" -)"#, "deleted_at IS NULL"),
+        (r#"(! "deleted_at" -)"#, "deleted_at IS NOT NULL"),
+        (r#"("laptop")"#, "MATCH('laptop')"),
+        (
+            r#"("location" @ "52.5,13.4,1000")"#,
+            "location WITHIN 1000 m OF (52.5, 13.4)",
+        ),
+        (r#"("name" # >= "3")"#, "LENGTH(name) >= 3"),
+        (r#"("e-mail" = "a@b.c")"#, r#""e-mail" = 'a@b.c'"#),
+        (
+            r#"(("name" = "Bob") & (! ("age" > "30") | "sex" ? ["male", "other"]))"#,
+            "name = 'Bob' AND (NOT age > '30' OR sex IN ('male', 'other'))",
+        ),
+        (
+            r#"((("a" = "1") & ("b" = "2")) & ("c" = "3"))"#,
+            "a = '1' AND b = '2' AND c = '3'",
+        ),
+        (
+            r#"!((("a" = "1") | ("b" = "2")) & ("c" = "3"))"#,
+            "NOT ((a = '1' OR b = '2') AND c = '3')",
+        ),
+    ];
+    for (s, expected) in cases {
+        assert_eq!(parse(s).to_sql_like_string(), expected, "{}", s);
+    }
+    #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+    assert_eq!(
+        parse(r#"("name" $ "^J")"#).to_sql_like_string(),
+        "name REGEXP '^J'"
+    );
+}
+
+#[test]
+fn test_to_builder_code() {
+    let expression = parse(
+        r#"(("name" = "Bob \"B\"") & (!("age" > "30") | (("sex" ? ["male"]) & ("name" # >= "3"))))"#,
+    );
+    assert_eq!(
+        expression.to_builder_code(),
+        r#"Expression::equal("name", "Bob \"B\"").and(Expression::greater("age", "30").not().or(Expression::any_empty_ok("sex", ["male"]).and(Expression::length("name", CmpOp::GreaterOrEqual, 3))))"#
+    );
+    // The code above, as generated.
+    let built =
+        Expression::equal("name", "Bob \"B\"").and(Expression::greater("age", "30").not().or(
+            Expression::any_empty_ok("sex", ["male"]).and(Expression::length(
+                "name",
+                CmpOp::GreaterOrEqual,
+                3,
+            )),
+        ));
+    assert_eq!(built, expression);
+
+    assert_eq!(
+        parse(r#"(("location" @ "37.7,-122.4,5km") | ("tags" ^ []))"#).to_builder_code(),
+        r#"Expression::geo_within("location", 37.7, -122.4, 5000.0).or(Expression::intersects("tags", Vec::<String>::new()))"#
+    );
+}
+
+#[test]
+fn test_builder() {
+    let cases = [
+        (
+            Expression::equal("a", "1").and(Expression::null("b")),
+            r#"(("a" = "1") & ("b" -))"#,
+        ),
+        (
+            Expression::not_equal("a", "1")
+                .or(Expression::equal_ci("b", "x"))
+                .not(),
+            r#"!(("a" != "1") | ("b" ~ "x"))"#,
+        ),
+        (
+            Expression::greater("a", "1")
+                .and(Expression::less("a", "9"))
+                .and(
+                    Expression::greater_or_equal("b", "2").or(Expression::less_or_equal("b", "0")),
+                ),
+            r#"((("a" > "1") & ("a" < "9")) & (("b" >= "2") | ("b" <= "0")))"#,
+        ),
+        (
+            Expression::between("age", "18", "65").and(Expression::length("name", CmpOp::Less, 10)),
+            r#"(("age" @ ["18", "65"]) & ("name" # < "10"))"#,
+        ),
+        (
+            Expression::wildcard("name", "J*").or(Expression::fuzzy("name", "Jack")),
+            r#"(("name" * "J*") | ("name" % "Jack"))"#,
+        ),
+        (
+            Expression::any("sex", ["male", "female"])
+                .unwrap()
+                .and(Expression::any_param("role", "roles"))
+                .and(Expression::intersects("tags", ["a", "b"])),
+            r#"((("sex" ? ["male", "female"]) & ("role" ? {roles})) & ("tags" ^ ["a", "b"]))"#,
+        ),
+        (
+            Expression::full_text("laptop").and(Expression::geo_within("loc", 1.0, 2.0, 3.0)),
+            r#"("laptop" & ("loc" @ "1,2,3"))"#,
+        ),
+    ];
+    for (built, s) in cases {
+        assert_eq!(built, parse(s), "{}", s);
+    }
+    #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+    assert_eq!(Expression::regex("name", "^J"), parse(r#"("name" $ "^J")"#));
+}
+
+#[test]
+fn test_equality_constraints() {
+    let constraints = |s: &str| parse(s).equality_constraints();
+    let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+    assert_eq!(constraints(r#"("a" = "1")"#), vec![pair("a", "1")]);
+    assert_eq!(
+        constraints(r#"((("a" = "1") & ("b" > "2")) & (("c" = "3") & ("a" = "4")))"#),
+        vec![pair("a", "1"), pair("c", "3"), pair("a", "4")]
+    );
+    // Equalities under an `Or` or a `Not` need not hold.
+    assert_eq!(
+        constraints(r#"((("a" = "1") | ("b" = "2")) & ("c" = "3"))"#),
+        vec![pair("c", "3")]
+    );
+    assert_eq!(
+        constraints(r#"((! "a" = "1") & (("b" = "2") & !(("c" = "3") & ("d" = "4"))))"#),
+        vec![pair("b", "2")]
+    );
+    assert!(constraints(r#"(("a" = "1") | ("a" = "2"))"#).is_empty());
+    assert!(constraints(r#"(("a" != "1") & ("b" ~ "2"))"#).is_empty());
+}
+
+#[test]
+fn test_matched_values() {
+    let values = |s: &str| {
+        parse(s).matched_values("tenant").map(|values| {
+            let mut values = values.into_iter().collect::<Vec<_>>();
+            values.sort();
+            values
+        })
+    };
+
+    assert_eq!(values(r#"("tenant" = "a")"#), Some(vec!["a".into()]));
+    assert_eq!(
+        values(r#"(("tenant" = "a") & ("age" > "18"))"#),
+        Some(vec!["a".into()])
+    );
+    assert_eq!(
+        values(r#"("tenant" ? ["a", "b", "c"])"#),
+        Some(vec!["a".into(), "b".into(), "c".into()])
+    );
+    assert_eq!(
+        values(r#"(("tenant" ? ["a", "b", "c"]) & ("tenant" ? ["b", "c", "d"]))"#),
+        Some(vec!["b".into(), "c".into()])
+    );
+    assert_eq!(
+        values(r#"((("tenant" = "a") | ("tenant" = "b")) & ("age" > "18"))"#),
+        Some(vec!["a".into(), "b".into()])
+    );
+    assert_eq!(
+        values(r#"(("tenant" = "a") & ("tenant" = "b"))"#),
+        Some(vec![])
+    );
+
+    assert_eq!(values(r#"("age" > "18")"#), None);
+    assert_eq!(values(r#"("tenant" > "a")"#), None);
+    assert_eq!(values(r#"("tenant" * "a*")"#), None);
+    assert_eq!(values(r#"!("tenant" = "a")"#), None);
+    assert_eq!(values(r#"(("tenant" = "a") | ("age" > "18"))"#), None);
+}
+
+#[test]
+fn test_visitor() {
+    #[derive(Default)]
+    struct Leaves {
+        count: usize,
+        wildcards: Vec<String>,
+    }
+    impl Visitor for Leaves {
+        fn visit_node(&mut self, node: &Node) {
+            if !matches!(node, Node::And(..) | Node::Or(..) | Node::Not(_)) {
+                self.count += 1;
+            }
+            flp_gsp::walk_node(self, node);
+        }
+
+        fn visit_wildcard(&mut self, key: &str, _target: &str) {
+            self.wildcards.push(key.to_string());
+        }
+    }
+
+    let mut leaves = Leaves::default();
+    parse(r#"((("a" = "1") | !("b" * "x*")) & (("c" ? ["1", "2"]) & ("d" * "y")))"#)
+        .accept(&mut leaves);
+    assert_eq!(leaves.count, 4);
+    assert_eq!(leaves.wildcards, vec!["b", "d"]);
+}
+
+#[test]
+fn test_simplify() {
+    let cases = [
+        (r#"!!("a" = "1")"#, r#"("a" = "1")"#),
+        (r#"!!!("a" = "1")"#, r#"!("a" = "1")"#),
+        (r#"!!!!("a" = "1")"#, r#"("a" = "1")"#),
+        (
+            r#"((("a" = "1") & ("b" = "2")) & ("c" = "3"))"#,
+            r#"((("a" = "1") & ("b" = "2")) & ("c" = "3"))"#,
+        ),
+        (
+            r#"(("a" = "1") & (("b" = "2") & (("c" = "3") & ("d" = "4"))))"#,
+            r#"(((("a" = "1") & ("b" = "2")) & ("c" = "3")) & ("d" = "4"))"#,
+        ),
+        (
+            r#"(("a" = "1") | (!!(("b" = "2") | ("c" = "3")) & ("d" = "4")))"#,
+            r#"(("a" = "1") | ((("b" = "2") | ("c" = "3")) & ("d" = "4")))"#,
+        ),
+        (
+            r#"(("a" = "1") & !!(("b" = "2") & !!!("c" = "3")))"#,
+            r#"((("a" = "1") & ("b" = "2")) & !("c" = "3"))"#,
+        ),
+        (
+            r#"!(("a" = "1") | (("b" = "2") | ("c" = "3")))"#,
+            r#"!((("a" = "1") | ("b" = "2")) | ("c" = "3"))"#,
+        ),
+    ];
+    for (s, expected) in cases {
+        let simplified = parse(s).simplify();
+        assert_eq!(simplified, parse(expected), "{}", s);
+        assert_eq!(simplified.clone().simplify(), simplified, "{}", s);
+    }
+}
+
+#[test]
+fn test_strip_redundant_not() {
+    let cases = [
+        (r#"!!("a" = "1")"#, r#"("a" = "1")"#),
+        (r#"!!!("a" = "1")"#, r#"!("a" = "1")"#),
+        (
+            r#"(("a" = "1") & (!!("b" = "2") | !(!!("c" = "3") & ("d" = "4"))))"#,
+            r#"(("a" = "1") & (("b" = "2") | !(("c" = "3") & ("d" = "4"))))"#,
+        ),
+        (
+            r#"!(!("a" = "1") | !("b" = "2"))"#,
+            r#"!(!("a" = "1") | !("b" = "2"))"#,
+        ),
+    ];
+    for (s, expected) in cases {
+        assert_eq!(parse(s).strip_redundant_not(), parse(expected), "{}", s);
+    }
+}
+
+#[test]
+fn test_combine_not() {
+    let cases = [
+        (r#"!!("a" = "1")"#, r#"("a" = "1")"#),
+        (
+            r#"!(!("a" = "1") | !("b" = "2"))"#,
+            r#"(("a" = "1") & ("b" = "2"))"#,
+        ),
+        (
+            r#"!(!("a" = "1") & !("b" = "2"))"#,
+            r#"(("a" = "1") | ("b" = "2"))"#,
+        ),
+        (
+            r#"(!("a" = "1") & !("b" = "2"))"#,
+            r#"!(("a" = "1") | ("b" = "2"))"#,
+        ),
+        (
+            r#"((!("a" = "1") | !("b" = "2")) & !("c" = "3"))"#,
+            r#"!((("a" = "1") & ("b" = "2")) | ("c" = "3"))"#,
+        ),
+        // Nothing to combine, or as many negations either way.
+        (
+            r#"(!("a" = "1") & ("b" = "2"))"#,
+            r#"(!("a" = "1") & ("b" = "2"))"#,
+        ),
+        (
+            r#"!(("a" = "1") & ("b" = "2"))"#,
+            r#"!(("a" = "1") & ("b" = "2"))"#,
+        ),
+        (
+            r#"(!("a" = "1") | (!("b" = "2") & ("c" = "3")))"#,
+            r#"(!("a" = "1") | (!("b" = "2") & ("c" = "3")))"#,
+        ),
+    ];
+    for (s, expected) in cases {
+        let combined = parse(s).combine_not();
+        assert_eq!(combined, parse(expected), "{}", s);
+        assert_eq!(combined.clone().combine_not(), combined, "{}", s);
+    }
+}
+
+#[test]
+fn test_push_negations() {
+    let cases = [
+        (r#"!!("a" = "1")"#, r#"("a" = "1")"#),
+        (r#"!("a" = "1")"#, r#"("a" != "1")"#),
+        (r#"!("a" != "1")"#, r#"("a" = "1")"#),
+        (r#"!("a" > "1")"#, r#"("a" <= "1")"#),
+        (r#"!("a" < "1")"#, r#"("a" >= "1")"#),
+        (r#"!("a" >= "1")"#, r#"("a" < "1")"#),
+        (r#"!("a" <= "1")"#, r#"("a" > "1")"#),
+        (r#"!("a" # > "1")"#, r#"("a" # <= "1")"#),
+        (
+            r#"!(("a" = "1") & ("b" > "2"))"#,
+            r#"(("a" != "1") | ("b" <= "2"))"#,
+        ),
+        (
+            r#"!(("a" = "1") | ("b" > "2"))"#,
+            r#"(("a" != "1") & ("b" <= "2"))"#,
+        ),
+        (
+            r#"!(("a" = "1") & !(("b" = "2") | !("c" < "3")))"#,
+            r#"(("a" != "1") | (("b" = "2") | ("c" >= "3")))"#,
+        ),
+        // Without a natural inverse.
+        (r#"!("a" # = "1")"#, r#"!("a" # = "1")"#),
+        (r#"!("a" -)"#, r#"!("a" -)"#),
+        (r#"!("a" ~ "x")"#, r#"!("a" ~ "x")"#),
+        (
+            r#"!(("a" * "x*") | ("b" ? ["1", "2"]))"#,
+            r#"(!("a" * "x*") & !("b" ? ["1", "2"]))"#,
+        ),
+        (
+            r#"(("a" = "1") & !!!("b" * "x"))"#,
+            r#"(("a" = "1") & !("b" * "x"))"#,
+        ),
+    ];
+    for (s, expected) in cases {
+        assert_eq!(parse(s).push_negations(), parse(expected), "{}", s);
+    }
+}
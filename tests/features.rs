@@ -0,0 +1,34 @@
+//! The parser builds without any feature, and the default features add `evaluate` and `sqlite`.
+//! CI checks every other feature on its own.
+
+use flp_gsp::Expression;
+
+#[test]
+fn test_core() {
+    let expression = r#"(("age" > "18") & ("name" = "Jack"))"#.parse::<Expression>().unwrap();
+    assert_eq!(
+        expression.to_string(),
+        r#"(("age" > "18") & ("name" = "Jack"))"#
+    );
+}
+
+#[cfg(all(feature = "evaluate", feature = "sqlite"))]
+#[test]
+fn test_default_features() {
+    use std::collections::HashMap;
+
+    use flp_gsp::interpreter::{evaluate, sqlite};
+
+    let expression = r#"("name" = "Jack")"#.parse::<Expression>().unwrap();
+
+    let mut rules = evaluate::EvaluateRules::new();
+    rules.insert("name".into(), evaluate::EvaluateRule::default());
+    let record = HashMap::from([("name".to_string(), "Jack".to_string())]);
+    assert!(evaluate::interpret(&expression, &rules, &record));
+
+    let mut types = sqlite::SqliteTypes::new();
+    types.insert("name".into(), sqlite::SqliteType::Text(None));
+    let (clause, _) =
+        sqlite::interpret(&expression, &sqlite::SqliteRenames::new(), &types).unwrap();
+    assert_eq!(clause, "name = ?");
+}
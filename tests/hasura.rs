@@ -19,4 +19,7 @@ fn test_sqlite() {
         clause,
         "{_and:[{_and:[{_and:[{_not:{age:{_is_null:true}}},{_not:{age:{_gt:18}}}]},{_or:[{sex:{_in:[\"male\",\"Male\"]}},{sex:{_ilike:\"Female\"}}]}]},{name:{_ilike:\"J_c%\"}}]}"
     );
+
+    let expression = Expression::conjoin_all(Vec::new());
+    assert_eq!(interpret(&expression, &types).unwrap(), "{}");
 }
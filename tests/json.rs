@@ -0,0 +1,140 @@
+#![cfg(feature = "json")]
+
+use flp_gsp::{Expression, interpreter::json::*};
+use serde_json::json;
+
+fn pairs() -> JsonPairs {
+    let mut pairs = JsonPairs::new();
+    pairs.insert("name".into(), json!("Jack"));
+    pairs.insert("age".into(), json!(10));
+    pairs.insert("score".into(), json!(9.5));
+    pairs.insert("active".into(), json!(true));
+    pairs.insert("tags".into(), json!(["a", "b"]));
+    pairs.insert("deleted".into(), json!(null));
+    pairs
+}
+
+fn matches(s: &str) -> bool {
+    interpret_map(&s.parse::<Expression>().unwrap(), &pairs())
+}
+
+#[test]
+fn test_json_map_numeric() {
+    // Numerically 10 > 9, while as text "10" < "9".
+    assert!(matches(r#"("age" > "9")"#));
+    assert!(!matches(r#"("age" < "9")"#));
+    assert!(matches(r#"("age" = "10.0")"#));
+    assert!(matches(r#"("score" < "10")"#));
+    assert!(matches(r#"("score" > "9.25")"#));
+    assert!(matches(r#"("age" ? ["1", "10"])"#));
+    assert!(!matches(r#"("age" = "ten")"#));
+    assert!(matches(r#"("age" * "1?")"#));
+}
+
+#[test]
+fn test_json_map_boolean() {
+    assert!(matches(r#"("active" = "true")"#));
+    assert!(!matches(r#"("active" = "false")"#));
+    assert!(!matches(r#"("active" = "1")"#));
+    assert!(matches(r#"("active" ~ "TRUE")"#));
+}
+
+#[test]
+fn test_json_map_other() {
+    assert!(matches(r#"("name" = "Jack")"#));
+    assert!(matches(r#"("name" ~ "jack")"#));
+    assert!(matches(r#"("deleted" -)"#));
+    assert!(matches(r#"("missing" -)"#));
+    assert!(!matches(r#"("name" -)"#));
+    assert!(!matches(r#"("tags" = "a")"#));
+    assert!(!matches(r#"("tags" -)"#));
+    assert!(matches("()"));
+    assert!(!matches("!()"));
+}
+
+#[test]
+fn test_json_record() {
+    let records = [
+        json!({
+            "name": "Jack",
+            "address": { "city": "NYC", "zip": 10001, "geo": { "verified": true } },
+            "tags": ["a", "b"],
+            "file.name": "a.txt",
+        }),
+        json!({ "name": "Jill", "address": { "city": "Boston", "zip": null } }),
+        json!({ "name": "Joe" }),
+    ];
+    let filter = |s: &str| {
+        let expression = s.parse::<Expression>().unwrap();
+        records
+            .iter()
+            .filter(|record| interpret(&expression, record))
+            .map(|record| record["name"].as_str().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(filter(r#"("address.city" = "NYC")"#), ["Jack"]);
+    assert_eq!(filter(r#"("address.zip" > "9999")"#), ["Jack"]);
+    assert_eq!(filter(r#"("address.geo.verified" = "true")"#), ["Jack"]);
+    assert_eq!(filter(r#"("address.zip" -)"#), ["Jill", "Joe"]);
+    assert_eq!(
+        filter(r#"("address.city" * "B*" | "name" = "Joe")"#),
+        ["Jill", "Joe"]
+    );
+    assert_eq!(filter(r#"("tags.1" = "b")"#), ["Jack"]);
+    assert_eq!(filter(r#"("file\\.name" = "a.txt")"#), ["Jack"]);
+    // An object itself never matches a comparison.
+    assert!(filter(r#"("address" = "NYC")"#).is_empty());
+    assert!(filter(r#"("name.first" = "Jack")"#).is_empty());
+}
+
+#[test]
+fn test_json_record_path_delimiter() {
+    use flp_gsp::interpreter::json::interpret_map;
+
+    let record = json!({
+        "address": { "zip.code": 10001, "a/b": "slash" },
+        "file.name": "a.txt",
+    });
+    let matches = |s: &str| {
+        let record = JsonRecord {
+            value: &record,
+            path_delimiter: '/',
+        };
+        interpret_map(&s.parse::<Expression>().unwrap(), &record)
+    };
+
+    // Dots are part of the segments, and `\\/` is a literal `/`.
+    assert!(matches(r#"("address/zip.code" = "10001")"#));
+    assert!(matches(r#"("file.name" = "a.txt")"#));
+    assert!(matches(r#"("address/a\\/b" = "slash")"#));
+    assert!(!matches(r#"("address.zip.code" = "10001")"#));
+}
+
+#[test]
+fn test_json_record_queryable() {
+    use flp_gsp::interpreter::evaluate::{self, EvaluateRule, EvaluateRules};
+
+    let record = json!({
+        "address": { "city": "New York", "zip": 10001, "street": null },
+        "tags": ["a"],
+    });
+    let mut rules = EvaluateRules::new();
+    rules.insert("address.city".into(), EvaluateRule::default());
+    rules.insert("address.zip".into(), EvaluateRule::numeric());
+    rules.insert("tags.0".into(), EvaluateRule::default());
+    rules.insert("address.street".into(), EvaluateRule::default());
+    let matches = |s: &str| {
+        evaluate::interpret(
+            &s.parse::<Expression>().unwrap(),
+            &rules,
+            &JsonRecord::new(&record),
+        )
+    };
+
+    assert!(matches(r#"("address.city" ~ "new york")"#));
+    assert!(matches(r#"("address.zip" > "9999")"#));
+    assert!(matches(r#"("tags.0" = "a")"#));
+    assert!(matches(r#"("address.street" -)"#));
+    assert!(!matches(r#"("address.city" -)"#));
+}
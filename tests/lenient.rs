@@ -0,0 +1,71 @@
+use flp_gsp::{Expression, lenient::Fixup};
+
+fn lenient(input: &str) -> (String, Vec<Fixup>) {
+    let (result, fixups) = Expression::parse_lenient(input);
+    (result.unwrap().to_string(), fixups)
+}
+
+#[test]
+fn test_parse_lenient() {
+    assert_eq!(
+        lenient("(name = Bob) &"),
+        (
+            r#"("name" = "Bob")"#.to_string(),
+            vec![
+                Fixup::QuotedWord {
+                    offset: 1,
+                    word: "name".into()
+                },
+                Fixup::QuotedWord {
+                    offset: 8,
+                    word: "Bob".into()
+                },
+                Fixup::DroppedOperator {
+                    offset: 13,
+                    operator: '&'
+                },
+            ]
+        )
+    );
+    assert_eq!(
+        lenient(r#"(("age" > 18) | ("name" = "Bo"#),
+        (
+            r#"(("age" > "18") | ("name" = "Bo"))"#.to_string(),
+            vec![
+                Fixup::QuotedWord {
+                    offset: 10,
+                    word: "18".into()
+                },
+                Fixup::ClosedQuote,
+                Fixup::ClosedParens(2),
+            ]
+        )
+    );
+    assert_eq!(
+        lenient(r#"(("a" = "1") & ("b" ? {bs})))"#),
+        (
+            r#"(("a" = "1") & ("b" ? {bs}))"#.to_string(),
+            vec![Fixup::DroppedParen { offset: 28 }]
+        )
+    );
+}
+
+#[test]
+fn test_parse_lenient_unchanged() {
+    // A valid query is not corrected.
+    let (result, fixups) = Expression::parse_lenient(r#"(("a" = "x") & !("b" != "y"))"#);
+    assert!(result.is_ok());
+    assert!(fixups.is_empty());
+
+    // Corrections are still reported when the query cannot be parsed anyway.
+    let (result, fixups) = Expression::parse_lenient("(a = )");
+    assert!(result.is_err());
+    assert_eq!(
+        fixups,
+        vec![Fixup::QuotedWord {
+            offset: 1,
+            word: "a".into()
+        }]
+    );
+    assert_eq!(fixups[0].to_string(), r#"quoted "a" at 1"#);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "log")]
+
+use flp_gsp::{
+    Expression,
+    interpreter::{
+        evaluate::{EvaluateRule, EvaluateRules},
+        log::*,
+    },
+};
+use regex::Regex;
+
+#[test]
+fn test_log() {
+    let s = r#"(("level" ? ["WARN", "ERROR"]) & ("message" * "*disk*"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let line_regex = Regex::new(r"^(\S+) \[(\w+)\] (.*)$").unwrap();
+    let mut groups = LogGroups::new();
+    groups.insert("time".into(), 1);
+    groups.insert("level".into(), 2);
+    groups.insert("message".into(), 3);
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("level".into(), EvaluateRule::default());
+    rules.insert("message".into(), EvaluateRule::default());
+
+    let lines = [
+        "2024-01-01T00:00:00Z [INFO] disk usage at 40%",
+        "2024-01-01T00:01:00Z [WARN] disk usage at 91%",
+        "2024-01-01T00:02:00Z [ERROR] network unreachable",
+        "garbage line",
+        "2024-01-01T00:03:00Z [ERROR] disk failure on /dev/sda",
+    ];
+
+    let matched = lines
+        .into_iter()
+        .filter(|line| interpret(&expression, &rules, &line_regex, &groups, line))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        matched,
+        vec![
+            "2024-01-01T00:01:00Z [WARN] disk usage at 91%",
+            "2024-01-01T00:03:00Z [ERROR] disk failure on /dev/sda",
+        ]
+    );
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "lua")]
+
+use flp_gsp::{Expression, interpreter::lua::*};
+
+#[test]
+fn test_lua() {
+    let s = r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut types = LuaTypes::new();
+    types.insert("age".into(), LuaType::Integer);
+    types.insert("sex".into(), LuaType::StringLike);
+    types.insert("name".into(), LuaType::StringLike);
+
+    assert_eq!(
+        interpret(&expression, &types).unwrap(),
+        r#"((((not (record.age == nil)) and (not (record.age > 18))) and ((record.sex == "male" or record.sex == "Male") or string.lower(record.sex) == string.lower("Female"))) and gsp.wildcard(record.name, "J?c*"))"#
+    );
+}
+
+#[test]
+fn test_lua_nodes() {
+    let mut types = LuaTypes::new();
+    types.insert("score".into(), LuaType::Float);
+    types.insert("active".into(), LuaType::Boolean);
+    types.insert("first name".into(), LuaType::StringLike);
+    types.insert("status".into(), LuaType::StringLike);
+    types.insert("end".into(), LuaType::StringLike);
+
+    let cases = [
+        (r#"("score" < "1.5")"#, r#"record.score < 1.5"#),
+        (r#"("score" > "2")"#, r#"record.score > 2.0"#),
+        (r#"("active" = "true")"#, r#"record.active == true"#),
+        (
+            r#"("first name" = "a\"b\\c")"#,
+            r#"record["first name"] == "a\"b\\c""#,
+        ),
+        (r#"("end" = "x")"#, r#"record["end"] == "x""#),
+        #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+        (
+            r#"("status" $ "^a.*$")"#,
+            r#"gsp.regex(record.status, "^a.*$")"#,
+        ),
+        (
+            r#"("status" % "open")"#,
+            r#"gsp.fuzzy(record.status, "open")"#,
+        ),
+        (r#"("status" ? [])"#, r#"false"#),
+        ("()", "true"),
+        (
+            r#"(! (("score" > "1") & ("active" = "true")))"#,
+            r#"(not (record.score > 1.0 and record.active == true))"#,
+        ),
+        (
+            r#"("status" ? {allowed})"#,
+            r#"gsp.any(record.status, params["allowed"])"#,
+        ),
+    ];
+    for (s, lua) in cases {
+        let expression = s.parse::<Expression>().unwrap();
+        assert_eq!(interpret(&expression, &types).unwrap(), lua);
+    }
+
+    let expression = r#"("score" > "abc")"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &types),
+        Err(Error::ParseFloat(_))
+    ));
+    let expression = r#"("missing" -)"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &types),
+        Err(Error::UnknownKey(_))
+    ));
+}
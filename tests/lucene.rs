@@ -0,0 +1,78 @@
+use flp_gsp::{Expression, ParseOptions, lucene::from_lucene};
+
+fn assert_same(lucene: &str, gsp: &str) {
+    // Bare Lucene terms are full-text terms.
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        format!("{:?}", from_lucene(lucene).unwrap()),
+        format!("{:?}", Expression::parse_with(gsp, &options).unwrap()),
+        "{}",
+        lucene
+    );
+}
+
+#[test]
+fn test_lucene() {
+    assert_same("name:Bob", r#"("name" = "Bob")"#);
+    assert_same(r#"name:"Bob Smith""#, r#"("name" = "Bob Smith")"#);
+    assert_same("name:J?c*", r#"("name" * "J?c*")"#);
+    assert_same(r"name:J\*ck", r#"("name" = "J*ck")"#);
+    assert_same(r"name:50\*off*", r#"("name" * "50\\*off*")"#);
+    assert_same(r"path:C\\dir\?*", r#"("path" * "C\\\\dir\\?*")"#);
+    #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+    assert_same("name:/J.*k/", r#"("name" $ "^(?:J.*k)$")"#);
+    assert_same("name:*", r#"(! ("name" -))"#);
+    assert_same("laptop", r#"("laptop")"#);
+    assert_same("age:[18 TO 30}", r#"(("age" >= "18") & ("age" < "30"))"#);
+    assert_same("age:{18 TO *]", r#"("age" > "18")"#);
+    assert_same("age:[* TO *]", r#"(! ("age" -))"#);
+    assert_same(
+        "name:Bob AND age:[18 TO 30]",
+        r#"(("name" = "Bob") & (("age" >= "18") & ("age" <= "30")))"#,
+    );
+    assert_same(
+        "a:1 OR b:2 AND NOT c:3",
+        r#"(("a" = "1") | (("b" = "2") & (! ("c" = "3"))))"#,
+    );
+    assert_same(
+        "(a:1 || b:2) -c:3 ORDER:x",
+        r#"(((("a" = "1") | ("b" = "2")) & (! ("c" = "3"))) & ("ORDER" = "x"))"#,
+    );
+}
+
+#[cfg(all(feature = "evaluate", any(feature = "regex", feature = "fancy-regex")))]
+#[test]
+fn test_lucene_regex_whole_term() {
+    use flp_gsp::interpreter::evaluate::*;
+
+    let mut rules = EvaluateRules::new();
+    rules.insert("name".into(), EvaluateRule::default());
+    let expression = from_lucene("name:/jo.*/").unwrap();
+    let matches = |name: &str| {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("name".into(), name.into());
+        interpret(&expression, &rules, &pairs)
+    };
+    assert!(matches("john"));
+    assert!(!matches("bojo"));
+}
+
+#[test]
+fn test_lucene_invalid() {
+    for input in [
+        "",
+        "name:",
+        "(a:1",
+        "age:[1 2]",
+        "age:[1 TO 2",
+        "J*ck",
+        r#"name:"Bob"#,
+        "a:1 OR",
+    ] {
+        assert!(from_lucene(input).is_err(), "{}", input);
+    }
+    assert!(from_lucene(&format!("{}a:1", "(NOT ".repeat(10_000))).is_err());
+}
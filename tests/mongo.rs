@@ -0,0 +1,105 @@
+#![cfg(feature = "mongo")]
+
+use flp_gsp::{Expression, ParseOptions, interpreter::mongo::*};
+use serde_json::json;
+
+fn types() -> MongoTypes {
+    let mut types = MongoTypes::new();
+    types.insert("age".into(), MongoType::Integer);
+    types.insert("sex".into(), MongoType::StringLike);
+    types.insert("name".into(), MongoType::StringLike);
+    types.insert("score".into(), MongoType::Float);
+    types.insert("active".into(), MongoType::Boolean);
+    types
+}
+
+#[test]
+fn test_mongo() {
+    let s = r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "F.male")) & ("name" * "J?c*" | ("active" = "true" & "score" @ ["1.5", "3"])))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = MongoRenames::new();
+    renames.insert("name".into(), "profile.name".into());
+
+    assert_eq!(
+        interpret(&expression, &renames, &types()).unwrap(),
+        json!({
+            "$and": [
+                {
+                    "$and": [
+                        {
+                            "$and": [
+                                { "age": { "$ne": null } },
+                                { "age": { "$not": { "$gt": 18 } } }
+                            ]
+                        },
+                        {
+                            "$or": [
+                                { "sex": { "$in": ["male", "Male"] } },
+                                { "sex": { "$regex": "\\AF\\.male\\z", "$options": "i" } }
+                            ]
+                        }
+                    ]
+                },
+                {
+                    "$or": [
+                        { "profile.name": { "$regex": "\\AJ.c.*\\z", "$options": "s" } },
+                        {
+                            "$and": [
+                                { "active": true },
+                                { "score": { "$gte": 1.5, "$lte": 3.0 } }
+                            ]
+                        }
+                    ]
+                }
+            ]
+        })
+    );
+
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    let interpret = |s: &str| {
+        let expression = Expression::parse_with(s, &options).unwrap();
+        interpret(&expression, &renames, &types()).unwrap()
+    };
+    assert_eq!(
+        interpret(r#"!("age" = "1" | "sex" != "male")"#),
+        json!({ "$nor": [{ "$or": [{ "age": 1 }, { "sex": { "$ne": "male" } }] }] })
+    );
+    assert_eq!(
+        interpret(r#"("name" = "Bob" & ("hello"))"#),
+        json!({ "$and": [{ "profile.name": "Bob" }, { "$text": { "$search": "hello" } }] })
+    );
+    assert_eq!(interpret("()"), json!({}));
+    assert_eq!(interpret("!()"), json!({ "$nor": [{}] }));
+}
+
+#[test]
+fn test_mongo_errors() {
+    let renames = MongoRenames::new();
+    let types = types();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &renames, &types);
+
+    assert!(matches!(
+        interpret(r#""height" = "1""#),
+        Err(Error::UnknownKey(key)) if key == "height"
+    ));
+    assert!(matches!(
+        interpret(r#""age" = "old""#),
+        Err(Error::ParseInt(_))
+    ));
+    assert!(matches!(
+        interpret(r#""score" = "NaN""#),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        interpret(r#""name" % "Bob""#),
+        Err(Error::UnsupportedNode(_))
+    ));
+    assert!(matches!(
+        interpret(r#""name" ? {names}"#),
+        Err(Error::UnresolvedParam(param)) if param == "names"
+    ));
+}
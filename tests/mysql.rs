@@ -21,7 +21,7 @@ fn test_mysql() {
 
     assert_eq!(
         clause,
-        "((((NOT age IS NULL) AND (NOT age > ?)) AND (gender IN (?, ?) OR gender LIKE ?)) AND t.name LIKE ?)"
+        "((((NOT `age` IS NULL) AND (NOT `age` > ?)) AND (`gender` IN (?, ?) OR `gender` LIKE ?)) AND `t`.`name` LIKE ?)"
     );
     assert_eq!(
         binds,
@@ -33,4 +33,61 @@ fn test_mysql() {
             MysqlType::StringLike(Some("J_c%".into()))
         ]
     );
+
+    // An empty list is always false, even for an unknown key.
+    let expression = r#"("missing" ? [])"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "FALSE");
+    assert!(binds.is_empty());
+
+    let expression = Expression::conjoin_all(Vec::new());
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "TRUE");
+    assert!(binds.is_empty());
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_mysql_quoting() {
+    let s = r#"((("order" > "3") | ("na`me" $ "^J(a|o)ck$")) & !(("order" <= "10") & ("tag" ? ["a", "b"])))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = MysqlRenames::new();
+    renames.insert("tag".into(), "item.group".into());
+
+    let mut types = MysqlTypes::new();
+    types.insert("order".into(), MysqlType::Int(None));
+    types.insert("na`me".into(), MysqlType::StringLike(None));
+    types.insert("tag".into(), MysqlType::StringLike(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "((`order` > ? OR `na``me` RLIKE ?) AND (NOT (`order` <= ? AND `item`.`group` IN (?, ?))))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            MysqlType::Int(Some(3)),
+            MysqlType::StringLike(Some("^J(a|o)ck$".into())),
+            MysqlType::Int(Some(10)),
+            MysqlType::StringLike(Some("a".into())),
+            MysqlType::StringLike(Some("b".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_mysql_escaped_wildcard() {
+    let expression = r#"("name" * "50\\*off_?*")"#.parse::<Expression>().unwrap();
+
+    let mut types = MysqlTypes::new();
+    types.insert("name".into(), MysqlType::StringLike(None));
+
+    let (clause, binds) = interpret(&expression, &MysqlRenames::new(), &types).unwrap();
+    assert_eq!(clause, "`name` LIKE ?");
+    assert_eq!(
+        binds,
+        vec![MysqlType::StringLike(Some(r"50*off\__%".into()))]
+    );
 }
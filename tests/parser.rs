@@ -0,0 +1,589 @@
+use flp_gsp::{CmpOp, Error, Expression, Node, ParseOptions};
+
+// Only the generator is used here.
+#[allow(dead_code)]
+mod common;
+
+use common::*;
+
+#[test]
+fn test_not_group() {
+    let expression = r#"!("name" = "Bob")"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::Not(inner) => {
+            assert!(matches!(inner.node, Node::Equal(ref k, ref v) if k == "name" && v == "Bob"))
+        }
+        _ => panic!("expected Not, got {:?}", expression.node),
+    }
+
+    let expression = r#"(("age" > "18") & !("name" = "Bob"))"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::And(_, right) => assert!(matches!(right.node, Node::Not(_))),
+        _ => panic!("expected And, got {:?}", expression.node),
+    }
+
+    let expression = r#"(!("name" = "Bob") | ("age" > "18"))"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::Or(left, _) => assert!(matches!(left.node, Node::Not(_))),
+        _ => panic!("expected Or, got {:?}", expression.node),
+    }
+}
+
+#[test]
+fn test_array_elements() {
+    let expression = r#"("name" ? [" ", "a,b",  "c"  ])"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::Any(_, targets) => assert_eq!(targets, vec![" ", "a,b", "c"]),
+        _ => panic!("expected Any, got {:?}", expression.node),
+    }
+
+    let expression = r#"("name" ? [])"#.parse::<Expression>().unwrap();
+    assert!(matches!(expression.node, Node::Any(_, ref targets) if targets.is_empty()));
+
+    assert!(r#"("name" ? ["a",,"b"])"#.parse::<Expression>().is_err());
+    assert!(r#"("name" ? ["a", , "b"])"#.parse::<Expression>().is_err());
+    assert!(r#"("name" ? ["a",])"#.parse::<Expression>().is_err());
+    assert!(r#"("name" ? [,"a"])"#.parse::<Expression>().is_err());
+    assert!(r#"("name" ? [,])"#.parse::<Expression>().is_err());
+}
+
+#[test]
+fn test_full_text() {
+    // A lone string is only a full-text term when enabled.
+    assert!(matches!(
+        r#"("laptop")"#.parse::<Expression>(),
+        Err(Error::Parser { offset: 9, .. })
+    ));
+
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    let expression = Expression::parse_with(r#"("laptop")"#, &options).unwrap();
+    assert!(matches!(expression.node, Node::FullText(ref t) if t == "laptop"));
+
+    let expression =
+        Expression::parse_with(r#"("gaming laptop" & ("price" < "1000"))"#, &options).unwrap();
+    match expression.node {
+        Node::And(left, right) => {
+            assert!(matches!(left.node, Node::FullText(ref t) if t == "gaming laptop"));
+            assert!(matches!(right.node, Node::Less(ref k, _) if k == "price"));
+        }
+        _ => panic!("expected And, got {:?}", expression.node),
+    }
+}
+
+#[test]
+fn test_dotted_key() {
+    // Keys are quoted strings, so a `.` is part of the key and only interpreters give it meaning.
+    let expression = r#"("address.city" = "NYC")"#.parse::<Expression>().unwrap();
+    assert!(
+        matches!(expression.node, Node::Equal(ref k, ref t) if k == "address.city" && t == "NYC")
+    );
+    let expression = r#""a\\.b.c" -"#.parse::<Expression>().unwrap();
+    assert!(matches!(expression.node, Node::Null(ref k) if k == r"a\.b.c"));
+}
+
+#[test]
+fn test_quoted_keys() {
+    // Keys are quoted text with the same escapes as values, so they may contain spaces,
+    // operator characters, quotes and any unicode.
+    let cases = [
+        (r#"("first name"="bob")"#, "first name"),
+        (r#"("status:code" = "200")"#, "status:code"),
+        (r#"("a&b|!c" = "1")"#, "a&b|!c"),
+        (r#"("x >= y" = "1")"#, "x >= y"),
+        (r#"("say \"hi\"" = "1")"#, r#"say "hi""#),
+        (r#"("tab\tkey" = "1")"#, "tab\tkey"),
+        (r#"("名前・氏名" = "1")"#, "名前・氏名"),
+    ];
+    for (s, key) in cases {
+        let expression = s.parse::<Expression>().unwrap();
+        assert!(
+            matches!(expression.node, Node::Equal(ref k, _) if k == key),
+            "{} {:?}",
+            s,
+            expression.node
+        );
+        assert_eq!(
+            expression.to_string().parse::<Expression>().unwrap(),
+            expression
+        );
+    }
+}
+
+#[test]
+fn test_or_equal() {
+    let expression = r#"("age">="18")"#.parse::<Expression>().unwrap();
+    assert!(
+        matches!(expression.node, Node::GreaterOrEqual(ref k, ref t) if k == "age" && t == "18")
+    );
+
+    let expression = r#"(("age" <= "30") & ("age" > "="))"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::And(left, right) => {
+            assert!(matches!(left.node, Node::LessOrEqual(_, ref t) if t == "30"));
+            assert!(matches!(right.node, Node::Greater(_, ref t) if t == "="));
+        }
+        _ => panic!("expected And, got {:?}", expression.node),
+    }
+}
+
+#[test]
+fn test_not_equal() {
+    let expression = r#"("a"!="b")"#.parse::<Expression>().unwrap();
+    assert!(matches!(expression.node, Node::NotEqual(ref k, ref t) if k == "a" && t == "b"));
+
+    let expression = r#"(!("a"="b"))"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::Not(inner) => assert!(matches!(inner.node, Node::Equal(ref k, _) if k == "a")),
+        _ => panic!("expected Not, got {:?}", expression.node),
+    }
+
+    let expression = r#"(!("a" != "b") & ("c" = "!="))"#.parse::<Expression>().unwrap();
+    match expression.node {
+        Node::And(left, right) => {
+            assert!(
+                matches!(left.node, Node::Not(ref inner) if matches!(inner.node, Node::NotEqual(..)))
+            );
+            assert!(matches!(right.node, Node::Equal(_, ref t) if t == "!="));
+        }
+        _ => panic!("expected And, got {:?}", expression.node),
+    }
+}
+
+#[test]
+fn test_bare_comparison() {
+    let parse = |s: &str| format!("{:?}", s.parse::<Expression>().unwrap());
+    assert_eq!(parse(r#""a"="b""#), parse(r#"("a"="b")"#));
+    assert_eq!(parse(r#""a" != "b"  "#), parse(r#"("a" != "b")"#));
+    assert!(matches!(
+        r#""status" = "active""#.parse::<Expression>().unwrap().node,
+        Node::Equal(ref k, ref t) if k == "status" && t == "active"
+    ));
+
+    // Only the whole input may be a bare comparison.
+    assert!(r#""a" = "b" & "c" = "d""#.parse::<Expression>().is_err());
+    assert!(r#"("a" = "b" & "c" = "d")"#.parse::<Expression>().is_ok());
+    assert!(r#"(("a" = "b") & ("c" = "d" | "e" = "f"))"#.parse::<Expression>().is_ok());
+    assert!(
+        r#"(("a" = "b") & (("c" = "d") | "e" = "f" & "g" = "h"))"#
+            .parse::<Expression>()
+            .is_err()
+    );
+    assert!(r#"! "a" = "b""#.parse::<Expression>().is_err());
+}
+
+#[test]
+fn test_match_all() {
+    let parse = |s: &str| s.parse::<Expression>();
+    assert_eq!(parse("()").unwrap(), Expression::match_all());
+    assert_eq!(parse("(  )").unwrap(), Expression::match_all());
+    assert_eq!(
+        parse(r#"(() & ("a" = "1"))"#).unwrap(),
+        Expression::match_all().and(Expression::equal("a", "1"))
+    );
+    assert_eq!(parse("!()").unwrap(), Expression::match_all().not());
+    assert_eq!(parse("(! ())").unwrap(), Expression::match_all().not());
+
+    // Like any relation, `()` needs an operator to be grouped again.
+    assert!(parse("(())").is_err());
+    assert!(parse("").is_err());
+}
+
+#[test]
+fn test_geo_within() {
+    let expression = r#"("location" @ "37.7, -122.4, 5km")"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        expression.node,
+        Node::GeoWithin(ref k, lat, lon, radius)
+            if k == "location" && lat == 37.7 && lon == -122.4 && radius == 5000.0
+    ));
+
+    for query in [
+        r#"("location" @ "37.7,-122.4")"#,
+        r#"("location" @ "91,-122.4,5km")"#,
+        r#"("location" @ "37.7,-180.5,5km")"#,
+        r#"("location" @ "37.7,-122.4,-5km")"#,
+        r#"("location" @ "37.7,-122.4,5ly")"#,
+        r#"("location" @ "north,west,5km")"#,
+    ] {
+        assert!(query.parse::<Expression>().is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn test_between() {
+    for query in [r#"("age" @ ["18", "65"])"#, r#"("age"@[ "18","65" ])"#] {
+        assert_eq!(
+            query.parse::<Expression>().unwrap().node,
+            Node::Between("age".into(), "18".into(), "65".into()),
+            "{}",
+            query
+        );
+    }
+
+    for query in [
+        r#"("age" @ [])"#,
+        r#"("age" @ ["18"])"#,
+        r#"("age" @ ["18", "65", "99"])"#,
+        r#"("age" @ ["18",])"#,
+    ] {
+        assert!(query.parse::<Expression>().is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn test_length() {
+    for (query, op) in [
+        (r#"("name" # = "10")"#, CmpOp::Equal),
+        (r##"("name"#>"10")"##, CmpOp::Greater),
+        (r#"("name" #< "10")"#, CmpOp::Less),
+        (r#"("name" # >= "10")"#, CmpOp::GreaterOrEqual),
+        (r#"("name" # <= "10")"#, CmpOp::LessOrEqual),
+    ] {
+        let expression = query.parse::<Expression>().unwrap();
+        assert_eq!(
+            expression.node,
+            Node::Length("name".into(), op, 10),
+            "{}",
+            query
+        );
+    }
+
+    for query in [
+        r#"("name" # > "ten")"#,
+        r#"("name" # > "-1")"#,
+        r#"("name" # > "+1")"#,
+        r#"("name" # > "1.5")"#,
+        r#"("name" # > "")"#,
+        r#"("name" # "10")"#,
+        r#"("name" # != "10")"#,
+    ] {
+        assert!(query.parse::<Expression>().is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn test_escapes() {
+    let target = |s: &str| match s.parse::<Expression>().unwrap().node {
+        Node::Equal(_, target) => target,
+        node => panic!("expected Equal, got {:?}", node),
+    };
+    assert_eq!(target(r#"("a" = "\u{1F600}")"#), "😀");
+    assert_eq!(target(r#"("a" = "a\nb")"#), "a\nb");
+    assert_eq!(target(r#"("a" = "\t\r\u00e9\u{41}")"#), "\t\réA");
+    assert_eq!(target(r#"("a" = "\\ \"")"#), "\\ \"");
+
+    for s in [
+        r#"("a" = "\x")"#,
+        r#"("a" = "\u{110000}")"#,
+        r#"("a" = "\u{D800}")"#,
+        r#"("a" = "\u{}")"#,
+        r#"("a" = "\u12")"#,
+    ] {
+        assert!(s.parse::<Expression>().is_err(), "{}", s);
+    }
+
+    let expression = Expression {
+        node: Node::Equal("a".into(), "x\ny".into()),
+    };
+    assert_eq!(expression.to_string(), r#"("a" = "x\ny")"#);
+}
+
+#[test]
+fn test_display_round_trip() {
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    let round_trip = |expression: &Expression| {
+        let displayed = expression.to_string();
+        let parsed = Expression::parse_with(&displayed, &options)
+            .unwrap_or_else(|err| panic!("{}: {}", displayed, err));
+        assert_eq!(&parsed, expression, "{}", displayed);
+    };
+
+    for s in [
+        r#"("a" = "b")"#,
+        r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#,
+        r#"!(("name" = "a\"b\\c") | ("score" < "1.5"))"#,
+        r#"(("status" ? {allowed}) & ("tags" ? []))"#,
+        r#"("laptop" & (("age" >= "18") & ("age" <= "30")))"#,
+        r#"(("a" != "b") | (("c" * "x?") & ("d" % "y")))"#,
+        #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+        r#"(("a" != "b") | (("c" $ "^x") & ("d" % "y")))"#,
+        r#"("location" @ "37.7,-122.4,5km")"#,
+        r#""bare" = "comparison""#,
+        r#"(("name" # > "10") | ("name" #<= "3"))"#,
+        r#"(("tags" ^ ["a", "b"]) & ("tags" ^ []))"#,
+        r#"(("age" @ ["18", "65"]) | ("location" @ "1,2,3"))"#,
+        r#"(() & !())"#,
+    ] {
+        round_trip(&Expression::parse_with(s, &options).unwrap());
+    }
+    assert_eq!(
+        r#"(("a" = "1") & (! "b" -))"#.parse::<Expression>().unwrap().to_string(),
+        r#"(("a" = "1") & !("b" -))"#
+    );
+
+    // Random trees with texts needing escapes.
+    let mut next = xorshift(0x2545_f491_4f6c_dd1d);
+    let texts = [
+        "",
+        "a",
+        "x\"y",
+        "back\\slash",
+        "\\\"",
+        "sp ace",
+        "(&|!)",
+        "ü",
+        "tab\tnew\nline\r",
+        "😀",
+    ];
+    fn generate(depth: u32, next: &mut dyn FnMut(u64) -> u64, texts: &[&str]) -> Expression {
+        let text =
+            |next: &mut dyn FnMut(u64) -> u64| texts[next(texts.len() as u64) as usize].to_string();
+        let node = match if depth == 0 { 3 + next(16) } else { next(19) } {
+            0 => Node::And(
+                Box::new(generate(depth - 1, next, texts)),
+                Box::new(generate(depth - 1, next, texts)),
+            ),
+            1 => Node::Or(
+                Box::new(generate(depth - 1, next, texts)),
+                Box::new(generate(depth - 1, next, texts)),
+            ),
+            2 => Node::Not(Box::new(generate(depth - 1, next, texts))),
+            3 => Node::Equal(text(next), text(next)),
+            4 => Node::NotEqual(text(next), text(next)),
+            5 => Node::EqualCI(text(next), text(next)),
+            6 => Node::Greater(text(next), text(next)),
+            7 => Node::Less(text(next), text(next)),
+            8 => Node::GreaterOrEqual(text(next), text(next)),
+            9 => Node::LessOrEqual(text(next), text(next)),
+            10 => Node::Wildcard(text(next), text(next)),
+            11 if cfg!(any(feature = "regex", feature = "fancy-regex")) => {
+                Node::Regex(text(next), text(next))
+            }
+            11 => Node::Wildcard(text(next), text(next)),
+            12 => Node::Any(text(next), (0..next(3)).map(|_| text(next)).collect()),
+            13 => Node::Null(text(next)),
+            14 => Node::Length(text(next), CmpOp::GreaterOrEqual, next(100) as usize),
+            15 => Node::Intersects(text(next), (0..next(3)).map(|_| text(next)).collect()),
+            16 => Node::Between(text(next), text(next), text(next)),
+            17 => Node::MatchAll,
+            _ => Node::FullText(text(next)),
+        };
+        Expression { node }
+    }
+    for _ in 0..200 {
+        round_trip(&generate(3, &mut next, &texts));
+    }
+}
+
+#[test]
+fn test_max_len() {
+    let options = ParseOptions {
+        max_len: Some(11),
+        ..Default::default()
+    };
+    let s = r#"("a" = "b")"#;
+    assert_eq!(s.len(), 11);
+    assert!(Expression::parse_with(s, &options).is_ok());
+    assert!(Expression::parse_with(r#"("a"="b")"#, &options).is_ok());
+
+    let s = r#"("a" = "bc")"#;
+    assert!(matches!(
+        Expression::parse_with(s, &options),
+        Err(Error::TooLong { len: 12, max: 11 })
+    ));
+    // The length is checked before parsing.
+    assert!(matches!(
+        Expression::parse_with(&"(".repeat(1_000_000), &options),
+        Err(Error::TooLong { .. })
+    ));
+    assert!(Expression::parse_with(s, &ParseOptions::default()).is_ok());
+}
+
+#[test]
+fn test_random_input() {
+    // Truncated and unbalanced input must fail cleanly rather than panic or loop.
+    let edge_cases = [
+        "",
+        "(",
+        ")",
+        "\"",
+        "\"\\",
+        "\"\\\"",
+        "\"\\u",
+        "\"\\u{",
+        "\"\\u{}\"",
+        "\"\\u{110000}\"",
+        "\"\\uD800\"",
+        r#""k" ? []"#,
+        r#""k" ? [,]"#,
+        r#""k" ^ []"#,
+        r#""k" @ """#,
+        r#""k" # > "#,
+        r#""k" ? {"#,
+        "&|!",
+        "!!!!",
+        "= != ~ > < >= <= * $ % ? - @ # ^",
+    ];
+    for s in edge_cases {
+        let _ = s.parse::<Expression>();
+        let _ = Expression::parse_lenient(s);
+    }
+
+    // Random sequences of grammar fragments. Whatever parses renders back to the same expression.
+    let mut next = xorshift(0x853c_49e6_748f_ea9b);
+    let fragments = [
+        "(",
+        ")",
+        "\"",
+        "\\",
+        "&",
+        "|",
+        "!",
+        "=",
+        "~",
+        ">",
+        "<",
+        "*",
+        "%",
+        "?",
+        "-",
+        "@",
+        "#",
+        "^",
+        "[",
+        "]",
+        ",",
+        "{",
+        "}",
+        " ",
+        "a",
+        "1",
+        "ü",
+        "😀",
+        "\\u{",
+        "\\u",
+        "\"k\"",
+        "\"v\"",
+        "\"0,0,1km\"",
+        "(\"k\" = \"v\")",
+        " & ",
+        " | ",
+        "!(",
+    ];
+    for _ in 0..5_000 {
+        let s = (0..next(24))
+            .map(|_| fragments[next(fragments.len() as u64) as usize])
+            .collect::<String>();
+        if let Ok(expression) = s.parse::<Expression>() {
+            let rendered = expression.to_string();
+            assert_eq!(
+                rendered.parse::<Expression>().ok(),
+                Some(expression),
+                "{}",
+                s
+            );
+        }
+        let _ = Expression::parse_lenient(&s);
+    }
+}
+
+#[test]
+fn test_max_depth() {
+    let too_deep = |s: &str, options: &ParseOptions| {
+        matches!(
+            Expression::parse_with(s, options),
+            Err(Error::Parser { ref message, .. }) if message == "max depth exceeded"
+        )
+    };
+    let options = ParseOptions::default();
+    let deep = format!(r#"{}"a" = "b"{}"#, "(".repeat(10_000), ")".repeat(10_000));
+    assert!(too_deep(&deep, &options));
+    let deep = format!(r#"{}("a" = "b")"#, "!".repeat(10_000));
+    assert!(too_deep(&deep, &options));
+
+    // `n` relations nested in each other.
+    let nested = |n: usize| {
+        (1..n).fold(r#"("a" = "1")"#.to_string(), |inner, _| {
+            format!(r#"(("a" = "1") & {})"#, inner)
+        })
+    };
+    let options = ParseOptions {
+        max_depth: 5,
+        ..Default::default()
+    };
+    assert!(Expression::parse_with(&nested(5), &options).is_ok());
+    assert!(too_deep(&nested(6), &options));
+    assert!(nested(128).parse::<Expression>().is_ok());
+    // At the first group too deep, which is the left side of the innermost `&`.
+    assert!(matches!(
+        nested(129).parse::<Expression>(),
+        Err(Error::Parser { offset: 1906, .. })
+    ));
+}
+
+#[test]
+fn test_left_nested_time() {
+    // Each level used to parse its left side once per alternative, doubling the time or worse.
+    let chain = (0..30).fold(r#"("a" = "b")"#.to_string(), |inner, _| {
+        format!(r#"({} | ("a" = "b"))"#, inner)
+    });
+    let start = std::time::Instant::now();
+    assert!(chain.parse::<Expression>().is_ok());
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn test_parse_error_offset() {
+    let cases = [
+        // Missing right operand.
+        (r#"("age" >)"#, 8, ")"),
+        (r#"(("a" = "1") & )"#, 15, ")"),
+        // Missing closing paren.
+        (r#"(("a" = "1")"#, 12, ""),
+        (r#"(("a" = "1") & ("b" ~ "2"]"#, 25, "]"),
+        // Missing operator.
+        (r#"("a" "1")"#, 5, r#""1")"#),
+        // Invalid value.
+        (r#"("a" # > "x")"#, 9, r#""x")"#),
+        ("", 0, ""),
+    ];
+    for (s, offset, remaining) in cases {
+        match s.parse::<Expression>() {
+            Err(Error::Parser {
+                offset: actual_offset,
+                remaining: actual_remaining,
+                ..
+            }) => {
+                assert_eq!(
+                    (actual_offset, actual_remaining.as_str()),
+                    (offset, remaining),
+                    "{}",
+                    s
+                );
+            }
+            other => panic!("{}: {:?}", s, other),
+        }
+    }
+    assert_eq!(
+        r#"("age" >)"#.parse::<Expression>().unwrap_err().to_string(),
+        "Parser error at 8: unexpected ')'"
+    );
+}
+
+#[cfg(not(any(feature = "regex", feature = "fancy-regex")))]
+#[test]
+fn test_regex_feature_disabled() {
+    for s in [r#""name" $ "^J""#, r#"(("age" > "18") & !("name" $ "^J"))"#] {
+        assert!(matches!(
+            s.parse::<Expression>(),
+            Err(Error::FeatureDisabled("regex"))
+        ));
+    }
+    assert!(r#""name" = "$""#.parse::<Expression>().is_ok());
+}
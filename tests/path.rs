@@ -0,0 +1,27 @@
+use flp_gsp::path::{DEFAULT_DELIMITER, split};
+
+#[test]
+fn test_split() {
+    assert_eq!(split("name", DEFAULT_DELIMITER), vec!["name"]);
+    assert_eq!(
+        split("address.city", DEFAULT_DELIMITER),
+        vec!["address", "city"]
+    );
+    assert_eq!(
+        split(r"file\.name.ext", DEFAULT_DELIMITER),
+        vec!["file.name", "ext"]
+    );
+    assert_eq!(split(r"a\\.b", DEFAULT_DELIMITER), vec![r"a\", "b"]);
+    assert_eq!(split(r"a\b", DEFAULT_DELIMITER), vec![r"a\b"]);
+    assert_eq!(split("a..b", DEFAULT_DELIMITER), vec!["a", "", "b"]);
+}
+
+#[test]
+fn test_split_custom_delimiter() {
+    assert_eq!(
+        split("example.com/host.name", '/'),
+        vec!["example.com", "host.name"]
+    );
+    assert_eq!(split(r"a\/b/c", '/'), vec!["a/b", "c"]);
+    assert_eq!(split(r"a\.b/c", '/'), vec![r"a\.b", "c"]);
+}
@@ -33,4 +33,40 @@ fn test_postgres() {
             PostgresType::StringLike(Some("J_c%".into()))
         ]
     );
+
+    // An empty list is always false, even for an unknown key.
+    let expression = r#"("missing" ? [])"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret(&expression, &renames, &types, 1).unwrap();
+    assert_eq!(clause, "FALSE");
+    assert!(binds.is_empty());
+
+    let expression = Expression::conjoin_all(Vec::new());
+    let (clause, binds) = interpret(&expression, &renames, &types, 1).unwrap();
+    assert_eq!(clause, "TRUE");
+    assert!(binds.is_empty());
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_postgres_placeholders() {
+    let s = r#"(("name" $ "^J") | (("age" ? ["1", "2", "3"]) & (("age" != "4") | "name" ~ "x")))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = PostgresRenames::new();
+    let mut types = PostgresTypes::new();
+    types.insert("age".into(), PostgresType::Int(None));
+    types.insert("name".into(), PostgresType::StringLike(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types, 3).unwrap();
+    assert_eq!(
+        clause,
+        "(name ~ $3 OR (age IN ($4, $5, $6) AND (age <> $7 OR name ILIKE $8)))"
+    );
+    assert_eq!(binds.len(), 6);
+
+    let (clause, _) = interpret_expression(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "(name ~ ?? OR (age IN (??, ??, ??) AND (age <> ?? OR name ILIKE ??)))"
+    );
 }
@@ -0,0 +1,65 @@
+#![cfg(feature = "postgrest")]
+
+use flp_gsp::{Expression, interpreter::postgrest::*};
+
+fn types() -> PostgrestTypes {
+    let mut types = PostgrestTypes::new();
+    types.insert("age".into(), PostgrestType::Integer);
+    types.insert("sex".into(), PostgrestType::StringLike);
+    types.insert("name".into(), PostgrestType::StringLike);
+    types.insert("tags".into(), PostgrestType::StringLike);
+    types
+}
+
+#[test]
+fn test_postgrest() {
+    let s = r#"(((((! "age" -) & ("age" > "18")) & ("sex" ~ "f_male")) & ("name" * "J?c*")) & (("tags" ? ["a", "b,c", "d\"e"]) & !("name" != "Bob")))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    assert_eq!(
+        interpret(&expression, &types()).unwrap(),
+        vec![
+            ("age".to_string(), "not.is.null".to_string()),
+            ("age".to_string(), "gt.18".to_string()),
+            ("sex".to_string(), "ilike.f\\_male".to_string()),
+            ("name".to_string(), "like.J_c*".to_string()),
+            ("tags".to_string(), "in.(a,\"b,c\",\"d\\\"e\")".to_string()),
+            ("name".to_string(), "not.neq.Bob".to_string()),
+        ]
+    );
+
+    // Matching everything needs no filter.
+    let expression = r#"(() & ("age" > "18"))"#.parse::<Expression>().unwrap();
+    assert_eq!(
+        interpret(&expression, &types()).unwrap(),
+        vec![("age".to_string(), "gt.18".to_string())]
+    );
+}
+
+#[test]
+fn test_postgrest_unsupported() {
+    for s in [
+        r#"(("age" > "18") | ("name" = "Bob"))"#,
+        r#"(("age" > "18") & (("name" = "Bob") | ("name" = "Alice")))"#,
+        r#"!(("age" > "18") & ("name" = "Bob"))"#,
+        r#"("name" % "Bob")"#,
+        r#"("name" * "50\\*off*")"#,
+        r#"("name" ~ "50*off")"#,
+        "!()",
+    ] {
+        let expression = s.parse::<Expression>().unwrap();
+        assert!(
+            matches!(
+                interpret(&expression, &types()),
+                Err(Error::UnsupportedNode(_))
+            ),
+            "{}",
+            s
+        );
+    }
+    let expression = r#"("height" = "180")"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &types()),
+        Err(Error::UnknownKey(_))
+    ));
+}
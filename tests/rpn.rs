@@ -0,0 +1,48 @@
+use flp_gsp::{Error, Expression, Node, rpn::RpnToken};
+
+#[test]
+fn test_rpn_round_trip() {
+    for s in [
+        r#"("name" = "Bob")"#,
+        r#"(("a" = "1") & !("b" -))"#,
+        r#"((("age" > "18") | ("sex" ? ["male", "Male"])) & !(("name" * "J?c*") | ("c" # <= "3")))"#,
+        r#"!!(("x" * "a?") | (("y" @ "1,2,3") & ("z" % "fuzz")))"#,
+        #[cfg(any(feature = "regex", feature = "fancy-regex"))]
+        r#"!(("x" $ "^a") | ("y" % "fuzz"))"#,
+    ] {
+        let expression = s.parse::<Expression>().unwrap();
+        let tokens = expression.to_rpn();
+        assert_eq!(Expression::from_rpn(&tokens).unwrap(), expression, "{}", s);
+    }
+
+    let expression = r#"(("a" = "1") & !("b" -))"#.parse::<Expression>().unwrap();
+    assert_eq!(
+        expression.to_rpn(),
+        vec![
+            RpnToken::Comparison(Node::Equal("a".into(), "1".into())),
+            RpnToken::Comparison(Node::Null("b".into())),
+            RpnToken::Not,
+            RpnToken::And,
+        ]
+    );
+}
+
+#[test]
+fn test_rpn_malformed() {
+    let a = RpnToken::Comparison(Node::Null("a".into()));
+    for tokens in [
+        vec![],
+        vec![RpnToken::Not],
+        vec![a.clone(), RpnToken::And],
+        vec![a.clone(), a.clone()],
+        vec![RpnToken::Comparison(
+            r#"(("a" -) & ("b" -))"#.parse::<Expression>().unwrap().node,
+        )],
+    ] {
+        assert!(
+            matches!(Expression::from_rpn(&tokens), Err(Error::MalformedRpn(_))),
+            "{:?}",
+            tokens
+        );
+    }
+}
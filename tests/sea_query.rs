@@ -0,0 +1,102 @@
+#![cfg(feature = "sea-query")]
+
+use flp_gsp::{Expression, interpreter::sea_query::*};
+use sea_query::{
+    Alias, Asterisk, MysqlQueryBuilder, PostgresQueryBuilder, Query, SqliteQueryBuilder, Value,
+};
+
+fn types() -> SeaQueryTypes {
+    let mut types = SeaQueryTypes::new();
+    types.insert("age".into(), SeaQueryType::Integer);
+    types.insert("sex".into(), SeaQueryType::StringLike);
+    types.insert("name".into(), SeaQueryType::StringLike);
+    types
+}
+
+#[test]
+fn test_sea_query() {
+    let s = r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = SeaQueryRenames::new();
+    renames.insert("sex".into(), "users.gender".into());
+
+    let condition = interpret(&expression, &renames, &types()).unwrap();
+    let query = Query::select()
+        .column(Asterisk)
+        .from(Alias::new("users"))
+        .cond_where(condition)
+        .to_owned();
+
+    assert_eq!(
+        query.to_string(SqliteQueryBuilder),
+        r#"SELECT * FROM "users" WHERE (NOT "age" IS NULL) AND (NOT "age" > 18) AND ("users"."gender" IN ('male', 'Male') OR LOWER("users"."gender") = 'female') AND "name" LIKE 'J_c%' ESCAPE '\'"#
+    );
+    assert_eq!(
+        query.to_string(MysqlQueryBuilder),
+        r#"SELECT * FROM `users` WHERE (NOT `age` IS NULL) AND (NOT `age` > 18) AND (`users`.`gender` IN ('male', 'Male') OR LOWER(`users`.`gender`) = 'female') AND `name` LIKE 'J_c%' ESCAPE '\\'"#
+    );
+
+    let (sql, values) = query.build(PostgresQueryBuilder);
+    assert_eq!(
+        sql,
+        r#"SELECT * FROM "users" WHERE (NOT "age" IS NULL) AND (NOT "age" > $1) AND ("users"."gender" IN ($2, $3) OR LOWER("users"."gender") = $4) AND "name" LIKE $5 ESCAPE E'\\'"#
+    );
+    assert_eq!(
+        values.0,
+        vec![
+            Value::BigInt(Some(18)),
+            Value::String(Some(Box::new("male".into()))),
+            Value::String(Some(Box::new("Male".into()))),
+            Value::String(Some(Box::new("female".into()))),
+            Value::String(Some(Box::new("J_c%".into()))),
+        ]
+    );
+}
+
+#[test]
+fn test_sea_query_errors() {
+    let renames = SeaQueryRenames::new();
+    let types = types();
+    let interpret = |s: &str| interpret(&s.parse::<Expression>().unwrap(), &renames, &types);
+
+    assert!(matches!(
+        interpret(r#""height" = "1""#),
+        Err(Error::UnknownKey(key)) if key == "height"
+    ));
+    assert!(matches!(
+        interpret(r#""age" = "old""#),
+        Err(Error::ParseInt(_))
+    ));
+    assert!(matches!(
+        interpret(r#""name" % "Bob""#),
+        Err(Error::UnsupportedNode(_))
+    ));
+    assert!(matches!(
+        interpret(r#""name" ? {names}"#),
+        Err(Error::UnresolvedParam(param)) if param == "names"
+    ));
+
+    // An empty list matches nothing, even on an unknown key.
+    let condition = interpret(r#"("missing" ? [])"#).unwrap();
+    let query = Query::select()
+        .column(Asterisk)
+        .from(Alias::new("users"))
+        .cond_where(condition)
+        .to_owned();
+    assert_eq!(
+        query.to_string(SqliteQueryBuilder),
+        r#"SELECT * FROM "users" WHERE 1 = 0"#
+    );
+
+    let condition = interpret("()").unwrap();
+    let query = Query::select()
+        .column(Asterisk)
+        .from(Alias::new("users"))
+        .cond_where(condition)
+        .to_owned();
+    assert_eq!(
+        query.to_string(SqliteQueryBuilder),
+        r#"SELECT * FROM "users" WHERE 1 = 1"#
+    );
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "serde")]
+
+use flp_gsp::{Expression, ParseOptions};
+
+const QUERIES: [&str; 6] = [
+    r#"((((! "age" -) & (! "age" > "18")) & ("sex" ? ["male", "Male"] | "sex" ~ "Female")) & "name" * "J?c*")"#,
+    r#"!(("name" = "a\"b\\c") | ("score" < "1.5"))"#,
+    r#"(("status" ? {allowed}) & ("tags" ? []))"#,
+    r#"("laptop" & (("age" >= "18") & ("age" != "30")))"#,
+    r#"(("location" @ "37.7,-122.4,5km") | (("name" $ "^J") & ("name" # <= "10")))"#,
+    r#""bare" % "comparison""#,
+];
+
+fn round_trip(expression: &Expression) -> Expression {
+    let json = serde_json::to_string(expression).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    for query in QUERIES {
+        let expression = Expression::parse_with(query, &options).unwrap();
+        assert_eq!(round_trip(&expression), expression, "{}", query);
+    }
+}
+
+#[test]
+fn test_serde_shape() {
+    let expression = r#"(("age" > "18") & !("name" # = "3"))"#.parse::<Expression>().unwrap();
+    assert_eq!(
+        serde_json::to_value(&expression).unwrap(),
+        serde_json::json!({
+            "type": "and",
+            "args": [
+                { "type": "greater", "args": ["age", "18"] },
+                {
+                    "type": "not",
+                    "args": { "type": "length", "args": ["name", "equal", 3] }
+                }
+            ]
+        })
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_serde_sqlite() {
+    use flp_gsp::interpreter::sqlite::*;
+
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("sex".into(), SqliteType::Text(None));
+    types.insert("name".into(), SqliteType::Text(None));
+    let expression = QUERIES[0].parse::<Expression>().unwrap();
+    assert_eq!(
+        interpret(&round_trip(&expression), &SqliteRenames::new(), &types).unwrap(),
+        interpret(&expression, &SqliteRenames::new(), &types).unwrap()
+    );
+}
+
+#[cfg(feature = "evaluate")]
+#[test]
+fn test_serde_evaluate() {
+    use flp_gsp::interpreter::evaluate::*;
+
+    let mut rules = EvaluateRules::new();
+    for key in ["age", "sex", "name"] {
+        rules.insert(key.into(), EvaluateRule::default());
+    }
+    let expression = QUERIES[0].parse::<Expression>().unwrap();
+    let deserialized = round_trip(&expression);
+    for (age, sex, name) in [
+        ("17", "Male", "Jack"),
+        ("19", "male", "Jack"),
+        ("", "female", "Jock"),
+        ("17", "other", "Jack"),
+    ] {
+        let mut pairs = EvaluatePairs::new();
+        pairs.insert("age".into(), age.into());
+        pairs.insert("sex".into(), sex.into());
+        pairs.insert("name".into(), name.into());
+        assert_eq!(
+            interpret(&deserialized, &rules, &pairs),
+            interpret(&expression, &rules, &pairs)
+        );
+    }
+}
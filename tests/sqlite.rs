@@ -1,6 +1,6 @@
 #![cfg(feature = "sqlite")]
 
-use flp_gsp::{Expression, interpreter::sqlite::*};
+use flp_gsp::{Expression, ParseOptions, interpreter::sqlite::*};
 
 #[test]
 fn test_sqlite() {
@@ -21,7 +21,7 @@ fn test_sqlite() {
 
     assert_eq!(
         clause,
-        "((((NOT age IS NULL) AND (NOT age > ?)) AND (gender IN (?, ?) OR gender LIKE ?)) AND t.name LIKE ?)"
+        "(((age IS NOT NULL AND (NOT age > ?)) AND (gender IN (?, ?) OR gender LIKE ? ESCAPE '\\')) AND t.name LIKE ? ESCAPE '\\')"
     );
     assert_eq!(
         binds,
@@ -33,4 +33,1051 @@ fn test_sqlite() {
             SqliteType::Text(Some("J_c%".into()))
         ]
     );
+
+    let expression = Expression::conjoin_all(Vec::new());
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "TRUE");
+    assert!(binds.is_empty());
+}
+
+#[test]
+fn test_sqlite_into() {
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let mut sql = String::from("SELECT * FROM users WHERE tenant = ? AND ");
+    let mut binds = vec![SqliteType::Integer(Some(7))];
+    let expression =
+        r#"(("age" > "18") | (! ("name" ? ["a", "b"])))"#.parse::<Expression>().unwrap();
+    interpret_into(
+        &expression,
+        &SqliteRenames::new(),
+        &types,
+        &mut sql,
+        &mut binds,
+    )
+    .unwrap();
+    sql.push_str(" AND ");
+    let expression = r#"("name" * "J*")"#.parse::<Expression>().unwrap();
+    interpret_into(
+        &expression,
+        &SqliteRenames::new(),
+        &types,
+        &mut sql,
+        &mut binds,
+    )
+    .unwrap();
+
+    assert_eq!(
+        sql,
+        "SELECT * FROM users WHERE tenant = ? AND (age > ? OR (NOT name IN (?, ?))) AND name LIKE ? ESCAPE '\\'"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Integer(Some(7)),
+            SqliteType::Integer(Some(18)),
+            SqliteType::Text(Some("a".into())),
+            SqliteType::Text(Some("b".into())),
+            SqliteType::Text(Some("J%".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_any_param() {
+    let s = r#"(("status" ? {allowed_statuses}) & ("age" > "18"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("status".into(), SqliteType::Text(None));
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    let mut params = SqliteParams::new();
+    params.insert(
+        "allowed_statuses".into(),
+        vec!["open".into(), "pending".into()],
+    );
+
+    let (clause, binds) = interpret_with_params(&expression, &renames, &types, &params).unwrap();
+    assert_eq!(clause, "(status IN (?, ?) AND age > ?)");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("open".into())),
+            SqliteType::Text(Some("pending".into())),
+            SqliteType::Integer(Some(18)),
+        ]
+    );
+
+    let err = interpret_with_params(&expression, &renames, &types, &SqliteParams::new());
+    assert!(matches!(err, Err(Error::UnresolvedParam(p)) if p == "allowed_statuses"));
+    let err = interpret(&expression, &renames, &types);
+    assert!(matches!(err, Err(Error::UnresolvedParam(_))));
+}
+
+#[test]
+fn test_sqlite_null_equality() {
+    let s = r#"(("status" = "x") | ("age" = "18"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("status".into(), SqliteType::Text(None));
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    let (clause, _) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(status = ? OR age = ?)");
+
+    let mut options = SqliteOptions {
+        null_safe_equal: true,
+        ..Default::default()
+    };
+    let (clause, _) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "(status IS ? OR age IS ?)");
+
+    options.null_defaults.insert("status".into(), "x".into());
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "(COALESCE(status, ?) = ? OR age IS ?)");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("x".into())),
+            SqliteType::Text(Some("x".into())),
+            SqliteType::Integer(Some(18)),
+        ]
+    );
+}
+
+#[cfg(feature = "spans")]
+#[test]
+fn test_sqlite_spans() {
+    use flp_gsp::{Span, SpannedExpression};
+
+    let s = r#"(("age" > "18") & (  "nmae" = "Jack"))"#;
+    let spanned = s.parse::<SpannedExpression>().unwrap();
+    assert_eq!(
+        spanned.spans,
+        vec![Span { start: 2, end: 14 }, Span { start: 21, end: 36 }]
+    );
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    match interpret_spanned(&spanned, &renames, &types) {
+        Err(Error::At(span, err)) => {
+            assert_eq!(&s[span.start..span.end], r#""nmae" = "Jack""#);
+            assert!(matches!(*err, Error::UnknownKey(ref k) if k == "nmae"));
+        }
+        other => panic!("expected spanned error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlite_any_element_errors() {
+    let s = r#"("age" ? ["1", "x", "3", "", "5"])"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    match interpret(&expression, &renames, &types) {
+        Err(Error::AnyElementErrors(errors)) => {
+            assert_eq!(
+                errors.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+                vec![1, 3]
+            );
+        }
+        other => panic!("expected element errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sqlite_btree_map() {
+    use std::collections::BTreeMap;
+
+    let s = r#"(("age" > "18") & ("sex" = "Male"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = BTreeMap::new();
+    renames.insert("sex".to_string(), "gender".to_string());
+    let mut types = BTreeMap::new();
+    types.insert("age".to_string(), SqliteType::Integer(None));
+    types.insert("sex".to_string(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(age > ? AND gender = ?)");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Integer(Some(18)),
+            SqliteType::Text(Some("Male".into()))
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_fuzzy() {
+    let expression = r#"("name" % "Jhon")"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let err = interpret(&expression, &renames, &types);
+    assert!(matches!(err, Err(Error::UnsupportedNode(_))));
+
+    let options = SqliteOptions {
+        spellfix_max_cost: Some(200),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "editdist3(name, ?) < ?");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("Jhon".into())),
+            SqliteType::Integer(Some(200))
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_full_text() {
+    let options = ParseOptions {
+        full_text: true,
+        ..Default::default()
+    };
+    let expression =
+        Expression::parse_with(r#"("laptop" & ("price" < "1000"))"#, &options).unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("price".into(), SqliteType::Integer(None));
+
+    let err = interpret(&expression, &renames, &types);
+    assert!(matches!(err, Err(Error::UnsupportedNode(_))));
+
+    let options = SqliteOptions {
+        default_fts_column: Some("products_fts".into()),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "(products_fts MATCH ? AND price < ?)");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("laptop".into())),
+            SqliteType::Integer(Some(1000))
+        ]
+    );
+}
+
+#[cfg(feature = "datetime")]
+#[test]
+fn test_sqlite_debug() {
+    let s = r#"(("name" = "O'Brien") & (("age" > "18") & (("score" < "1.5") & (("active" = "true") & (("data" = "ab") & (("id" ? ["1", "2"]) & ("born" < "2020-01-02T03:04:05Z")))))))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("score".into(), SqliteType::Real(None));
+    types.insert("active".into(), SqliteType::Boolean(None));
+    types.insert("data".into(), SqliteType::Blob(None));
+    types.insert("id".into(), SqliteType::BigInt(None));
+    types.insert("born".into(), SqliteType::DateTime(None));
+
+    assert_eq!(
+        interpret_debug(&expression, &renames, &types).unwrap(),
+        "(name = 'O''Brien' AND (age > 18 AND (score < 1.5 AND (active = 1 AND (data = X'6162' AND (id IN (1, 2) AND born < '2020-01-02 03:04:05+00:00'))))))"
+    );
+    types.insert("why?".into(), SqliteType::Text(None));
+    let expression = r#"(("why?" = "x") & ("name" = "y"))"#.parse::<Expression>().unwrap();
+    assert_eq!(
+        interpret_debug(&expression, &renames, &types).unwrap(),
+        "(why? = 'x' AND name = 'y')"
+    );
+    assert_eq!(SqliteType::Text(None).to_sql_literal(), "NULL");
+    assert_eq!(SqliteType::Real(Some(f64::NAN)).to_sql_literal(), "NULL");
+}
+
+#[test]
+fn test_sqlite_wildcard_equal() {
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let cases = [
+        (r#"("name" * "Bob")"#, "name = ? COLLATE NOCASE", "Bob"),
+        (r#"("name" * "B*b")"#, r"name LIKE ? ESCAPE '\'", "B%b"),
+        // Literal `%` and `_` need no LIKE.
+        (r#"("name" * "100%")"#, "name = ? COLLATE NOCASE", "100%"),
+        (
+            r#"("name" * "snake_case")"#,
+            "name = ? COLLATE NOCASE",
+            "snake_case",
+        ),
+        (
+            r#"("name" * "100%*")"#,
+            r"name LIKE ? ESCAPE '\'",
+            r"100\%%",
+        ),
+        (
+            r#"("name" * "snake_?\\\\*")"#,
+            r"name LIKE ? ESCAPE '\'",
+            r"snake\__\\%",
+        ),
+        // Escaped wildcards are literal.
+        (
+            r#"("name" * "50\\*off")"#,
+            "name = ? COLLATE NOCASE",
+            "50*off",
+        ),
+        (
+            r#"("name" * "50\\*off*")"#,
+            r"name LIKE ? ESCAPE '\'",
+            "50*off%",
+        ),
+    ];
+    for (s, clause, bind) in cases {
+        let expression = s.parse::<Expression>().unwrap();
+        assert_eq!(
+            interpret(&expression, &renames, &types).unwrap(),
+            (
+                clause.to_string(),
+                vec![SqliteType::Text(Some(bind.into()))]
+            )
+        );
+    }
+}
+
+#[test]
+fn test_sqlite_wildcard_config() {
+    let expression = r#"(("name" * "J_c%") & ("code" * "a*b\\%"))"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("code".into(), SqliteType::Text(None));
+
+    let options = SqliteOptions {
+        wildcard: flp_gsp::wildcard::WildcardConfig::sql(),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    // `*` is literal and `\%` an escaped `%`, so the second pattern has no wildcard.
+    assert_eq!(
+        clause,
+        r"(name LIKE ? ESCAPE '\' AND code = ? COLLATE NOCASE)"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("J_c%".into())),
+            SqliteType::Text(Some("a*b%".into())),
+        ]
+    );
+
+    // With the default config, `*` is the wildcard and `%` and `_` are literal.
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        r"(name = ? COLLATE NOCASE AND code LIKE ? ESCAPE '\')"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("J_c%".into())),
+            SqliteType::Text(Some(r"a%b\\\%".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_collate_nocase() {
+    let expression = r#"(("name" ~ "J_hn") & ("code" * "A*"))"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("code".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        r"(name LIKE ? ESCAPE '\' AND code LIKE ? ESCAPE '\')"
+    );
+    assert!(!clause.contains("COLLATE"));
+    assert_eq!(binds[0], SqliteType::Text(Some(r"J\_hn".into())));
+
+    let options = SqliteOptions {
+        collate_nocase: true,
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    // The target is compared as is, without `LIKE` escapes.
+    assert_eq!(
+        clause,
+        r"(name = ? COLLATE NOCASE AND code LIKE ? ESCAPE '\')"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("J_hn".into())),
+            SqliteType::Text(Some("A%".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_null_inclusive_negation() {
+    let s = r#"((! "name" * "J*") & (! ("age" > "18")))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    let (clause, _) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, r"((NOT name LIKE ? ESCAPE '\') AND (NOT age > ?))");
+
+    let options = SqliteOptions {
+        null_inclusive_negation: true,
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        r"((name NOT LIKE ? ESCAPE '\' OR name IS NULL) AND (NOT age > ?))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("J%".into())),
+            SqliteType::Integer(Some(18))
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_indexed() {
+    let s =
+        r#"((("age" > "18") & ("name" = "Jack")) | (("age" > "18") & ("name" ? ["Joe", "Jack"])))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret_indexed(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "((age > ?1 AND name = ?2) OR (age > ?1 AND name IN (?3, ?2)))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Integer(Some(18)),
+            SqliteType::Text(Some("Jack".into())),
+            SqliteType::Text(Some("Joe".into())),
+        ]
+    );
+
+    // A `?` in a column is not a placeholder.
+    types.insert("why?".into(), SqliteType::Text(None));
+    let expression = r#"(("why?" = "x") & ("name" = "x"))"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret_indexed(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(why? = ?1 AND name = ?1)");
+    assert_eq!(binds, vec![SqliteType::Text(Some("x".into()))]);
+}
+
+#[test]
+fn test_sqlite_named() {
+    let s = r#"((("age" > "18") & ("name" = "Jack")) | (!("age" @ ["1", "18"]) & ("name" ? ["Joe", "Jack"])))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret_named(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "((age > :p0 AND name = :p1) OR ((NOT age BETWEEN :p2 AND :p3) AND name IN (:p4, :p5)))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            (":p0".to_string(), SqliteType::Integer(Some(18))),
+            (":p1".to_string(), SqliteType::Text(Some("Jack".into()))),
+            (":p2".to_string(), SqliteType::Integer(Some(1))),
+            (":p3".to_string(), SqliteType::Integer(Some(18))),
+            (":p4".to_string(), SqliteType::Text(Some("Joe".into()))),
+            (":p5".to_string(), SqliteType::Text(Some("Jack".into()))),
+        ]
+    );
+
+    // A `?` in a column is not a placeholder.
+    types.insert("why?".into(), SqliteType::Text(None));
+    let expression = r#"(("why?" = "y") & ("name" = "b"))"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret_named(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(why? = :p0 AND name = :p1)");
+    assert_eq!(binds.len(), 2);
+}
+
+#[test]
+fn test_sqlite_empty_any() {
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+
+    // An empty list is always false, without looking up the key, as in `evaluate`.
+    for s in [r#"("name" ? [])"#, r#"("missing" ? [])"#] {
+        let expression = s.parse::<Expression>().unwrap();
+        let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+        assert_eq!(clause, "FALSE");
+        assert!(binds.is_empty());
+    }
+}
+
+#[test]
+fn test_sqlite_keyed() {
+    let s = r#"((("age" > "18") & ("name" ~ "jack")) | (!("age" @ ["1", "18"]) & ("city" ? ["Oslo", "Rome"])))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = SqliteRenames::new();
+    renames.insert("city".into(), "town".into());
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("city".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret_keyed(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, interpret(&expression, &renames, &types).unwrap().0);
+    // Binds are attributed to keys rather than renamed columns.
+    assert_eq!(
+        binds,
+        vec![
+            ("age".to_string(), SqliteType::Integer(Some(18))),
+            ("name".to_string(), SqliteType::Text(Some("jack".into()))),
+            ("age".to_string(), SqliteType::Integer(Some(1))),
+            ("age".to_string(), SqliteType::Integer(Some(18))),
+            ("city".to_string(), SqliteType::Text(Some("Oslo".into()))),
+            ("city".to_string(), SqliteType::Text(Some("Rome".into()))),
+        ]
+    );
+
+    // Negations writing their own binds keep their keys.
+    let s = r#"((! ("name" * "J*")) & ((! "age" -) | ("code" > "7")))"#;
+    let expression = s.parse::<Expression>().unwrap();
+    types.insert("code".into(), SqliteType::Integer(None));
+    let mut options = SqliteOptions {
+        null_inclusive_negation: true,
+        ..Default::default()
+    };
+    options.null_sentinels.insert("age".into(), "0".into());
+    let (clause, binds) =
+        interpret_keyed_with_options(&expression, &renames, &types, &options).unwrap();
+    assert_eq!(
+        clause,
+        "((name NOT LIKE ? ESCAPE '\\' OR name IS NULL) AND ((age IS NOT NULL AND age <> ?) OR code > ?))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            ("name".to_string(), SqliteType::Text(Some("J%".into()))),
+            ("age".to_string(), SqliteType::Integer(Some(0))),
+            ("code".to_string(), SqliteType::Integer(Some(7))),
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_date_functions() {
+    let s = r#"(("created" > "2024-01-01") & (("updated" < "2024-06-30") & ("age" > "18")))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let mut renames = SqliteRenames::new();
+    renames.insert("created".into(), "t.created_at".into());
+    let mut types = SqliteTypes::new();
+    types.insert("created".into(), SqliteType::Text(None));
+    types.insert("updated".into(), SqliteType::Text(None));
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    let mut options = SqliteOptions::default();
+    options
+        .date_functions
+        .insert("created".into(), SqliteDateFunction::DateTime);
+    options
+        .date_functions
+        .insert("updated".into(), SqliteDateFunction::Date);
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        "(datetime(t.created_at) > datetime(?) AND (date(updated) < date(?) AND age > ?))"
+    );
+    assert_eq!(binds[0], SqliteType::Text(Some("2024-01-01".into())));
+}
+
+#[test]
+fn test_sqlite_or_equal() {
+    let expression = r#"(("age" >= "18") & ("age" <= "30"))"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(age >= ? AND age <= ?)");
+    assert_eq!(
+        binds,
+        vec![SqliteType::Integer(Some(18)), SqliteType::Integer(Some(30))]
+    );
+}
+
+#[test]
+fn test_sqlite_not_equal() {
+    let expression = r#"("status" != "active")"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("status".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "status <> ?");
+    assert_eq!(binds, vec![SqliteType::Text(Some("active".into()))]);
+
+    let options = SqliteOptions {
+        null_safe_equal: true,
+        ..Default::default()
+    };
+    let (clause, _) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "status IS NOT ?");
+}
+
+#[test]
+fn test_sqlite_null_sentinels() {
+    let expression = r#"(("score" -) | ("name" -))"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("score".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let options = SqliteOptions {
+        null_sentinels: [("score".into(), "-1".into())].into(),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "((score IS NULL OR score = ?) OR name IS NULL)");
+    assert_eq!(binds, vec![SqliteType::Integer(Some(-1))]);
+}
+
+#[test]
+fn test_sqlite_json_columns() {
+    let expression = r#"((("address.city" = "NYC") & ("address.zip code" > "10000")) & (("tags.0" = "a") | ("a.b" = "c")))"#
+        .parse::<Expression>()
+        .unwrap();
+
+    let mut renames = SqliteRenames::new();
+    renames.insert("address".into(), "user.address".into());
+    let mut types = SqliteTypes::new();
+    types.insert("address.city".into(), SqliteType::Text(None));
+    types.insert("address.zip code".into(), SqliteType::Integer(None));
+    types.insert("tags.0".into(), SqliteType::Text(None));
+    types.insert("a.b".into(), SqliteType::Text(None));
+
+    let options = SqliteOptions {
+        json_columns: ["address".into(), "tags".into()].into(),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        r#"((json_extract(user.address, '$.city') = ? AND json_extract(user.address, '$."zip code"') > ?) AND (json_extract(tags, '$[0]') = ? OR a.b = ?))"#
+    );
+    assert_eq!(binds.len(), 4);
+
+    // Without `json_columns`, a dotted key is a plain column.
+    let (clause, _) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "((address.city = ? AND address.zip code > ?) AND (tags.0 = ? OR a.b = ?))"
+    );
+
+    // With `/` as the delimiter, dots are part of the segments, and `\/` is a literal `/`.
+    let expression = r#"(("address/zip.code" = "1") & ("address/a\\/b" = "2"))"#
+        .parse::<Expression>()
+        .unwrap();
+    types.insert("address/zip.code".into(), SqliteType::Integer(None));
+    types.insert(r"address/a\/b".into(), SqliteType::Integer(None));
+    let options = SqliteOptions {
+        json_columns: ["address".into()].into(),
+        path_delimiter: Some('/'),
+        ..Default::default()
+    };
+    let (clause, _) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        r#"(json_extract(user.address, '$."zip.code"') = ? AND json_extract(user.address, '$."a/b"') = ?)"#
+    );
+}
+
+#[test]
+fn test_sqlite_not_null() {
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("foo".into(), SqliteType::Text(None));
+    types.insert("score".into(), SqliteType::Integer(None));
+
+    let expression = r#"(! "foo" -)"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "foo IS NOT NULL");
+    assert!(binds.is_empty());
+
+    // Only a negated null check itself is rewritten.
+    let expression = r#"!(! "foo" -)"#.parse::<Expression>().unwrap();
+    let (clause, _) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(NOT foo IS NOT NULL)");
+
+    let options = SqliteOptions {
+        null_sentinels: [("score".into(), "-1".into())].into(),
+        ..Default::default()
+    };
+    let expression = r#"(! "score" -)"#.parse::<Expression>().unwrap();
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(clause, "(score IS NOT NULL AND score <> ?)");
+    assert_eq!(binds, vec![SqliteType::Integer(Some(-1))]);
+}
+
+#[test]
+fn test_sqlite_geo_within() {
+    let expression = r#"("location" @ "0,10,111.19508km")"#.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let types = SqliteTypes::new();
+    let options = SqliteOptions {
+        geo_columns: [("location".into(), ("lat".into(), "lon".into()))].into(),
+        ..Default::default()
+    };
+    let (clause, binds) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        "(lat BETWEEN ? AND ? AND lon BETWEEN ? AND ? AND (lat - ?) * (lat - ?) + (lon - ?) * (lon - ?) * ? <= ?)"
+    );
+    let binds = binds
+        .into_iter()
+        .map(|bind| match bind {
+            SqliteType::Real(Some(value)) => (value * 1000.0).round() / 1000.0,
+            bind => panic!("unexpected bind {:?}", bind),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        binds,
+        vec![-1.0, 1.0, 9.0, 11.0, 0.0, 0.0, 10.0, 10.0, 1.0, 1.0]
+    );
+
+    assert!(interpret(&expression, &renames, &types).is_err());
+}
+
+#[cfg(feature = "datetime")]
+#[test]
+fn test_sqlite_check() {
+    let mut renames = SqliteRenames::new();
+    renames.insert("age".into(), "person.age".into());
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("score".into(), SqliteType::Real(None));
+    types.insert("active".into(), SqliteType::Boolean(None));
+    types.insert("data".into(), SqliteType::Blob(None));
+    types.insert("created".into(), SqliteType::DateTime(None));
+
+    let check = |s: &str| interpret_check(&s.parse::<Expression>().unwrap(), &renames, &types);
+    for (query, expected) in [
+        (r#"("name" = "it's")"#, "name = 'it''s'"),
+        (r#"("name" != "Bob")"#, "name <> 'Bob'"),
+        (r#"("name" ~ "bob")"#, r"name LIKE 'bob' ESCAPE '\'"),
+        (r#"("age" > "18")"#, "person.age > 18"),
+        (r#"("score" < "1.5")"#, "score < 1.5"),
+        (r#"("age" >= "18")"#, "person.age >= 18"),
+        (r#"("age" <= "65")"#, "person.age <= 65"),
+        (r#"("name" * "J?c*")"#, r"name LIKE 'J_c%' ESCAPE '\'"),
+        (r#"("name" * "Jack")"#, "name = 'Jack' COLLATE NOCASE"),
+        (r#"("name" $ "^J")"#, "name REGEXP '^J'"),
+        (r#"("age" ? ["1", "2"])"#, "person.age IN (1, 2)"),
+        (r#"("age" ? [])"#, "FALSE"),
+        (r#"("age" -)"#, "person.age IS NULL"),
+        (r#"("active" = "true")"#, "active = 1"),
+        (r#"("data" = "ab")"#, "data = X'6162'"),
+        (
+            r#"("created" > "2024-01-02T03:04:05Z")"#,
+            "created > '2024-01-02 03:04:05+00:00'",
+        ),
+        (
+            r#"(!("age" < "18") & ("name" = "a" | "name" = "b"))"#,
+            "((NOT person.age < 18) AND (name = 'a' OR name = 'b'))",
+        ),
+    ] {
+        assert_eq!(check(query).unwrap(), expected, "{}", query);
+    }
+
+    assert!(check(r#"("age" = "x")"#).is_err());
+    assert!(check(r#"("age" ? {ages})"#).is_err());
+    assert!(check(r#"("name" % "Jack")"#).is_err());
+    assert!(interpret_check(&Expression::full_text("laptop"), &renames, &types).is_err());
+}
+
+#[cfg(any(feature = "regex", feature = "fancy-regex"))]
+#[test]
+fn test_sqlite_regexp() {
+    let s = r#"(("name" $ "^J.c?k$") & (! "name" $ "^Bob"))"#;
+    let expression = s.parse::<Expression>().unwrap();
+
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(clause, "(name REGEXP ? AND (NOT name REGEXP ?))");
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("^J.c?k$".into())),
+            SqliteType::Text(Some("^Bob".into()))
+        ]
+    );
+
+    let options = SqliteOptions {
+        null_inclusive_negation: true,
+        ..Default::default()
+    };
+    let (clause, _) = interpret_with_options(
+        &expression,
+        &renames,
+        &types,
+        &SqliteParams::new(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        clause,
+        "(name REGEXP ? AND (name NOT REGEXP ? OR name IS NULL))"
+    );
+}
+
+#[test]
+fn test_sqlite_equal_ci_escape() {
+    let renames = SqliteRenames::new();
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let cases = [
+        (r#"("name" ~ "Bob")"#, "Bob"),
+        (r#"("name" ~ "50%")"#, r"50\%"),
+        (r#"("name" ~ "a_b")"#, r"a\_b"),
+        (r#"("name" ~ "back\\slash")"#, r"back\\slash"),
+        (r#"("name" ~ "*?")"#, "*?"),
+    ];
+    for (s, bind) in cases {
+        let expression = s.parse::<Expression>().unwrap();
+        assert_eq!(
+            interpret(&expression, &renames, &types).unwrap(),
+            (
+                r"name LIKE ? ESCAPE '\'".to_string(),
+                vec![SqliteType::Text(Some(bind.into()))]
+            )
+        );
+    }
+}
+
+#[test]
+fn test_sqlite_between() {
+    let expression = r#"(("age" @ ["18", "65"]) & !("name" @ ["a", "m"]))"#
+        .parse::<Expression>()
+        .unwrap();
+
+    let mut types = SqliteTypes::new();
+    types.insert("age".into(), SqliteType::Integer(None));
+    types.insert("name".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &SqliteRenames::new(), &types).unwrap();
+    assert_eq!(
+        clause,
+        "(age BETWEEN ? AND ? AND (NOT name BETWEEN ? AND ?))"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Integer(Some(18)),
+            SqliteType::Integer(Some(65)),
+            SqliteType::Text(Some("a".into())),
+            SqliteType::Text(Some("m".into())),
+        ]
+    );
+
+    let expression = r#"("age" @ ["18", "old"])"#.parse::<Expression>().unwrap();
+    assert!(interpret(&expression, &SqliteRenames::new(), &types).is_err());
+}
+
+#[test]
+fn test_sqlite_intersects() {
+    let expression = r#"(("tags" ^ ["a", "50%"]) | ("tags" ^ []))"#.parse::<Expression>().unwrap();
+
+    let mut types = SqliteTypes::new();
+    types.insert("tags".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &SqliteRenames::new(), &types).unwrap();
+    assert_eq!(
+        clause,
+        r"((',' || tags || ',' LIKE ? ESCAPE '\' OR ',' || tags || ',' LIKE ? ESCAPE '\') OR FALSE)"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::Text(Some("%,a,%".into())),
+            SqliteType::Text(Some(r"%,50\%,%".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_sqlite_length() {
+    let expression = r#"((("name" # > "10") & ("name" # < "20")) | ("code" # = "3"))"#
+        .parse::<Expression>()
+        .unwrap();
+
+    let mut renames = SqliteRenames::new();
+    renames.insert("code".into(), "country.code".into());
+    let mut types = SqliteTypes::new();
+    types.insert("name".into(), SqliteType::Text(None));
+    types.insert("code".into(), SqliteType::Text(None));
+
+    let (clause, binds) = interpret(&expression, &renames, &types).unwrap();
+    assert_eq!(
+        clause,
+        "((LENGTH(name) > ? AND LENGTH(name) < ?) OR LENGTH(country.code) = ?)"
+    );
+    assert_eq!(
+        binds,
+        vec![
+            SqliteType::BigInt(Some(10)),
+            SqliteType::BigInt(Some(20)),
+            SqliteType::BigInt(Some(3))
+        ]
+    );
+
+    let expression = r#"("missing" # > "1")"#.parse::<Expression>().unwrap();
+    assert!(matches!(
+        interpret(&expression, &renames, &types),
+        Err(Error::UnknownKey(_))
+    ));
 }
@@ -0,0 +1,50 @@
+use flp_gsp::wildcard::*;
+
+#[test]
+fn test_is_match() {
+    let cases = [
+        ("J?c*", "Jack", true),
+        ("J?c*", "Jc", false),
+        ("*", "", true),
+        ("a*b*c", "aXbYbZc", true),
+        ("a*b*c", "aXbYbZ", false),
+        (r"50\*off", "50*off", true),
+        (r"50\*off", "50Xoff", false),
+        (r"what\?", "what?", true),
+        (r"what\?", "whats", false),
+        (r"C:\\dir\*", r"C:\dir*", true),
+        (r"C:\dir", r"C:\dir", true),
+    ];
+    for (pattern, value, expected) in cases {
+        assert_eq!(is_match(pattern, value), expected, "{} {}", pattern, value);
+    }
+}
+
+#[test]
+fn test_literal_and_like() {
+    assert_eq!(literal(r"50\*off"), Some("50*off".into()));
+    assert_eq!(literal("50*off"), None);
+    assert_eq!(to_like(r"100%_\*?*"), r"100\%\_*_%");
+    assert_eq!(to_like(r"C:\\dir"), r"C:\\dir");
+}
+
+#[test]
+fn test_config_translate() {
+    let sql = WildcardConfig::sql();
+    assert_eq!(sql.translate("J_c%"), "J?c*");
+    assert_eq!(sql.translate(r"100\%*?"), r"100%\*\?");
+    assert_eq!(sql.translate(r"C:\\dir\x"), r"C:\\dir\\x");
+    assert!(is_match(&sql.translate("J_c%"), "Jack"));
+    assert!(!is_match(&sql.translate(r"50\%"), "50 percent"));
+
+    let default = WildcardConfig::default();
+    assert_eq!(default.translate(r"J?c*\*"), r"J?c*\*");
+    assert_eq!(default.tokens("J?c*"), tokens("J?c*"));
+
+    // Without a single-character wildcard, `?` is literal.
+    let any_many_only = WildcardConfig {
+        any_one: None,
+        ..Default::default()
+    };
+    assert_eq!(any_many_only.translate("what?*"), r"what\?*");
+}